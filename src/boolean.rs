@@ -0,0 +1,261 @@
+// Boolean operations on the areas filled by paths. Both paths are flattened and every edge
+// is split where it meets another one. Each piece is then kept if the result is filled on
+// one side of it but not the other, and the kept pieces are linked up into contours.
+
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use crate::path_builder::{distance_to_segment, segment_intersection, Path, PathBuilder, Winding};
+use crate::{Point, Vector};
+
+/* The edges of the polygons that `path` is flattened into using `tolerance`. Every
+ * subpath is treated as closed, like when filling, and zero length edges are left out. */
+fn polygon_edges(path: &Path, tolerance: f32) -> Vec<(Point, Point)> {
+    let mut edges = Vec::new();
+    for (points, _) in path.flattened_subpaths(tolerance) {
+        for (i, &p) in points.iter().enumerate() {
+            let q = points[(i + 1) % points.len()];
+            if p != q {
+                edges.push((p, q));
+            }
+        }
+    }
+    edges
+}
+
+/* The winding number of `edges` around `p`, which shouldn't be on any of them. Edges
+ * include their lower end and exclude their upper end so that a ray through a vertex
+ * counts it once. */
+fn winding_number(edges: &[(Point, Point)], p: Point) -> i32 {
+    let mut winding = 0;
+    for &(a, b) in edges {
+        if (a.y <= p.y) != (b.y <= p.y) {
+            let x = a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x);
+            if x > p.x {
+                winding += if b.y > a.y { 1 } else { -1 };
+            }
+        }
+    }
+    winding
+}
+
+fn is_inside(winding: Winding, count: i32) -> bool {
+    match winding {
+        Winding::EvenOdd => count & 1 != 0,
+        Winding::NonZero => count != 0,
+    }
+}
+
+type PointKey = (u32, u32);
+
+fn point_key(p: Point) -> PointKey {
+    (p.x.to_bits(), p.y.to_bits())
+}
+
+/* Replaces points by the first point seen within `epsilon` of them, so that an intersection
+ * that lands a rounding error away from a vertex becomes that vertex. */
+struct PointMerger {
+    epsilon: f32,
+    cells: HashMap<(i64, i64), Vec<Point>>,
+}
+
+impl PointMerger {
+    fn merge(&mut self, p: Point) -> Point {
+        let cell = ((p.x / self.epsilon).floor() as i64, (p.y / self.epsilon).floor() as i64);
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(points) = self.cells.get(&(cell.0 + dx, cell.1 + dy)) {
+                    if let Some(&q) = points.iter().find(|q| (**q - p).length() <= self.epsilon) {
+                        return q;
+                    }
+                }
+            }
+        }
+        self.cells.entry(cell).or_default().push(p);
+        p
+    }
+}
+
+/* Returns the outline of the area where `keep` is true given whether a point is inside
+ * `a` and whether it's inside `b`. Outlines have positive areas, like `PathBuilder::rect`,
+ * and holes negative ones so the result is meant to be filled with `Winding::NonZero`. */
+pub(crate) fn path_boolean(a: &Path, b: &Path, tolerance: f32, keep: &dyn Fn(bool, bool) -> bool) -> Path {
+    let (edges_a, edges_b) = (polygon_edges(a, tolerance), polygon_edges(b, tolerance));
+    let edges: Vec<(Point, Point)> = edges_a.iter().chain(&edges_b).cloned().collect();
+
+    let scale = edges.iter().fold(0f32, |scale, (p, q)| scale.max(p.x.abs()).max(p.y.abs()).max(q.x.abs()).max(q.y.abs()));
+    let mut merger = PointMerger { epsilon: (tolerance * 1e-3).max(scale * 1e-6), cells: HashMap::new() };
+
+    // split every edge at its ends and everywhere it meets another edge
+    let mut splits: Vec<Vec<Point>> = edges.iter().map(|&(p, q)| vec![merger.merge(p), merger.merge(q)]).collect();
+    for i in 0..edges.len() {
+        let (p0, p1) = edges[i];
+        for j in i + 1..edges.len() {
+            let (q0, q1) = edges[j];
+            if p0.x.max(p1.x) < q0.x.min(q1.x) || q0.x.max(q1.x) < p0.x.min(p1.x)
+                || p0.y.max(p1.y) < q0.y.min(q1.y) || q0.y.max(q1.y) < p0.y.min(p1.y) {
+                continue;
+            }
+            segment_intersection(p0, p1, q0, q1, &mut |x| {
+                let x = merger.merge(x);
+                splits[i].push(x);
+                splits[j].push(x);
+            });
+        }
+    }
+
+    // the pieces between the splits, without the duplicates where edges overlap
+    let mut pieces: HashMap<(PointKey, PointKey), (Point, Point)> = HashMap::new();
+    for (&(p, q), points) in edges.iter().zip(&mut splits) {
+        let d = q - p;
+        points.sort_by(|u, v| (*u - p).dot(d).partial_cmp(&(*v - p).dot(d)).unwrap_or(Ordering::Equal));
+        points.dedup();
+        for pair in points.windows(2) {
+            let (u, v) = (pair[0], pair[1]);
+            let (u, v) = if (u.x, u.y) < (v.x, v.y) { (u, v) } else { (v, u) };
+            pieces.insert((point_key(u), point_key(v)), (u, v));
+        }
+    }
+    let mut pieces: Vec<(Point, Point)> = pieces.into_values().collect();
+    // so that the output doesn't depend on the order of the hash map
+    pieces.sort_by(|p, q| (p.0.x, p.0.y, p.1.x, p.1.y).partial_cmp(&(q.0.x, q.0.y, q.1.x, q.1.y)).unwrap_or(Ordering::Equal));
+
+    // keep the pieces with the result on one side of them, directed with it on their left
+    // when y points up, like the edges of `PathBuilder::rect`
+    let mut kept: Vec<(Point, Point)> = Vec::new();
+    for (i, &(p, q)) in pieces.iter().enumerate() {
+        let d = q - p;
+        let length = d.length();
+        let middle = p.lerp(q, 0.5);
+        // sample either side of the piece closer to it than to anything else
+        let nearest = pieces.iter().enumerate()
+            .filter(|&(j, _)| j != i)
+            .map(|(_, &(u, v))| distance_to_segment(middle, u, v))
+            .fold(f32::INFINITY, f32::min);
+        let offset = (length / 4.).min(nearest / 2.);
+        if offset.is_nan() || offset <= 0. {
+            continue;
+        }
+        let normal = Vector::new(-d.y, d.x) / length;
+        let filled = |p: Point| {
+            let in_a = is_inside(a.winding, winding_number(&edges_a, p));
+            let in_b = is_inside(b.winding, winding_number(&edges_b, p));
+            keep(in_a, in_b)
+        };
+        match (filled(middle + normal * offset), filled(middle - normal * offset)) {
+            (true, false) => kept.push((p, q)),
+            (false, true) => kept.push((q, p)),
+            _ => {}
+        }
+    }
+
+    link_contours(&kept)
+}
+
+/* Returns the runs of `polylines` that are inside the area filled by `clip`. Pieces that
+ * run along the outline of `clip` count as inside. */
+pub(crate) fn clip_polylines(polylines: &[Vec<Point>], clip: &Path, tolerance: f32) -> Vec<Vec<Point>> {
+    let edges = polygon_edges(clip, tolerance);
+    let epsilon = tolerance * 1e-3;
+    let inside = |p: Point| {
+        edges.iter().any(|&(u, v)| distance_to_segment(p, u, v) <= epsilon)
+            || is_inside(clip.winding, winding_number(&edges, p))
+    };
+
+    let mut runs = Vec::new();
+    for points in polylines {
+        let mut run: Vec<Point> = Vec::new();
+        for pair in points.windows(2) {
+            let (p, q) = (pair[0], pair[1]);
+            let d = q - p;
+            let mut splits = vec![p, q];
+            for &(u, v) in &edges {
+                segment_intersection(p, q, u, v, &mut |x| splits.push(x));
+            }
+            splits.sort_by(|u, v| (*u - p).dot(d).partial_cmp(&(*v - p).dot(d)).unwrap_or(Ordering::Equal));
+            splits.dedup();
+            for piece in splits.windows(2) {
+                let (u, v) = (piece[0], piece[1]);
+                if inside(u.lerp(v, 0.5)) {
+                    if run.last() != Some(&u) {
+                        if run.len() > 1 {
+                            runs.push(std::mem::take(&mut run));
+                        }
+                        run.clear();
+                        run.push(u);
+                    }
+                    run.push(v);
+                } else if run.len() > 1 {
+                    runs.push(std::mem::take(&mut run));
+                } else {
+                    run.clear();
+                }
+            }
+        }
+        if run.len() > 1 {
+            runs.push(run);
+        }
+    }
+    runs
+}
+
+/* Links up directed edges into closed contours. Where several edges leave a point the
+ * one that turns most towards the filled side is taken, so that shapes that only touch
+ * at a corner stay separate. */
+fn link_contours(edges: &[(Point, Point)]) -> Path {
+    let mut leaving: HashMap<PointKey, Vec<usize>> = HashMap::new();
+    for (i, &(p, _)) in edges.iter().enumerate() {
+        leaving.entry(point_key(p)).or_default().push(i);
+    }
+    let mut used = vec![false; edges.len()];
+    let mut pb = PathBuilder::new();
+    for first in 0..edges.len() {
+        if used[first] {
+            continue;
+        }
+        let start = edges[first].0;
+        let mut points = vec![start];
+        let mut cur = first;
+        loop {
+            used[cur] = true;
+            let (from, to) = edges[cur];
+            if to == start {
+                break;
+            }
+            points.push(to);
+            let d = to - from;
+            let next = leaving.get(&point_key(to)).and_then(|candidates| {
+                candidates.iter().cloned().filter(|&e| !used[e]).max_by(|&e1, &e2| {
+                    let turn = |e: usize| {
+                        let out = edges[e].1 - edges[e].0;
+                        d.cross(out).atan2(d.dot(out))
+                    };
+                    turn(e1).partial_cmp(&turn(e2)).unwrap_or(Ordering::Equal)
+                })
+            });
+            match next {
+                Some(next) => cur = next,
+                None => break,
+            }
+        }
+
+        // drop the points that the splitting left in the middle of straight lines
+        let mut simplified: Vec<Point> = Vec::with_capacity(points.len());
+        for i in 0..points.len() {
+            let (prev, pt, next) = (points[(i + points.len() - 1) % points.len()], points[i], points[(i + 1) % points.len()]);
+            let (d1, d2) = (pt - prev, next - pt);
+            if d1.cross(d2).abs() > 1e-6 * d1.length() * d2.length() || d1.dot(d2) < 0. {
+                simplified.push(pt);
+            }
+        }
+        if simplified.len() < 3 {
+            continue;
+        }
+        pb.move_to(simplified[0].x, simplified[0].y);
+        for p in &simplified[1..] {
+            pb.line_to(p.x, p.y);
+        }
+        pb.close();
+    }
+    pb.finish()
+}
@@ -12,6 +12,12 @@ struct DashState {
 }
 
 pub fn dash_path(path: &Path, dash_array: &[f32], mut dash_offset: f32) -> Path {
+    // A pattern made up of only zero length dashes would never make progress.
+    // Treat it like no dashing at all and draw a solid line.
+    if dash_array.iter().all(|dash| *dash == 0.) {
+        return path.clone();
+    }
+
     let mut dashed = PathBuilder::new();
 
     let mut cur_pt = None;
@@ -204,3 +210,114 @@ pub fn dash_path(path: &Path, dash_array: &[f32], mut dash_offset: f32) -> Path
     }
     dashed.finish()
 }
+
+/// Like `dash_path` but moves both ends of every dash by `extend` along the path,
+/// lengthening the dashes into the gaps when it's positive and shortening them
+/// when it's negative. The period of the pattern doesn't change: dashes shrink
+/// to a point at their middle at most, and grow until they meet the dash next to
+/// them at most, sharing the gap between them evenly. Dashes that shrink to
+/// nothing are dropped instead of being left as points, which the stroker would
+/// draw as dots, and their gaps merge with the gap before them.
+pub fn dash_path_extended(path: &Path, dash_array: &[f32], dash_offset: f32, extend: f32) -> Path {
+    let mut dashes = dash_array.to_vec();
+    if dashes.len() % 2 == 1 {
+        // an odd pattern only alternates between on and off over two periods
+        dashes.extend_from_slice(dash_array);
+    }
+    let original = dashes.clone();
+    let n = dashes.len() / 2;
+    // how much each dash grows at its start and at its end
+    let (starts, ends): (Vec<f32>, Vec<f32>) = if extend < 0. {
+        (0..n).map(|i| {
+            let shrink = (-extend).min(dashes[2 * i] / 2.);
+            (-shrink, -shrink)
+        }).unzip()
+    } else {
+        let grow = |i: usize| extend.min(dashes[2 * i + 1] / 2.);
+        (0..n).map(|i| (grow((i + n - 1) % n), grow(i))).unzip()
+    };
+    for i in 0..n {
+        dashes[2 * i] += starts[i] + ends[i];
+        dashes[2 * i + 1] -= ends[i] + starts[(i + 1) % n];
+    }
+    // Zero length dashes that were asked for are kept, only the ones that
+    // shrank away are dropped.
+    let mut pattern: Vec<f32> = Vec::new();
+    // the length of the dropped dashes and gaps before the first dash that's kept
+    let mut skipped = 0.;
+    for i in 0..n {
+        if dashes[2 * i] <= 0. && original[2 * i] > 0. {
+            match pattern.last_mut() {
+                Some(gap) => *gap += dashes[2 * i + 1],
+                None => skipped += dashes[2 * i + 1],
+            }
+        } else {
+            pattern.push(dashes[2 * i]);
+            pattern.push(dashes[2 * i + 1]);
+        }
+    }
+    match pattern.last_mut() {
+        Some(gap) => *gap += skipped,
+        None => return PathBuilder::new().finish(),
+    }
+    // the first dash now starts at `-starts[0]` in the old pattern and the
+    // first one that's kept at `skipped - starts[0]`
+    dash_path(path, &pattern, dash_offset + starts[0] - skipped)
+}
+
+/// Dashes each subpath of `path` with exactly `count` dashes spread evenly along
+/// it. Each dash covers `duty_cycle` of the distance from its start to the start
+/// of the next one and the first dash begins at the start of the subpath. Because
+/// the dash lengths come from the length of the subpath, the last gap ends exactly
+/// where the first dash starts and closed subpaths don't get a partial dash at the
+/// seam.
+pub fn dash_path_evenly(path: &Path, count: usize, duty_cycle: f32) -> Path {
+    let mut dashed = PathBuilder::new();
+    if count == 0 {
+        return dashed.finish();
+    }
+    let duty_cycle = duty_cycle.clamp(0., 1.);
+
+    for range in path.subpath_ranges() {
+        let subpath = Path { ops: path.ops[range].to_vec(), winding: path.winding };
+        let segments = subpath.line_segments();
+        let total: f32 = segments.iter().map(|(p0, p1)| (*p1 - *p0).length()).sum();
+        if segments.is_empty() || total <= 0. {
+            continue;
+        }
+
+        let period = total / count as f32;
+        // the segment that we're in and the distance along the subpath to its start
+        let mut i = 0;
+        let mut walked = 0.;
+        let mut point_at = |distance: f32, dashed: &mut PathBuilder, start: bool| {
+            loop {
+                let (p0, p1) = segments[i];
+                let length = (p1 - p0).length();
+                if distance <= walked + length || i == segments.len() - 1 {
+                    let t = ((distance - walked) / length).clamp(0., 1.);
+                    let pt = p0.lerp(p1, t);
+                    if start {
+                        dashed.move_to(pt.x, pt.y);
+                    } else {
+                        dashed.line_to(pt.x, pt.y);
+                    }
+                    return;
+                }
+                if !start {
+                    // the dash continues around the corner
+                    dashed.line_to(p1.x, p1.y);
+                }
+                walked += length;
+                i += 1;
+            }
+        };
+        for k in 0..count {
+            // computed from `k` instead of accumulated so that the dashes don't drift
+            let start = period * k as f32;
+            point_at(start, &mut dashed, true);
+            point_at(start + period * duty_cycle, &mut dashed, false);
+        }
+    }
+    dashed.finish()
+}
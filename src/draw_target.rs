@@ -678,12 +678,20 @@ impl<Backing : AsRef<[u32]> + AsMut<[u32]>> DrawTarget<Backing> {
         // alternative would be to use transform specific flattening but I haven't seen that done
         // anywhere.
         let tolerance = scaled_tolerance(tolerance, &self.transform);
-        let mut path = path.flatten(tolerance);
+        let flattened;
+        let mut path = if path.has_curves() {
+            flattened = path.flatten(tolerance);
+            &flattened
+        } else {
+            path
+        };
 
+        let dashed;
         if !style.dash_array.is_empty() {
-            path = dash_path(&path, &style.dash_array, style.dash_offset);
+            dashed = dash_path(path, &style.dash_array, style.dash_offset);
+            path = &dashed;
         }
-        let stroked = stroke_to_path(&path, style);
+        let stroked = stroke_to_path(path, style);
         self.fill(&stroked, src, options);
     }
 
@@ -99,6 +99,7 @@ Produces:
 #![warn(missing_copy_implementations)]
 
 mod blitter;
+mod boolean;
 mod dash;
 mod draw_target;
 mod geom;
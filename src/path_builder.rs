@@ -1,9 +1,17 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::ops::Range;
+
+use euclid::default::Box2D;
 use lyon_geom::Angle;
 use lyon_geom::Arc;
 use lyon_geom::CubicBezierSegment;
 use lyon_geom::QuadraticBezierSegment;
 
-use crate::{Point, Transform, Vector};
+use crate::boolean::{clip_polylines, path_boolean};
+use crate::dash::{dash_path, dash_path_evenly, dash_path_extended};
+use crate::stroke::{arc_points, polygon_area, ribbon_to_path};
+use crate::{DashCapFit, LineCap, LineJoin, Point, StrokeStyle, Transform, Vector};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum Winding {
@@ -11,7 +19,7 @@ pub enum Winding {
     NonZero,
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 pub enum PathOp {
     MoveTo(Point),
     LineTo(Point),
@@ -37,10 +45,99 @@ impl PathOp {
             PathOp::Close => PathOp::Close,
         }
     }
+
+    /// Returns false if any coordinate of `self` is NaN or infinite
+    pub(crate) fn is_finite(&self) -> bool {
+        let finite = |p: &Point| p.x.is_finite() && p.y.is_finite();
+        match self {
+            PathOp::MoveTo(p) | PathOp::LineTo(p) => finite(p),
+            PathOp::QuadTo(c, p) => finite(c) && finite(p),
+            PathOp::CubicTo(c1, c2, p) => finite(c1) && finite(c2) && finite(p),
+            PathOp::Close => true,
+        }
+    }
+
+    fn map_coords<F: Fn(Point) -> Point>(self, f: &F) -> PathOp {
+        match self {
+            PathOp::MoveTo(p) => PathOp::MoveTo(f(p)),
+            PathOp::LineTo(p) => PathOp::LineTo(f(p)),
+            PathOp::QuadTo(p1, p2) => PathOp::QuadTo(f(p1), f(p2)),
+            PathOp::CubicTo(p1, p2, p3) => PathOp::CubicTo(f(p1), f(p2), f(p3)),
+            PathOp::Close => PathOp::Close,
+        }
+    }
+}
+
+/// A vector of length one, used for directions like tangents and normals so
+/// that they can't be confused with vectors of any other length. It derefs
+/// to the `Vector` it wraps.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct UnitVector(Vector);
+
+impl UnitVector {
+    /// Returns `v` scaled to a length of one, or `None` if it has no
+    /// direction or isn't finite.
+    pub fn new(v: Vector) -> Option<UnitVector> {
+        let length = v.length();
+        if length > 0. && length.is_finite() {
+            Some(UnitVector(v / length))
+        } else {
+            None
+        }
+    }
+
+    /// Wraps `v`, which the caller has already made unit length
+    pub(crate) fn new_unchecked(v: Vector) -> UnitVector {
+        UnitVector(v)
+    }
+
+    /// Returns the wrapped vector
+    pub fn get(self) -> Vector {
+        self.0
+    }
+
+    /// Returns `self` rotated 90 degrees, counterclockwise when y points up.
+    /// This turns the direction of travel into the normal on its left.
+    pub fn perp(self) -> UnitVector {
+        UnitVector(Vector::new(-self.0.y, self.0.x))
+    }
+
+    /// Returns `self` rotated 90 degrees the other way from `perp`, which
+    /// turns a normal back into the direction of travel.
+    pub fn unperp(self) -> UnitVector {
+        UnitVector(Vector::new(self.0.y, -self.0.x))
+    }
+
+    /// Returns the direction opposite to `self`
+    pub fn flip(self) -> UnitVector {
+        UnitVector(-self.0)
+    }
+}
+
+impl std::ops::Deref for UnitVector {
+    type Target = Vector;
+
+    fn deref(&self) -> &Vector {
+        &self.0
+    }
+}
+
+impl std::ops::Mul<f32> for UnitVector {
+    type Output = Vector;
+
+    fn mul(self, scale: f32) -> Vector {
+        self.0 * scale
+    }
+}
+
+impl From<UnitVector> for Vector {
+    fn from(v: UnitVector) -> Vector {
+        v.0
+    }
 }
 
 /// Represents a complete path usable for filling or stroking.
-#[derive(Clone, Debug)]
+#[derive(Clone, PartialEq, Debug)]
 pub struct Path {
     pub ops: Vec<PathOp>,
     pub winding: Winding,
@@ -51,8 +148,18 @@ impl Path {
     /// commands with an appropriate number of LineTo commands
     /// so that the error is not greater than `tolerance`.
     pub fn flatten(&self, tolerance: f32) -> Path {
-        let mut cur_pt = None;
-        let mut flattened = Path { ops: Vec::new(), winding: Winding::NonZero };
+        let mut flattened = PathBuilder::new();
+        self.flatten_into(tolerance, &mut flattened);
+        flattened.finish()
+    }
+
+    /// Like `flatten` but appends the flattened ops to `out` instead of
+    /// returning a new `Path`, so that many paths can be flattened into one
+    /// buffer without allocating for each of them. Ops at the start of `self`
+    /// continue from the current point of `out`.
+    pub fn flatten_into(&self, tolerance: f32, out: &mut PathBuilder) {
+        let mut cur_pt = out.current_point();
+        let flattened = &mut out.path;
         for op in &self.ops {
             match *op {
                 PathOp::MoveTo(pt) | PathOp::LineTo(pt) => {
@@ -83,21 +190,329 @@ impl Path {
                         ctrl2: cpt2,
                         to: pt,
                     };
-                    for l in c.flattened(tolerance) {
-                        flattened.ops.push(PathOp::LineTo(l));
+                    flatten_cubic(&c, tolerance, &mut |l| flattened.ops.push(PathOp::LineTo(l)));
+                    cur_pt = Some(pt);
+                }
+            }
+        }
+    }
+
+    /// Returns the smallest box that contains every point of `self`.
+    ///
+    /// Unlike a box around the control points, this is tight around curves:
+    /// the extrema of each curve are found by solving for where its derivative
+    /// in x and y is zero. An empty path gives an empty box at the origin.
+    pub fn bounds(&self) -> Box2D<f32> {
+        ops_bounds(&self.ops).unwrap_or_else(Box2D::zero)
+    }
+
+    /// Returns the tight bounds of the subpath at `index`, like `bounds`,
+    /// or `None` if there's no subpath at `index`. Subpaths are counted the
+    /// same way as in `reverse_subpath`. Only the ops of that subpath are
+    /// looked at, so this is cheaper than splitting the path up to bound
+    /// each of its contours.
+    pub fn subpath_bounds(&self, index: usize) -> Option<Box2D<f32>> {
+        let mut count = 0;
+        let mut start = 0;
+        for (i, op) in self.ops.iter().enumerate() {
+            if let PathOp::MoveTo(_) = op {
+                if i > start {
+                    if count == index {
+                        return ops_bounds(&self.ops[start..i]);
+                    }
+                    count += 1;
+                }
+                start = i;
+            }
+        }
+        if count == index && start < self.ops.len() {
+            return ops_bounds(&self.ops[start..]);
+        }
+        None
+    }
+
+    /// Returns how many ops of each kind `self` contains.
+    pub fn count_ops(&self) -> OpCounts {
+        let mut counts = OpCounts::default();
+        for op in &self.ops {
+            match op {
+                PathOp::MoveTo(..) => counts.moves += 1,
+                PathOp::LineTo(..) => counts.lines += 1,
+                PathOp::QuadTo(..) => counts.quads += 1,
+                PathOp::CubicTo(..) => counts.cubics += 1,
+                PathOp::Close => counts.closes += 1,
+            }
+        }
+        counts
+    }
+
+    /// Returns true if `self` contains any quadratic or cubic curves
+    pub(crate) fn has_curves(&self) -> bool {
+        self.ops.iter().any(|op| matches!(op, PathOp::QuadTo(..) | PathOp::CubicTo(..)))
+    }
+
+    /// Splits every curve in `self` at the points where its derivative in x or y
+    /// is zero. This gives curves that are monotonic in both axes. The curves are
+    /// split exactly using de Casteljau's algorithm instead of being flattened.
+    pub fn split_at_extrema(&self) -> Path {
+        let mut cur_pt = None;
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for op in &self.ops {
+            match *op {
+                PathOp::MoveTo(pt) | PathOp::LineTo(pt) => {
+                    cur_pt = Some(pt);
+                    ops.push(*op);
+                }
+                PathOp::Close => {
+                    cur_pt = None;
+                    ops.push(*op);
+                }
+                PathOp::QuadTo(cpt, pt) => {
+                    let c = QuadraticBezierSegment {
+                        from: cur_pt.unwrap_or(cpt),
+                        ctrl: cpt,
+                        to: pt,
+                    };
+                    c.for_each_monotonic(&mut |sub| ops.push(PathOp::QuadTo(sub.ctrl, sub.to)));
+                    // make sure we end up exactly where the curve did
+                    if let Some(PathOp::QuadTo(_, end)) = ops.last_mut() {
+                        *end = pt;
+                    }
+                    cur_pt = Some(pt);
+                }
+                PathOp::CubicTo(cpt1, cpt2, pt) => {
+                    let c = CubicBezierSegment {
+                        from: cur_pt.unwrap_or(cpt1),
+                        ctrl1: cpt1,
+                        ctrl2: cpt2,
+                        to: pt,
+                    };
+                    c.for_each_monotonic(&mut |sub| ops.push(PathOp::CubicTo(sub.ctrl1, sub.ctrl2, sub.to)));
+                    if let Some(PathOp::CubicTo(_, _, end)) = ops.last_mut() {
+                        *end = pt;
+                    }
+                    cur_pt = Some(pt);
+                }
+            }
+        }
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` without empty subpaths. A run of `MoveTo`s
+    /// is collapsed into the last one, since each overrides the one before
+    /// it, and a `MoveTo` at the very end is dropped. A `MoveTo` followed by
+    /// a `Close` is kept because it's stroked as a dot.
+    pub fn normalize(&self) -> Path {
+        let mut ops: Vec<PathOp> = Vec::with_capacity(self.ops.len());
+        for op in &self.ops {
+            if let (PathOp::MoveTo(_), Some(PathOp::MoveTo(_))) = (op, ops.last()) {
+                ops.pop();
+            }
+            ops.push(*op);
+        }
+        if let Some(PathOp::MoveTo(_)) = ops.last() {
+            ops.pop();
+        }
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` without the `LineTo`s that end within `epsilon`
+    /// of the current point. Lines that don't go anywhere add nothing to a fill
+    /// or stroke but can trip up code that needs a direction for every segment.
+    /// The `MoveTo` of each subpath is always kept so no subpath disappears, and
+    /// a subpath made only of lines that don't go anywhere keeps the first one
+    /// so that it's still stroked as a dot.
+    pub fn dedup_points(&self, epsilon: f32) -> Path {
+        let mut ops = Vec::with_capacity(self.ops.len());
+        let mut start = None;
+        let mut cur_pt: Option<Point> = None;
+        // the first line dropped right after a `MoveTo`, which is put back if
+        // nothing else follows it
+        let mut dot = None;
+        let put_back_dot = |ops: &mut Vec<PathOp>, dot: &mut Option<PathOp>| {
+            if let (Some(line), Some(PathOp::MoveTo(_))) = (dot.take(), ops.last()) {
+                ops.push(line);
+            }
+        };
+        for op in &self.ops {
+            match *op {
+                PathOp::MoveTo(pt) => {
+                    put_back_dot(&mut ops, &mut dot);
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::LineTo(pt) => {
+                    if let Some(cur) = cur_pt {
+                        if (pt - cur).length() <= epsilon {
+                            if let Some(PathOp::MoveTo(_)) = ops.last() {
+                                dot.get_or_insert(*op);
+                            }
+                            continue;
+                        }
                     }
+                    start.get_or_insert(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::QuadTo(_, pt) | PathOp::CubicTo(_, _, pt) => {
                     cur_pt = Some(pt);
                 }
+                PathOp::Close => {
+                    cur_pt = start;
+                }
+            }
+            dot = None;
+            ops.push(*op);
+        }
+        put_back_dot(&mut ops, &mut dot);
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` with the open subpaths whose ends are within
+    /// `epsilon` of each other joined into longer subpaths, reversing them
+    /// where needed so that each one starts where the one before it ends.
+    /// Consecutive lines that continue in the same direction are then merged
+    /// into one line when the points between them are within `epsilon` of it.
+    /// Closed subpaths aren't joined to anything but have their lines merged
+    /// too. Where more than two subpaths meet, which ones get joined depends
+    /// on their order in `self`.
+    ///
+    /// This is useful for geometry stitched together from many small pieces,
+    /// like tiled map data, which would otherwise be stroked with caps at the
+    /// ends of every piece instead of joins.
+    pub fn join_collinear(&self, epsilon: f32) -> Path {
+        struct Subpath {
+            ops: Vec<PathOp>,
+            open: bool,
+        }
+        let end_of = |ops: &[PathOp]| match ops.last() {
+            Some(PathOp::MoveTo(pt)) | Some(PathOp::LineTo(pt)) | Some(PathOp::QuadTo(_, pt)) | Some(PathOp::CubicTo(_, _, pt)) => *pt,
+            _ => unreachable!(),
+        };
+
+        let mut subpaths: Vec<Option<Subpath>> = self.subpath_ranges().into_iter().map(|range| {
+            let ops = self.ops[range].to_vec();
+            let open = matches!(ops.first(), Some(PathOp::MoveTo(_))) && !ops.contains(&PathOp::Close);
+            Some(Subpath { ops, open })
+        }).collect();
+
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for i in 0..subpaths.len() {
+            let mut chain = match subpaths[i].take() {
+                Some(subpath) if subpath.open => subpath.ops,
+                Some(subpath) => {
+                    ops.extend(merge_collinear_lines(&subpath.ops, epsilon));
+                    continue;
+                }
+                None => continue,
+            };
+            loop {
+                let (start, end) = match chain[0] {
+                    PathOp::MoveTo(pt) => (pt, end_of(&chain)),
+                    _ => unreachable!(),
+                };
+                let near = |a: Point, b: Point| (a - b).length() <= epsilon;
+                let next = subpaths.iter_mut().find_map(|subpath| {
+                    let candidate = match subpath {
+                        Some(candidate) if candidate.open => candidate,
+                        _ => return None,
+                    };
+                    let (s, e) = match candidate.ops[0] {
+                        PathOp::MoveTo(pt) => (pt, end_of(&candidate.ops)),
+                        _ => unreachable!(),
+                    };
+                    // whether to reverse it and whether it goes before the chain
+                    let placement = if near(end, s) {
+                        (false, false)
+                    } else if near(end, e) {
+                        (true, false)
+                    } else if near(start, e) {
+                        (false, true)
+                    } else if near(start, s) {
+                        (true, true)
+                    } else {
+                        return None;
+                    };
+                    subpath.take().map(|subpath| (subpath.ops, placement))
+                });
+                let (next, reverse, before) = match next {
+                    Some((next, (reverse, before))) => (next, reverse, before),
+                    None => break,
+                };
+                let next = if reverse { reverse_ops(&next) } else { next };
+                // the `MoveTo` of the later one is dropped so that it continues
+                // from the end of the earlier one
+                chain = if before {
+                    next.into_iter().chain(chain.into_iter().skip(1)).collect()
+                } else {
+                    chain.into_iter().chain(next.into_iter().skip(1)).collect()
+                };
             }
+            ops.extend(merge_collinear_lines(&chain, epsilon));
         }
-        flattened
+        Path { ops, winding: self.winding }
+    }
+
+    /// Converts every quadratic curve in `self` into the exactly equivalent
+    /// cubic curve. This is useful for formats like PDF and PostScript that
+    /// only have cubic curves.
+    pub fn elevate_quads(&self) -> Path {
+        let mut start = None;
+        let mut cur_pt = None;
+        let ops = self.ops.iter().map(|op| {
+            match *op {
+                PathOp::MoveTo(pt) => {
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::LineTo(pt) | PathOp::CubicTo(_, _, pt) => {
+                    start.get_or_insert(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::QuadTo(cpt, pt) => {
+                    let from = cur_pt.unwrap_or(cpt);
+                    start.get_or_insert(from);
+                    cur_pt = Some(pt);
+                    return PathOp::CubicTo(
+                        from + (cpt - from) * (2. / 3.),
+                        pt + (cpt - pt) * (2. / 3.),
+                        pt,
+                    );
+                }
+                PathOp::Close => cur_pt = start,
+            }
+            *op
+        }).collect();
+        Path { ops, winding: self.winding }
     }
 
     /// Returns true if the point `x`, `y` is within the filled
-    /// area of of `self`. The path will be flattened using `tolerance`.
-    /// The point is considered contained if it's on the path.
+    /// area of of `self`. Curves are flattened using `tolerance`.
+    ///
+    /// Points within `tolerance` of the outline are considered contained.
+    /// This biases picking towards hits, which matches what a user clicking
+    /// on the edge of a shape expects, and it covers the error introduced by
+    /// flattening. Pass a smaller tolerance for more accurate hit testing
+    /// of large or zoomed in curves.
     // this function likely has bugs
     pub fn contains_point(&self, tolerance: f32, x: f32, y: f32) -> bool {
+        let (count, on_edge) = self.wind(tolerance, x, y);
+        let inside = match self.winding {
+            Winding::EvenOdd => count & 1 != 0,
+            Winding::NonZero => count != 0,
+        };
+        inside || on_edge
+    }
+
+    /// Returns the number of times the outline of `self` winds around the
+    /// point `x`, `y`. Curves are flattened using `tolerance`. The result
+    /// isn't meaningful for points on the outline.
+    pub fn winding_number(&self, tolerance: f32, x: f32, y: f32) -> i32 {
+        self.wind(tolerance, x, y).0
+    }
+
+    /// Returns the winding number of `x`, `y` and whether it's within
+    /// `tolerance` of the outline.
+    fn wind(&self, tolerance: f32, x: f32, y: f32) -> (i32, bool) {
         //XXX Instead of making a new path we should just use flattening callbacks
         let flat_path = self.flatten(tolerance);
         struct WindState {
@@ -108,6 +523,7 @@ impl Path {
 
             x: f32,
             y: f32,
+            tolerance: f32,
         }
 
         impl WindState {
@@ -123,6 +539,10 @@ impl Path {
 
             // to determine containment we just need to count crossing of ray from (x, y) going to infinity
             fn add_edge(&mut self, p1: Point, p2: Point) {
+                if !self.on_edge && distance_to_segment(Point::new(self.x, self.y), p1, p2) <= self.tolerance {
+                    self.on_edge = true;
+                }
+
                 let (x1, y1) = (p1.x, p1.y);
                 let (x2, y2) = (p2.x, p2.y);
 
@@ -169,7 +589,7 @@ impl Path {
             }
         }
 
-        let mut ws = WindState { count: 0, first_point: None, current_point: None, x, y, on_edge: false};
+        let mut ws = WindState { count: 0, first_point: None, current_point: None, x, y, tolerance, on_edge: false};
 
         for op in &flat_path.ops {
             match *op {
@@ -194,11 +614,7 @@ impl Path {
         // make sure the path is closed
         ws.close();
 
-        let inside = match self.winding {
-            Winding::EvenOdd => ws.count & 1 != 0,
-            Winding::NonZero => ws.count != 0,
-        };
-        inside || ws.on_edge
+        (ws.count, ws.on_edge)
     }
 
     pub fn transform(self, transform: &Transform) -> Path {
@@ -206,55 +622,2691 @@ impl Path {
         let ops = ops.into_iter().map(|op| op.transform(transform)).collect();
         Path { ops, winding }
     }
-}
 
-/// A helper struct used for constructing a `Path`.
-pub struct PathBuilder {
-    path: Path,
-}
+    /// Calls `f` with the index and a copy of each subpath of `self` and
+    /// returns the paths it gives back joined together, keeping the winding
+    /// of `self`. This makes it easy to treat the subpaths differently, like
+    /// moving each glyph of a line of text by its own transform.
+    pub fn map_subpaths<F: Fn(usize, &Path) -> Path>(&self, f: F) -> Path {
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for (i, range) in self.subpath_ranges().into_iter().enumerate() {
+            let subpath = Path { ops: self.ops[range].to_vec(), winding: self.winding };
+            ops.extend(f(i, &subpath).ops);
+        }
+        Path { ops, winding: self.winding }
+    }
 
-impl From<Path> for PathBuilder {
-    fn from(path: Path) -> Self {
-        PathBuilder {
-            path
+    /// Returns the convex hull of `self` as a closed polygon. Curves are
+    /// flattened using `tolerance` so that they are included in the hull.
+    /// The hull winds in the same direction as `PathBuilder::rect`.
+    pub fn convex_hull(&self, tolerance: f32) -> Path {
+        let mut points: Vec<Point> = self.flatten(tolerance).ops.iter().filter_map(|op| match *op {
+            PathOp::MoveTo(p) | PathOp::LineTo(p) => Some(p),
+            _ => None,
+        }).collect();
+
+        // Andrew's monotone chain
+        points.sort_by(|a, b| {
+            a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal)
+                .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+        });
+        points.dedup();
+
+        fn cross(o: Point, a: Point, b: Point) -> f32 {
+            (a - o).cross(b - o)
+        }
+
+        let mut hull: Vec<Point> = Vec::with_capacity(points.len() + 1);
+        if points.len() < 3 {
+            hull = points;
+        } else {
+            // lower hull
+            for &p in &points {
+                while hull.len() >= 2 && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0. {
+                    hull.pop();
+                }
+                hull.push(p);
+            }
+            // upper hull
+            let lower_len = hull.len() + 1;
+            for &p in points.iter().rev().skip(1) {
+                while hull.len() >= lower_len && cross(hull[hull.len() - 2], hull[hull.len() - 1], p) <= 0. {
+                    hull.pop();
+                }
+                hull.push(p);
+            }
+            // the last point is the same as the first
+            hull.pop();
         }
+
+        let mut pb = PathBuilder::new();
+        if let Some((first, rest)) = hull.split_first() {
+            pb.move_to(first.x, first.y);
+            for p in rest {
+                pb.line_to(p.x, p.y);
+            }
+            pb.close();
+        }
+        pb.finish()
     }
-}
 
-impl PathBuilder {
-    pub fn new() -> PathBuilder {
-        PathBuilder {
-            path: Path {
-                ops: Vec::new(),
-                winding: Winding::NonZero,
-            },
+    /// Returns the closed band between the two offsets of `self` at the signed
+    /// distances `inner` and `outer`, joined using `join`. Positive distances are
+    /// to the right of the direction of travel (in a y-down coordinate system)
+    /// and both offsets may be on the same side of the path. Like `stroke_to_path`
+    /// open subpaths end without caps and segments are not trimmed on the
+    /// inside of turns. Miter joins use the default miter limit of `StrokeStyle`.
+    pub fn ribbon(&self, inner: f32, outer: f32, join: LineJoin) -> Path {
+        ribbon_to_path(self, inner, outer, join)
+    }
+
+    /// Splits `self` into subpaths returning the end points and control
+    /// points of each one along with whether it was explicitly closed.
+    fn subpath_points(&self) -> Vec<(Vec<Point>, bool)> {
+        let mut subpaths = Vec::new();
+        let mut points: Vec<Point> = Vec::new();
+        let mut start = None;
+        for op in &self.ops {
+            let (first, rest): (Point, &[Point]) = match *op {
+                PathOp::MoveTo(pt) => {
+                    if !points.is_empty() {
+                        subpaths.push((std::mem::take(&mut points), false));
+                    }
+                    points.push(pt);
+                    start = Some(pt);
+                    continue;
+                }
+                PathOp::Close => {
+                    if !points.is_empty() {
+                        subpaths.push((std::mem::take(&mut points), true));
+                    }
+                    continue;
+                }
+                PathOp::LineTo(pt) => (pt, &[]),
+                PathOp::QuadTo(cpt, pt) => (cpt, &[pt][..]),
+                PathOp::CubicTo(cpt1, cpt2, pt) => (cpt1, &[cpt2, pt][..]),
+            };
+            if points.is_empty() {
+                // A closed subpath continues from its start. Otherwise,
+                // like when filling, the first point starts a new subpath.
+                match start {
+                    Some(start) => points.push(start),
+                    None => start = Some(first),
+                }
+            }
+            points.push(first);
+            points.extend_from_slice(rest);
         }
+        if !points.is_empty() {
+            subpaths.push((points, false));
+        }
+        subpaths
     }
 
-    /// Moves the current point to `x`, `y`
-    pub fn move_to(&mut self, x: f32, y: f32) {
-        self.path.ops.push(PathOp::MoveTo(Point::new(x, y)))
+    /// Returns the center and radius of the smallest circle that contains all of
+    /// the end points and control points of `self`. Because curves lie within
+    /// their control points the circle also contains the curves, though it may
+    /// be larger than necessary for them. An empty path gives a zero sized
+    /// circle at the origin.
+    pub fn bounding_circle(&self) -> (Point, f32) {
+        let mut points: Vec<Point> = self.subpath_points().into_iter().flat_map(|(points, _)| points).collect();
+        points.dedup();
+
+        // Welzl's algorithm in its iterative form. Its expected linear running time
+        // relies on the points being in random order so we shuffle them
+        // deterministically with a xorshift generator.
+        let mut state = 0x2545f491u32;
+        for i in (1..points.len()).rev() {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            points.swap(i, state as usize % (i + 1));
+        }
+
+        fn circle2(a: Point, b: Point) -> (Point, f32) {
+            (a.lerp(b, 0.5), (b - a).length() / 2.)
+        }
+
+        fn circle3(a: Point, b: Point, c: Point) -> (Point, f32) {
+            let (bx, by) = (b.x as f64 - a.x as f64, b.y as f64 - a.y as f64);
+            let (cx, cy) = (c.x as f64 - a.x as f64, c.y as f64 - a.y as f64);
+            let d = 2. * (bx * cy - by * cx);
+            if d == 0. {
+                // collinear so the circle is defined by the two points furthest apart
+                let candidates = [circle2(a, b), circle2(a, c), circle2(b, c)];
+                return candidates.iter().cloned().fold((a, 0.), |m, c| if c.1 > m.1 { c } else { m });
+            }
+            let b2 = bx * bx + by * by;
+            let c2 = cx * cx + cy * cy;
+            let ux = (cy * b2 - by * c2) / d;
+            let uy = (bx * c2 - cx * b2) / d;
+            let center = Point::new((a.x as f64 + ux) as f32, (a.y as f64 + uy) as f32);
+            let radius = (ux * ux + uy * uy).sqrt() as f32;
+            (center, radius)
+        }
+
+        let contains = |circle: (Point, f32), p: Point| {
+            (p - circle.0).length() <= circle.1 * (1. + 1e-5) + 1e-6
+        };
+
+        let mut circle = match points.first() {
+            Some(p) => (*p, 0.),
+            None => return (Point::zero(), 0.),
+        };
+        for i in 1..points.len() {
+            if contains(circle, points[i]) {
+                continue;
+            }
+            circle = (points[i], 0.);
+            for j in 0..i {
+                if contains(circle, points[j]) {
+                    continue;
+                }
+                circle = circle2(points[i], points[j]);
+                for k in 0..j {
+                    if !contains(circle, points[k]) {
+                        circle = circle3(points[i], points[j], points[k]);
+                    }
+                }
+            }
+        }
+
+        // make sure rounding error didn't leave any points outside
+        let radius = points.iter().fold(circle.1, |r, p| r.max((*p - circle.0).length()));
+        (circle.0, radius)
     }
 
-    /// Adds a line segment from the current point to `x`, `y`
-    pub fn line_to(&mut self, x: f32, y: f32) {
-        self.path.ops.push(PathOp::LineTo(Point::new(x, y)))
+    /// Returns the unit tangent at the start of the first subpath of `self`
+    /// that has a direction. For curves this is the derivative at the start
+    /// of the curve.
+    pub fn start_tangent(&self) -> Option<UnitVector> {
+        self.subpath_points().iter().find_map(|(points, _)| {
+            let start = *points.first()?;
+            let next = points.iter().find(|p| **p != start)?;
+            UnitVector::new(*next - start)
+        })
     }
 
-    /// Adds a quadratic bezier from the current point to `x`, `y`,
-    /// using a control point of `cx`, `cy`
-    pub fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
-        self.path
-            .ops
-            .push(PathOp::QuadTo(Point::new(cx, cy), Point::new(x, y)))
+    /// Returns the unit tangent at the end of the last subpath of `self`
+    /// that has a direction. For closed subpaths this is the direction of
+    /// the closing segment back to the start if it isn't empty.
+    pub fn end_tangent(&self) -> Option<UnitVector> {
+        self.subpath_points().iter().rev().find_map(|(points, closed)| {
+            let mut points = points.clone();
+            if *closed {
+                points.push(points[0]);
+            }
+            let end = *points.last()?;
+            let prev = points.iter().rev().find(|p| **p != end)?;
+            UnitVector::new(end - *prev)
+        })
     }
 
-    /// Adds a rect to the path
-    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
-        self.move_to(x, y);
-        self.line_to(x + width, y);
-        self.line_to(x + width, y + height);
-        self.line_to(x, y + height);
+    /// Returns the signed angle, in radians, that `self` turns through at each
+    /// vertex where one segment meets the next. The angle is between the
+    /// direction of the incoming segment and the outgoing one, positive for
+    /// turns to the left as seen on screen (counterclockwise with y pointing
+    /// down) and in the range -π to π. For curves the directions are the
+    /// tangents at their ends.
+    ///
+    /// The vertices are listed in order. The ends of open subpaths aren't
+    /// vertices, but the start of a closed subpath is and comes last, after
+    /// the closing segment. Zero length segments are ignored.
+    pub fn turn_angles(&self) -> Vec<f32> {
+        let mut angles = Vec::new();
+        // the tangents at the start and end of each segment of the current subpath
+        let mut tangents: Vec<(Vector, Vector)> = Vec::new();
+        let finish = |tangents: &mut Vec<(Vector, Vector)>, closed: bool, angles: &mut Vec<f32>| {
+            let turn = |a: Vector, b: Vector| -a.cross(b).atan2(a.dot(b));
+            for pair in tangents.windows(2) {
+                angles.push(turn(pair[0].1, pair[1].0));
+            }
+            if closed && tangents.len() > 1 {
+                angles.push(turn(tangents[tangents.len() - 1].1, tangents[0].0));
+            }
+            tangents.clear();
+        };
+        let mut start: Option<Point> = None;
+        let mut cur_pt: Option<Point> = None;
+        for op in &self.ops {
+            let points: Vec<Point> = match *op {
+                PathOp::MoveTo(pt) => {
+                    finish(&mut tangents, false, &mut angles);
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                    continue;
+                }
+                PathOp::Close => {
+                    if let (Some(from), Some(to)) = (cur_pt, start) {
+                        if from != to {
+                            tangents.push((to - from, to - from));
+                        }
+                    }
+                    finish(&mut tangents, true, &mut angles);
+                    cur_pt = start;
+                    continue;
+                }
+                PathOp::LineTo(pt) => vec![pt],
+                PathOp::QuadTo(cpt, pt) => vec![cpt, pt],
+                PathOp::CubicTo(cpt1, cpt2, pt) => vec![cpt1, cpt2, pt],
+            };
+            let from = cur_pt.unwrap_or(points[0]);
+            start.get_or_insert(from);
+            let to = points[points.len() - 1];
+            // the first and last control points that differ from the ends give the tangents
+            let next = points.iter().find(|p| **p != from);
+            let prev = std::iter::once(&from).chain(&points).rev().find(|p| **p != to);
+            if let (Some(&next), Some(&prev)) = (next, prev) {
+                tangents.push((next - from, to - prev));
+            }
+            cur_pt = Some(to);
+        }
+        finish(&mut tangents, false, &mut angles);
+        angles
+    }
+
+    /// Returns a copy of `self` with fewer points along its runs of lines,
+    /// dropping those that are within `tolerance` of the simplified outline
+    /// using the Ramer–Douglas–Peucker algorithm. Unlike plain simplification,
+    /// a vertex that turns by more than `corner_angle` radians, measured like
+    /// `turn_angles`, is never removed so sharp corners stay sharp. Curves,
+    /// and the ends of the runs of lines between them, are kept as they are.
+    pub fn simplify_preserving_corners(&self, tolerance: f32, corner_angle: f32) -> Path {
+        let mut ops = Vec::with_capacity(self.ops.len());
+        // the current run of lines, starting at the point it continues from
+        let mut run: Vec<Point> = Vec::new();
+        let flush = |run: &mut Vec<Point>, ops: &mut Vec<PathOp>| {
+            if run.len() > 1 {
+                let turn = |a: Vector, b: Vector| -a.cross(b).atan2(a.dot(b));
+                let mut kept = vec![0];
+                for i in 1..run.len() - 1 {
+                    // skip over repeated points to find the directions in and out
+                    let prev = run[..i].iter().rev().find(|p| **p != run[i]);
+                    let next = run[i + 1..].iter().find(|p| **p != run[i]);
+                    if let (Some(&prev), Some(&next)) = (prev, next) {
+                        if turn(run[i] - prev, next - run[i]).abs() > corner_angle {
+                            kept.push(i);
+                        }
+                    }
+                }
+                kept.push(run.len() - 1);
+                for pair in kept.windows(2) {
+                    let mut points = Vec::new();
+                    simplify_run(&run[pair[0]..=pair[1]], tolerance, &mut points);
+                    ops.extend(points.into_iter().map(PathOp::LineTo));
+                }
+            }
+            run.clear();
+        };
+        let mut cur_pt = None;
+        let mut start = None;
+        for op in &self.ops {
+            match *op {
+                PathOp::LineTo(pt) => {
+                    if run.is_empty() {
+                        run.push(cur_pt.unwrap_or(pt));
+                    }
+                    run.push(pt);
+                    start.get_or_insert(pt);
+                    cur_pt = Some(pt);
+                    continue;
+                }
+                PathOp::MoveTo(pt) => {
+                    flush(&mut run, &mut ops);
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::QuadTo(_, pt) | PathOp::CubicTo(_, _, pt) => {
+                    flush(&mut run, &mut ops);
+                    cur_pt = Some(pt);
+                }
+                PathOp::Close => {
+                    flush(&mut run, &mut ops);
+                    cur_pt = start;
+                }
+            }
+            ops.push(*op);
+        }
+        flush(&mut run, &mut ops);
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns true if every subpath of `self` is closed, either explicitly
+    /// with `Close` or by ending where it started.
+    pub fn is_closed(&self) -> bool {
+        self.subpath_points().iter().all(|(points, closed)| {
+            *closed || points.first() == points.last()
+        })
+    }
+
+    /// Returns true if every subpath of `self` is convex. That is, it
+    /// turns consistently in one direction and winds around only once.
+    /// Subpaths are treated as closed and curves are flattened using
+    /// `tolerance` first, so a curve counts by its shape rather than by
+    /// where its control points are.
+    pub fn is_convex(&self, tolerance: f32) -> bool {
+        self.flatten(tolerance).subpath_points().iter().all(|(points, _)| {
+            // ignore points that only differ by rounding error
+            let scale = points.iter().fold(0f32, |m, p| m.max(p.x.abs()).max(p.y.abs()));
+            let close = |a: &Point, b: &Point| (*a - *b).length() <= scale * 1e-5;
+            let mut points = points.clone();
+            points.dedup_by(|a, b| close(a, b));
+            while points.len() > 1 && close(&points[0], &points[points.len() - 1]) {
+                points.pop();
+            }
+            if points.len() < 3 {
+                return true;
+            }
+
+            let mut sign = 0.;
+            let mut total_turn = 0.;
+            for i in 0..points.len() {
+                let prev = points[(i + points.len() - 1) % points.len()];
+                let next = points[(i + 1) % points.len()];
+                let a = points[i] - prev;
+                let b = next - points[i];
+                let cross = a.cross(b);
+                let dot = a.dot(b);
+                // treat nearly collinear points as collinear so that
+                // rounding error doesn't make curves look concave
+                let collinear = cross.abs() <= 1e-4 * a.length() * b.length();
+                if collinear && dot < 0. {
+                    // the path doubles back on itself
+                    return false;
+                }
+                if !collinear {
+                    if sign * cross < 0. {
+                        return false;
+                    }
+                    sign = cross;
+                }
+                total_turn += cross.atan2(dot);
+            }
+            // a star turns consistently but winds around more than once
+            total_turn.abs() < 2. * std::f32::consts::PI + 0.01
+        })
+    }
+
+    /// Returns true if `self` and `other` have the same winding and the same
+    /// ops in the same order with every coordinate within `epsilon` of the other.
+    pub fn approx_eq(&self, other: &Path, epsilon: f32) -> bool {
+        let close = |a: Point, b: Point| (a.x - b.x).abs() <= epsilon && (a.y - b.y).abs() <= epsilon;
+        self.winding == other.winding &&
+            self.ops.len() == other.ops.len() &&
+            self.ops.iter().zip(&other.ops).all(|(a, b)| match (*a, *b) {
+                (PathOp::MoveTo(a), PathOp::MoveTo(b)) |
+                (PathOp::LineTo(a), PathOp::LineTo(b)) => close(a, b),
+                (PathOp::QuadTo(a1, a2), PathOp::QuadTo(b1, b2)) => close(a1, b1) && close(a2, b2),
+                (PathOp::CubicTo(a1, a2, a3), PathOp::CubicTo(b1, b2, b3)) => {
+                    close(a1, b1) && close(a2, b2) && close(a3, b3)
+                }
+                (PathOp::Close, PathOp::Close) => true,
+                _ => false,
+            })
+    }
+
+    /// Applies `f` to every end point and control point of `self`.
+    ///
+    /// Unlike `transform` this allows arbitrary non-affine mappings. Only the
+    /// points themselves are mapped so straight lines stay straight; flatten
+    /// the path first if the distortion should also bend the segments.
+    pub fn map_coords<F: Fn(Point) -> Point>(&self, f: F) -> Path {
+        let ops = self.ops.iter().map(|op| op.map_coords(&f)).collect();
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` with every end point and control point moved
+    /// into `bounds`, with NaN coordinates moved to the minimum. This doesn't
+    /// preserve the shape of anything outside of `bounds`, it only keeps the
+    /// coordinates of untrusted paths small enough that the stroker and
+    /// rasterizer won't overflow on them.
+    pub fn clamp_to(&self, bounds: &Box2D<f32>) -> Path {
+        // f32::max returns the other value when one of them is NaN
+        self.map_coords(|p| Point::new(
+            p.x.max(bounds.min.x).min(bounds.max.x),
+            p.y.max(bounds.min.y).min(bounds.max.y),
+        ))
+    }
+
+    /// Returns a copy of `self` scaled and translated so that its `bounds`
+    /// fill `target`, like fitting an icon into a viewport. When
+    /// `preserve_aspect` is true `self` is scaled by the same amount in both
+    /// directions, as much as fits, and centered in `target` along the other
+    /// direction. Otherwise it's stretched to fill `target` exactly. A path
+    /// that's flat in one direction, like a horizontal line, isn't scaled in
+    /// that direction, only centered, and an empty path is returned as it is.
+    pub fn scale_to_fit(&self, target: &Box2D<f32>, preserve_aspect: bool) -> Path {
+        let bounds = match ops_bounds(&self.ops) {
+            Some(bounds) => bounds,
+            None => return self.clone(),
+        };
+        let scale = |target: f32, size: f32| if size > 0. { Some(target / size) } else { None };
+        let (sx, sy) = (scale(target.width(), bounds.width()), scale(target.height(), bounds.height()));
+        let (sx, sy) = if preserve_aspect {
+            let s = match (sx, sy) {
+                (Some(sx), Some(sy)) => sx.min(sy),
+                (Some(s), None) | (None, Some(s)) => s,
+                (None, None) => 1.,
+            };
+            (s, s)
+        } else {
+            (sx.unwrap_or(1.), sy.unwrap_or(1.))
+        };
+        let (from, to) = (bounds.center(), target.center());
+        self.map_coords(|p| Point::new(to.x + (p.x - from.x) * sx, to.y + (p.y - from.y) * sy))
+    }
+
+    /// Returns a copy of `self` with every coordinate rounded to the nearest
+    /// multiple of `grid`, which makes the output the same across platforms
+    /// and shorter to write out. Points that round to the same place leave
+    /// behind lines that don't go anywhere, which `dedup_points(0.)` removes
+    /// while keeping subpaths that collapse to a single point as dots.
+    /// A `grid` that isn't positive leaves `self` as it is.
+    pub fn quantize(&self, grid: f32) -> Path {
+        if grid.is_nan() || grid <= 0. {
+            return self.clone();
+        }
+        self.map_coords(|p| Point::new((p.x / grid).round() * grid, (p.y / grid).round() * grid))
+    }
+
+    /// Returns a copy of `self` that traces exactly the same outline backwards.
+    /// The subpaths come in the opposite order and each one is reversed like
+    /// with `reverse_subpath`, with the control points of curves swapped so
+    /// that they keep their shape. Reversing twice gives back the same
+    /// outline, although a closed subpath that ends with a line back to its
+    /// start point loses that line.
+    pub fn reverse(&self) -> Path {
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for range in self.subpath_ranges().into_iter().rev() {
+            ops.extend(reverse_ops(&self.ops[range]));
+        }
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` with the direction of the subpath at `index`
+    /// reversed and every other subpath left as is. Subpaths are counted from
+    /// 0 and each one starts at a `MoveTo`. Any ops before the first `MoveTo`
+    /// count as a subpath of their own. If there's no subpath at `index` the
+    /// path is returned unchanged.
+    ///
+    /// This is useful for fixing a single contour with the wrong winding,
+    /// which would otherwise fill a hole or leave a filled region empty.
+    pub fn reverse_subpath(&self, index: usize) -> Path {
+        let range = match self.subpath_ranges().get(index) {
+            Some(range) => range.clone(),
+            None => return self.clone(),
+        };
+
+        let mut ops = self.ops[..range.start].to_vec();
+        ops.extend(reverse_ops(&self.ops[range.clone()]));
+        ops.extend_from_slice(&self.ops[range.end..]);
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns the outline of the area filled by either `self` or `other`,
+    /// each with its own fill rule. Curves are flattened using `tolerance` and
+    /// open subpaths are treated as closed, like when filling. The result is
+    /// made of lines that don't cross each other, with the outlines wound like
+    /// `PathBuilder::rect` and the holes in them the other way, and is filled
+    /// with the nonzero rule. Every pair of edges is tested so this is O(n²) in
+    /// the number of edges once flattened.
+    pub fn union(&self, other: &Path, tolerance: f32) -> Path {
+        path_boolean(self, other, tolerance, &|in_self, in_other| in_self || in_other)
+    }
+
+    /// Returns the outline of the area filled by both `self` and `other`. See
+    /// `union` for how the inputs are read and the result is wound.
+    pub fn bool_intersect(&self, other: &Path, tolerance: f32) -> Path {
+        path_boolean(self, other, tolerance, &|in_self, in_other| in_self && in_other)
+    }
+
+    /// Returns the outline of the area filled by `self` but not by `other`. See
+    /// `union` for how the inputs are read and the result is wound.
+    pub fn bool_difference(&self, other: &Path, tolerance: f32) -> Path {
+        path_boolean(self, other, tolerance, &|in_self, in_other| in_self && !in_other)
+    }
+
+    /// Returns the parts of `self` that are inside the area filled by `clip`.
+    /// The closed subpaths of `self` are taken as a fill and intersected with
+    /// `clip` like `bool_intersect`, using the winding rule of `self`. The open
+    /// subpaths are taken as lines to be stroked and are cut where they cross
+    /// the outline of `clip`, keeping the pieces inside as open subpaths after
+    /// the fill. Curves are flattened using `tolerance`.
+    pub fn clip_to_path(&self, clip: &Path, tolerance: f32) -> Path {
+        let subpaths = self.flattened_subpaths(tolerance);
+        let mut fill = PathBuilder::new();
+        let mut lines = Vec::new();
+        for (points, closed) in subpaths {
+            if closed {
+                fill.move_to(points[0].x, points[0].y);
+                for p in &points[1..] {
+                    fill.line_to(p.x, p.y);
+                }
+                fill.close();
+            } else {
+                lines.push(points);
+            }
+        }
+        let mut fill = fill.finish();
+        fill.winding = self.winding;
+
+        let mut result = fill.bool_intersect(clip, tolerance);
+        for run in clip_polylines(&lines, clip, tolerance) {
+            result.ops.push(PathOp::MoveTo(run[0]));
+            result.ops.extend(run[1..].iter().map(|&p| PathOp::LineTo(p)));
+        }
+        result
+    }
+
+    /// Returns the points where the outlines of `self` and `other` cross
+    /// or touch. Both paths are flattened using `tolerance` and each pair
+    /// of line segments is tested, so this is O(n·m) in the number of
+    /// segments. Where segments overlap along a line the two ends of the
+    /// overlap are reported. Points closer together than `tolerance` are
+    /// only reported once.
+    ///
+    /// Only the lines that are drawn are considered: open subpaths aren't
+    /// implicitly closed.
+    pub fn intersections(&self, other: &Path, tolerance: f32) -> Vec<Point> {
+        let a = self.flatten(tolerance).line_segments();
+        let b = other.flatten(tolerance).line_segments();
+        let mut points: Vec<Point> = Vec::new();
+        let mut add = |pt: Point| {
+            if !points.iter().any(|p| (*p - pt).length() <= tolerance) {
+                points.push(pt);
+            }
+        };
+        for &(p0, p1) in &a {
+            for &(q0, q1) in &b {
+                segment_intersection(p0, p1, q0, q1, &mut add);
+            }
+        }
+        points
+    }
+
+    /// Returns true if every point of the filled area of `other` is within
+    /// the filled area of `self`, with curves flattened using `tolerance`.
+    ///
+    /// Testing the vertices of `other` isn't enough because an edge of
+    /// `other` can poke out of `self` between them, so this also checks
+    /// that the outlines don't cross using `intersections`, and that no
+    /// hole in `self` sits inside `other`. Outlines that touch count as
+    /// crossing, which makes this conservative for shapes that share an
+    /// edge. Like `intersections`, only the lines that are drawn are
+    /// checked, so open subpaths should be closed first.
+    pub fn contains_path(&self, other: &Path, tolerance: f32) -> bool {
+        let segments = other.flatten(tolerance).line_segments();
+        if !segments.iter().all(|(p, _)| self.contains_point(tolerance, p.x, p.y)) {
+            return false;
+        }
+        if !self.intersections(other, tolerance).is_empty() {
+            return false;
+        }
+        // Each subpath of `self` is now entirely inside or outside of `other`. The
+        // ones inside must have `self` filled on both sides or they'd cut a hole.
+        let flattened = self.flatten(tolerance);
+        flattened.subpath_ranges().into_iter().all(|range| {
+            let subpath = Path { ops: flattened.ops[range].to_vec(), winding: self.winding };
+            let (p0, p1) = match subpath.line_segments().first() {
+                Some(&segment) => segment,
+                None => return true,
+            };
+            let mid = p0.lerp(p1, 0.5);
+            if !other.contains_point(tolerance, mid.x, mid.y) {
+                return true;
+            }
+            let normal = Vector::new(p0.y - p1.y, p1.x - p0.x).normalize() * (2. * tolerance);
+            self.contains_point(tolerance, mid.x + normal.x, mid.y + normal.y) &&
+                self.contains_point(tolerance, mid.x - normal.x, mid.y - normal.y)
+        })
+    }
+
+    /// Returns the distance from `p` to the nearest point on the outline of
+    /// `self` after flattening it using `tolerance`. Thresholding this at half
+    /// the width of a stroke gives the area covered by a stroke with round caps
+    /// and joins, which is useful for rendering strokes as a distance field.
+    /// Returns infinity if `self` doesn't draw any lines.
+    pub fn distance_to(&self, p: Point, tolerance: f32) -> f32 {
+        self.flatten(tolerance)
+            .line_segments()
+            .iter()
+            .map(|&(p0, p1)| distance_to_segment(p, p0, p1))
+            .fold(f32::INFINITY, f32::min)
+    }
+
+    /// Returns the point on the outline of `self` nearest to `p`, after flattening
+    /// it using `tolerance`, along with its distance along the outline from the
+    /// start of `self`. Like `sample_uniform`, the gaps between subpaths don't
+    /// count towards the distance. Returns `None` if `self` doesn't draw any lines.
+    pub fn closest_point(&self, p: Point, tolerance: f32) -> Option<(Point, f32)> {
+        let mut closest: Option<(f32, Point, f32)> = None;
+        // the distance along the path to the start of the current segment
+        let mut walked = 0.;
+        for (p0, p1) in self.flatten(tolerance).line_segments() {
+            let d = p1 - p0;
+            let length = d.length();
+            let t = ((p - p0).dot(d) / d.square_length()).clamp(0., 1.);
+            let pt = p0 + d * t;
+            let distance = (p - pt).length();
+            match closest {
+                Some((best, ..)) if best <= distance => {}
+                _ => closest = Some((distance, pt, walked + length * t)),
+            }
+            walked += length;
+        }
+        closest.map(|(_, pt, along)| (pt, along))
+    }
+
+    /// Returns whether `p` is within half of `style.width` of the centerline of
+    /// `self`, i.e. whether it would be covered when stroking `self` with `style`.
+    /// Curves are flattened using `tolerance` and `style.dash_array` is applied
+    /// if it's not empty, so points in the gaps between dashes aren't on the
+    /// stroke unless the cap of a dash reaches them. The caps at the ends of
+    /// open subpaths and dashes follow `style.cap` but joins are always treated
+    /// as round, so the tips of miter joins don't count and the corners cut off
+    /// by bevel joins do.
+    pub fn point_on_stroke(&self, p: Point, style: &StrokeStyle, tolerance: f32) -> bool {
+        let mut path = self.flatten(tolerance);
+        if !style.dash_array.is_empty() {
+            path = dash_path(&path, &style.dash_array, style.dash_offset);
+        }
+        let half_width = style.width / 2.;
+
+        // whether `p` is covered by the segment from `p0` to `p1` along with the
+        // caps at either end that are given
+        let hit = |p0: Point, p1: Point, start_cap: bool, end_cap: bool| {
+            let d = p1 - p0;
+            let len = d.length();
+            let along = (p - p0).dot(d) / len;
+            let beyond = if along < 0. && start_cap {
+                Some((-along, p0))
+            } else if along > len && end_cap {
+                Some((along - len, p1))
+            } else {
+                None
+            };
+            match (beyond, style.cap) {
+                (None, _) => distance_to_segment(p, p0, p1) <= half_width,
+                (Some(_), LineCap::Butt) => false,
+                (Some((beyond, _)), LineCap::Square) => beyond <= half_width && d.cross(p - p0).abs() / len <= half_width,
+                (Some((_, end)), LineCap::Round) => (p - end).length() <= half_width,
+                (Some((beyond, _)), LineCap::Rounded(radius)) => {
+                    let radius = if radius > 0. { radius.min(half_width) } else { 0. };
+                    let across = d.cross(p - p0).abs() / len;
+                    // how far into the rounded corner, if at all, along and across the line
+                    let corner = Vector::new(beyond - (half_width - radius), across - (half_width - radius));
+                    beyond <= half_width && across <= half_width
+                        && (corner.x <= 0. || corner.y <= 0. || corner.length() <= radius)
+                }
+            }
+        };
+
+        let mut segments = Vec::new();
+        let mut start = None;
+        let mut cur_pt = None;
+        let hit_subpath = |segments: &mut Vec<(Point, Point)>, closed: bool| {
+            let last = segments.len().wrapping_sub(1);
+            let hit = segments.iter().enumerate().any(|(i, &(p0, p1))| hit(p0, p1, !closed && i == 0, !closed && i == last));
+            segments.clear();
+            hit
+        };
+        for op in &path.ops {
+            match *op {
+                PathOp::MoveTo(pt) => {
+                    if hit_subpath(&mut segments, false) {
+                        return true;
+                    }
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                }
+                PathOp::LineTo(pt) => {
+                    let from = cur_pt.unwrap_or(pt);
+                    start.get_or_insert(from);
+                    if from != pt {
+                        segments.push((from, pt));
+                    }
+                    cur_pt = Some(pt);
+                }
+                PathOp::Close => {
+                    if let (Some(from), Some(to)) = (cur_pt, start) {
+                        if from != to {
+                            segments.push((from, to));
+                        }
+                    }
+                    if hit_subpath(&mut segments, true) {
+                        return true;
+                    }
+                    cur_pt = start;
+                }
+                PathOp::QuadTo(..) | PathOp::CubicTo(..) => panic!("Only flat paths handled"),
+            }
+        }
+        hit_subpath(&mut segments, false)
+    }
+
+    /// Returns `self` dashed with exactly `count` evenly spaced dashes along each
+    /// subpath, each covering `duty_cycle` of the spacing. Unlike an absolute dash
+    /// pattern this doesn't leave a partial dash where closed subpaths meet
+    /// themselves, which suits dashed circles and other closed shapes. Curves are
+    /// flattened using `tolerance` and the result can be stroked as usual.
+    pub fn dash_evenly(&self, count: usize, duty_cycle: f32, tolerance: f32) -> Path {
+        dash_path_evenly(&self.flatten(tolerance), count, duty_cycle)
+    }
+
+    /// Returns `self` dashed with `style.dash_array` and `style.dash_offset`,
+    /// with the dashes adjusted for the round or square caps that stroking
+    /// them with `style` adds: half the width of the stroke past each end.
+    /// `fit` decides whether the dashes are shortened so the caps end where
+    /// the dash pattern says, or lengthened so the caps overlap into a
+    /// continuous line. With butt caps the dashes are left as they are.
+    /// Dashes no longer than the width of the stroke shrink to nothing and
+    /// are left out, rather than being drawn as dots.
+    /// Curves are flattened using `tolerance`.
+    ///
+    /// Because only the dashes move, animating `style.dash_offset` keeps
+    /// the look chosen by `fit`.
+    pub fn dash_with_caps(&self, style: &StrokeStyle, fit: DashCapFit, tolerance: f32) -> Path {
+        let path = self.flatten(tolerance);
+        if style.dash_array.is_empty() {
+            return path;
+        }
+        let cap_length = match style.cap {
+            LineCap::Round | LineCap::Square | LineCap::Rounded(_) => style.width / 2.,
+            LineCap::Butt => 0.,
+        };
+        let extend = match fit {
+            DashCapFit::Flush => -cap_length,
+            DashCapFit::Overlap => cap_length,
+        };
+        dash_path_extended(&path, &style.dash_array, style.dash_offset, extend)
+    }
+
+    /// Returns each closed subpath of `self` as a loop of points, after flattening
+    /// curves using `tolerance`. The first point isn't repeated at the end and
+    /// repeated points are dropped. Loops with fewer than three points don't
+    /// enclose anything and are skipped. Open subpaths are returned by
+    /// `to_polylines` instead.
+    pub fn to_polygons(&self, tolerance: f32) -> Vec<Vec<Point>> {
+        self.flattened_subpaths(tolerance)
+            .into_iter()
+            .filter(|(points, closed)| *closed && points.len() >= 3)
+            .map(|(points, _)| points)
+            .collect()
+    }
+
+    /// Returns each open subpath of `self` as a list of points, after flattening
+    /// curves using `tolerance`. Repeated points are dropped and subpaths that
+    /// don't go anywhere are skipped. Closed subpaths are returned by
+    /// `to_polygons` instead.
+    pub fn to_polylines(&self, tolerance: f32) -> Vec<Vec<Point>> {
+        self.flattened_subpaths(tolerance)
+            .into_iter()
+            .filter(|(points, closed)| !*closed && points.len() >= 2)
+            .map(|(points, _)| points)
+            .collect()
+    }
+
+    /// Returns a smooth curve through the vertices of each subpath of `self`,
+    /// made of a cubic between each pair of neighbouring vertices. This is a
+    /// cardinal spline: the curve passes through each vertex heading in the
+    /// direction from the vertex before it to the one after it. `tension`
+    /// goes from 0, which gives a Catmull-Rom spline, to 1, which gives
+    /// straight lines, with values in between hugging the vertices more
+    /// tightly. Closed subpaths wrap around smoothly and open ones start and
+    /// end heading towards their neighbouring vertex. Any curves in `self`
+    /// are flattened using `tolerance` first.
+    ///
+    /// This suits turning a shaky polyline, like a stroke sampled from the
+    /// mouse, into a curve that strokes smoothly.
+    pub fn smooth(&self, tension: f32, tolerance: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        let scale = (1. - tension) / 6.;
+        for (points, closed) in self.flattened_subpaths(tolerance) {
+            let n = points.len();
+            pb.move_to(points[0].x, points[0].y);
+            let segments = if closed && n > 2 { n } else { n - 1 };
+            for i in 0..segments {
+                let p1 = points[i];
+                let p2 = points[(i + 1) % n];
+                // the ends of open subpaths act as their own neighbours
+                let p0 = if i > 0 || segments == n { points[(i + n - 1) % n] } else { p1 };
+                let p3 = if i + 2 < n || segments == n { points[(i + 2) % n] } else { p2 };
+                let c1 = p1 + (p2 - p0) * scale;
+                let c2 = p2 - (p3 - p1) * scale;
+                pb.cubic_to(c1.x, c1.y, c2.x, c2.y, p2.x, p2.y);
+            }
+            if closed {
+                pb.close();
+            }
+        }
+        pb.finish()
+    }
+
+    /// Returns the points of each subpath of `self` once flattened, without
+    /// repeats, along with whether it was closed. A closed subpath doesn't
+    /// repeat its first point and ops after a `Close` without a `MoveTo`
+    /// start a new subpath from the point that was closed to.
+    pub(crate) fn flattened_subpaths(&self, tolerance: f32) -> Vec<(Vec<Point>, bool)> {
+        self.flattened_subpaths_inner(tolerance, false)
+    }
+
+    /// Like `flattened_subpaths` but an open subpath with lines that don't go
+    /// anywhere, which the stroker draws as a dot, has its point twice to tell
+    /// it apart from a lone `MoveTo`.
+    pub(crate) fn flattened_subpaths_with_dots(&self, tolerance: f32) -> Vec<(Vec<Point>, bool)> {
+        self.flattened_subpaths_inner(tolerance, true)
+    }
+
+    fn flattened_subpaths_inner(&self, tolerance: f32, keep_dots: bool) -> Vec<(Vec<Point>, bool)> {
+        let mut subpaths = Vec::new();
+        let mut points: Vec<Point> = Vec::new();
+        let mut start = None;
+        // whether the current subpath has any lines
+        let mut drawn = false;
+        let push = |points: &mut Vec<Point>, pt: Point| {
+            if points.last() != Some(&pt) {
+                points.push(pt);
+            }
+        };
+        let open = |mut points: Vec<Point>, drawn: bool| {
+            if keep_dots && drawn && points.len() == 1 {
+                points.push(points[0]);
+            }
+            (points, false)
+        };
+        for op in &self.flatten(tolerance).ops {
+            match *op {
+                PathOp::MoveTo(pt) => {
+                    if !points.is_empty() {
+                        subpaths.push(open(std::mem::take(&mut points), drawn));
+                    }
+                    start = Some(pt);
+                    points.push(pt);
+                    drawn = false;
+                }
+                PathOp::LineTo(pt) => {
+                    if points.is_empty() {
+                        // continue from where the last subpath was closed
+                        if let Some(start) = start {
+                            points.push(start);
+                        }
+                    }
+                    push(&mut points, pt);
+                    drawn = true;
+                }
+                PathOp::Close => {
+                    if points.len() > 1 && points.last() == points.first() {
+                        points.pop();
+                    }
+                    if !points.is_empty() {
+                        subpaths.push((std::mem::take(&mut points), true));
+                    }
+                    drawn = false;
+                }
+                PathOp::QuadTo(..) | PathOp::CubicTo(..) => panic!("Only flat paths handled"),
+            }
+        }
+        if !points.is_empty() {
+            subpaths.push(open(points, drawn));
+        }
+        subpaths
+    }
+
+    /// Splits the outline of `self` into chains of points that are monotone in y,
+    /// which is the form a scanline fill consumes with a list of active edges.
+    /// Curves are flattened using `tolerance` and every subpath is treated as
+    /// closed, like when filling. Each chain runs in the direction of the path
+    /// and shares its end points with its neighbours. Horizontal segments are
+    /// kept in the chain they follow.
+    pub fn split_y_monotone(&self, tolerance: f32) -> Vec<Vec<Point>> {
+        let flattened = self.flatten(tolerance);
+        let mut chains = Vec::new();
+        for range in flattened.subpath_ranges() {
+            let subpath = Path { ops: flattened.ops[range].to_vec(), winding: self.winding };
+            let segments = subpath.line_segments();
+            let (first, last) = match (segments.first(), segments.last()) {
+                (Some(first), Some(last)) => (first.0, last.1),
+                _ => continue,
+            };
+            let mut points: Vec<Point> = Some(first).into_iter().chain(segments.iter().map(|s| s.1)).collect();
+            if last != first {
+                points.push(first);
+            }
+
+            let subpath_start = chains.len();
+            let mut chain = vec![first];
+            // the direction in y of the current chain, `Equal` until it's known
+            let mut direction = Ordering::Equal;
+            let mut first_direction = Ordering::Equal;
+            for pair in points.windows(2) {
+                let dy = pair[1].y.partial_cmp(&pair[0].y).unwrap_or(Ordering::Equal);
+                if dy != Ordering::Equal && direction != Ordering::Equal && dy != direction {
+                    chains.push(std::mem::replace(&mut chain, vec![pair[0]]));
+                    direction = Ordering::Equal;
+                }
+                if direction == Ordering::Equal {
+                    direction = dy;
+                }
+                if first_direction == Ordering::Equal {
+                    first_direction = dy;
+                }
+                chain.push(pair[1]);
+            }
+            // the subpath is closed so the last chain continues into the first one
+            // when they go the same way
+            if chains.len() > subpath_start && direction == first_direction {
+                chain.extend_from_slice(&chains[subpath_start][1..]);
+                chains[subpath_start] = chain;
+            } else {
+                chains.push(chain);
+            }
+        }
+        chains
+    }
+
+    /// Returns `n` points spread evenly by distance along `self`, from its
+    /// start to its end, along with the unit tangent at each of them. Curves
+    /// are flattened using `tolerance` and the samples are all found in a
+    /// single walk along the path. The gaps between subpaths don't count
+    /// towards the distance.
+    pub fn sample_uniform(&self, n: usize, tolerance: f32) -> Vec<(Point, UnitVector)> {
+        let segments = self.flatten(tolerance).line_segments();
+        let total: f32 = segments.iter().map(|(p0, p1)| (*p1 - *p0).length()).sum();
+        let mut samples = Vec::with_capacity(n);
+        if segments.is_empty() {
+            return samples;
+        }
+
+        let step = if n > 1 { total / (n - 1) as f32 } else { 0. };
+        // the distance along the path to the start of `segments[i]`
+        let mut walked = 0.;
+        let mut i = 0;
+        for k in 0..n {
+            let target = step * k as f32;
+            loop {
+                let (p0, p1) = segments[i];
+                let length = (p1 - p0).length();
+                if target <= walked + length || i == segments.len() - 1 {
+                    let t = ((target - walked) / length).clamp(0., 1.);
+                    samples.push((p0.lerp(p1, t), UnitVector::new_unchecked((p1 - p0) / length)));
+                    break;
+                }
+                walked += length;
+                i += 1;
+            }
+        }
+        samples
+    }
+
+    /// Returns the part of `self` between the distances `start` and `end`
+    /// along it, after flattening curves using `tolerance`. Like in
+    /// `sample_uniform`, the gaps between subpaths don't count towards the
+    /// distance. Closed subpaths that are entirely in the range stay closed;
+    /// the ones that are cut become open.
+    pub fn slice_by_length(&self, start: f32, end: f32, tolerance: f32) -> Path {
+        let flattened = self.flatten(tolerance);
+        let mut pb = PathBuilder::new();
+        // the distance along the path to the start of the current segment
+        let mut walked = 0.;
+        for range in flattened.subpath_ranges() {
+            let ops = &flattened.ops[range];
+            let subpath = Path { ops: ops.to_vec(), winding: self.winding };
+            let segments = subpath.line_segments();
+            let length: f32 = segments.iter().map(|(p0, p1)| (*p1 - *p0).length()).sum();
+            if ops.last() == Some(&PathOp::Close) && start <= walked && walked + length <= end {
+                pb.extend_from_slice(ops);
+                walked += length;
+                continue;
+            }
+            let mut drawing = false;
+            for (p0, p1) in segments {
+                let segment_length = (p1 - p0).length();
+                let from = start.max(walked);
+                let to = end.min(walked + segment_length);
+                if from < to {
+                    let q0 = p0.lerp(p1, (from - walked) / segment_length);
+                    let q1 = p0.lerp(p1, (to - walked) / segment_length);
+                    if !drawing {
+                        pb.move_to(q0.x, q0.y);
+                        drawing = true;
+                    }
+                    pb.line_to(q1.x, q1.y);
+                }
+                walked += segment_length;
+            }
+        }
+        pb.finish()
+    }
+
+    /// Returns the end points of each line of `self`, including the lines
+    /// drawn by `Close`, or `None` if `self` has any curves. Zero length
+    /// lines are skipped. This is a cheap way to consume paths that are
+    /// already flat, like the output of `flatten` or `stroke_to_path`.
+    pub fn as_lines(&self) -> Option<Vec<(Point, Point)>> {
+        if self.has_curves() {
+            return None;
+        }
+        Some(self.line_segments())
+    }
+
+    /// Returns the end points of each line in a flattened path, including
+    /// the lines drawn by `Close`. Zero length lines are skipped.
+    pub(crate) fn line_segments(&self) -> Vec<(Point, Point)> {
+        let mut segments = Vec::new();
+        let mut start = None;
+        let mut cur_pt: Option<Point> = None;
+        for op in &self.ops {
+            let (from, to) = match *op {
+                PathOp::MoveTo(pt) => {
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                    continue;
+                }
+                PathOp::LineTo(pt) => {
+                    let from = cur_pt.unwrap_or(pt);
+                    start.get_or_insert(from);
+                    cur_pt = Some(pt);
+                    (from, pt)
+                }
+                PathOp::Close => match (cur_pt, start) {
+                    (Some(from), Some(to)) => {
+                        cur_pt = start;
+                        (from, to)
+                    }
+                    _ => continue,
+                },
+                PathOp::QuadTo(..) | PathOp::CubicTo(..) => panic!("Only flat paths handled"),
+            };
+            if from != to {
+                segments.push((from, to));
+            }
+        }
+        segments
+    }
+
+    /// Returns a copy of `self` that fills the same area with the nonzero
+    /// rule as `self` does with the even-odd rule. Each subpath is oriented
+    /// by how many of the other subpaths it's nested inside: the outermost
+    /// ones wind like `PathBuilder::rect`, the ones inside them wind the
+    /// other way to cut holes and so on.
+    ///
+    /// Nesting is decided from the first point of each subpath, with curves
+    /// flattened using `tolerance`, so this assumes that subpaths don't
+    /// cross each other, which is the case for most shapes like glyph
+    /// outlines and traced images.
+    pub fn even_odd_to_nonzero(&self, tolerance: f32) -> Path {
+        self.orient_by_nesting(tolerance)
+    }
+
+    /// Returns a copy of `self` with outer contours given a positive area,
+    /// like `with_outer_ccw`, and the holes inside them wound the other way,
+    /// nested contours alternating from there, for filling with the nonzero
+    /// rule. This fixes imported or traced shapes that fill solid because
+    /// all of their subpaths wind the same way. It's the same as
+    /// `even_odd_to_nonzero` and makes the same assumption that subpaths
+    /// don't cross each other.
+    pub fn with_consistent_winding(&self, tolerance: f32) -> Path {
+        self.even_odd_to_nonzero(tolerance)
+    }
+
+    /// Returns a copy of `self` with the subpath that encloses the largest
+    /// area wound counterclockwise when y points up, giving it a positive
+    /// area like `PathBuilder::rect`, and every other subpath wound the other
+    /// way. This suits the common case of a single shape with holes, like
+    /// a glyph imported from a source with no winding guarantee, and is
+    /// cheaper than `even_odd_to_nonzero`, which also handles separate shapes
+    /// and shapes inside holes by testing how the subpaths nest. Subpaths
+    /// that don't enclose any area are left as they are.
+    pub fn with_outer_ccw(&self) -> Path {
+        let ranges = self.subpath_ranges();
+        let areas: Vec<f32> = ranges.iter().map(|range| ops_area(&self.ops[range.clone()])).collect();
+        let outer = areas.iter().enumerate()
+            .max_by(|(_, a), (_, b)| a.abs().partial_cmp(&b.abs()).unwrap_or(Ordering::Equal))
+            .map(|(i, _)| i);
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for (i, range) in ranges.into_iter().enumerate() {
+            let positive = Some(i) == outer;
+            let area = areas[i];
+            if (positive && area < 0.) || (!positive && area > 0.) {
+                ops.extend(reverse_ops(&self.ops[range]));
+            } else {
+                ops.extend_from_slice(&self.ops[range]);
+            }
+        }
+        Path { ops, winding: self.winding }
+    }
+
+    /// Orients each subpath by how many of the others it's nested inside.
+    /// Outermost subpaths get a positive area.
+    fn orient_by_nesting(&self, tolerance: f32) -> Path {
+        let ranges = self.subpath_ranges();
+        let depths = self.nesting_depths(tolerance);
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for (range, depth) in ranges.into_iter().zip(depths) {
+            let subpath = &self.ops[range];
+            let depth = match depth {
+                Some(depth) => depth,
+                None => {
+                    ops.extend_from_slice(subpath);
+                    continue;
+                }
+            };
+            let area = ops_area(subpath);
+            let positive = depth % 2 == 0;
+            if (positive && area < 0.) || (!positive && area > 0.) {
+                ops.extend(reverse_ops(subpath));
+            } else {
+                ops.extend_from_slice(subpath);
+            }
+        }
+        Path { ops, winding: Winding::NonZero }
+    }
+
+    /// Returns how many of the other subpaths the first point of each subpath
+    /// is inside of, or None for the subpaths that have no points. Curves
+    /// are flattened using `tolerance`.
+    fn nesting_depths(&self, tolerance: f32) -> Vec<Option<usize>> {
+        let subpaths: Vec<Path> = self.subpath_ranges().into_iter()
+            .map(|range| Path { ops: self.ops[range].to_vec(), winding: Winding::NonZero })
+            .collect();
+        subpaths.iter().enumerate().map(|(i, subpath)| {
+            let first = subpath.ops.iter().find_map(|op| match *op {
+                PathOp::MoveTo(pt) | PathOp::LineTo(pt) | PathOp::QuadTo(pt, _) | PathOp::CubicTo(pt, _, _) => Some(pt),
+                PathOp::Close => None,
+            })?;
+            Some(subpaths.iter().enumerate()
+                .filter(|&(j, other)| j != i && other.winding_number(tolerance, first.x, first.y) != 0)
+                .count())
+        }).collect()
+    }
+
+    /// Returns the ranges of `self.ops` that make up each subpath. Each one
+    /// starts at a `MoveTo` except for any ops before the first `MoveTo`,
+    /// which form a subpath of their own.
+    pub(crate) fn subpath_ranges(&self) -> Vec<Range<usize>> {
+        let mut ranges = Vec::new();
+        let mut start = 0;
+        for (i, op) in self.ops.iter().enumerate() {
+            if let PathOp::MoveTo(_) = op {
+                if i > start {
+                    ranges.push(start..i);
+                }
+                start = i;
+            }
+        }
+        if start < self.ops.len() {
+            ranges.push(start..self.ops.len());
+        }
+        ranges
+    }
+
+    /// Returns the signed area enclosed by `self`. Every subpath is treated
+    /// as closed, like when filling. Subpaths that wind the same way as
+    /// `PathBuilder::rect`, clockwise when y points down, have a positive
+    /// area. Curves are integrated exactly instead of being flattened.
+    pub fn signed_area(&self) -> f32 {
+        self.subpath_ranges().into_iter().map(|range| ops_area(&self.ops[range])).sum()
+    }
+
+    /// Returns an iterator over the segments of `self`, including the lines
+    /// drawn by `Close` back to the start of each subpath, so that measuring
+    /// the segments gives the same result as `length`. Ops before the first
+    /// `MoveTo` start from their first point, like when filling.
+    pub fn segments(&self) -> PathSegments<'_> {
+        PathSegments::new(&self.ops)
+    }
+
+    /// Returns the length of `self`, including the lines drawn by `Close` but
+    /// not closing open subpaths, unlike `perimeter`. Lines and quadratic
+    /// curves are measured exactly and cubic curves are approximated to within
+    /// `tolerance`.
+    pub fn length(&self, tolerance: f32) -> f32 {
+        ops_length(&self.ops, tolerance)
+    }
+
+    /// Returns the length of the outline of the area that `self` fills. Unlike
+    /// `length`, which measures what a stroke would follow and so only counts
+    /// the lines drawn by `Close`, every subpath is treated as closed, like
+    /// when filling, so open subpaths also count the line back to where they
+    /// started. For paths whose subpaths are all closed the two are the same.
+    /// Cubic curves are approximated to within `tolerance`.
+    pub fn perimeter(&self, tolerance: f32) -> f32 {
+        self.subpath_ranges().into_iter().map(|range| {
+            let mut segments = PathSegments::new(&self.ops[range]).peekable();
+            let start = match segments.peek() {
+                Some(segment) => segment.from(),
+                None => return 0.,
+            };
+            let mut length = 0.;
+            let mut last = None;
+            for segment in segments {
+                length += segment.length(tolerance);
+                last = Some(segment);
+            }
+            match last {
+                Some(PathSegment::Close { .. }) | None => length,
+                Some(segment) => length + (start - segment.to()).length(),
+            }
+        }).sum()
+    }
+
+    /// Returns a copy of `self` without the subpaths that are too small to
+    /// matter, like the specks left behind when tracing a bitmap. Closed
+    /// subpaths are removed when the absolute value of their area is less
+    /// than `min_area`. Open subpaths don't enclose anything so they're
+    /// removed when they're shorter than `min_area.sqrt()`, the side of a
+    /// square with that area. A `min_area` of zero or less, or one that
+    /// isn't finite, removes nothing.
+    pub fn remove_tiny_subpaths(&self, min_area: f32) -> Path {
+        if min_area <= 0. || !min_area.is_finite() {
+            return self.clone();
+        }
+        let min_length = min_area.sqrt();
+        // lyon needs a tolerance above zero to measure curves
+        let tolerance = (min_length * 0.01).max(1e-4);
+        let mut ops = Vec::with_capacity(self.ops.len());
+        for range in self.subpath_ranges() {
+            let subpath = &self.ops[range];
+            let closed = subpath.contains(&PathOp::Close);
+            let keep = if closed {
+                ops_area(subpath).abs() >= min_area
+            } else {
+                ops_length(subpath, tolerance) >= min_length
+            };
+            if keep {
+                ops.extend_from_slice(subpath);
+            }
+        }
+        Path { ops, winding: self.winding }
+    }
+
+    /// Returns a copy of `self` with the corners between lines rounded off by
+    /// circular arcs of `radius`. The lines on either side of each corner are
+    /// trimmed back to where the arc meets them. Where a line is too short for
+    /// the arcs at both of its ends, the radius is reduced so that each arc
+    /// uses at most half of it.
+    ///
+    /// The ends of open subpaths aren't corners so they're left alone, as are
+    /// subpaths that contain curves.
+    pub fn round_corners(&self, radius: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        for range in self.subpath_ranges() {
+            let subpath = &self.ops[range];
+            let (points, closed) = match polygon_points(subpath) {
+                Some(polygon) => polygon,
+                None => {
+                    pb.path.ops.extend_from_slice(subpath);
+                    continue;
+                }
+            };
+            let n = points.len();
+            let mut fillets: Vec<Option<Arc<f32>>> = vec![None; n];
+            let corners = if closed { 0..n } else { 1..n - 1 };
+            for i in corners {
+                fillets[i] = fillet(points[(i + n - 1) % n], points[i], points[(i + 1) % n], radius, 0.5);
+            }
+            add_filleted_polygon(&mut pb, &points, closed, &fillets);
+        }
+        let mut path = pb.finish();
+        path.winding = self.winding;
+        path
+    }
+
+    /// Returns a copy of `self` with only one corner rounded off by a
+    /// circular arc of `radius`, like `round_corners` does for all of them.
+    /// `vertex` counts the points of subpath number `subpath`, starting from
+    /// its `MoveTo`. Since the neighbouring corners stay sharp the radius is
+    /// only reduced when the arc would need more than the whole of either
+    /// adjacent line.
+    ///
+    /// `self` is returned unchanged if there's no such vertex, if it's the end
+    /// of an open subpath or if the subpath contains curves.
+    pub fn fillet(&self, subpath: usize, vertex: usize, radius: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        for (index, range) in self.subpath_ranges().into_iter().enumerate() {
+            let ops = &self.ops[range];
+            let polygon = if index == subpath { polygon_points(ops) } else { None };
+            let (points, closed) = match polygon {
+                Some(polygon) => polygon,
+                None => {
+                    pb.path.ops.extend_from_slice(ops);
+                    continue;
+                }
+            };
+            let n = points.len();
+            let mut fillets: Vec<Option<Arc<f32>>> = vec![None; n];
+            if vertex < n && (closed || (vertex > 0 && vertex < n - 1)) {
+                let (prev, next) = (points[(vertex + n - 1) % n], points[(vertex + 1) % n]);
+                fillets[vertex] = fillet(prev, points[vertex], next, radius, 1.);
+            }
+            add_filleted_polygon(&mut pb, &points, closed, &fillets);
+        }
+        let mut path = pb.finish();
+        path.winding = self.winding;
+        path
+    }
+
+    /// Returns a copy of `self` with each closed subpath moved outwards, away
+    /// from the area it encloses, by `distance(index)` where `index` counts the
+    /// subpaths. Negative distances move subpaths inwards. Curves are flattened
+    /// using `tolerance` and the corners that a subpath moves away from are
+    /// rounded, so that an outward offset stays the same distance from the
+    /// subpath all the way around.
+    ///
+    /// Where moving inwards makes a subpath cross itself, like when part of it
+    /// is narrower than twice the distance, the loops that turn inside out are
+    /// trimmed off. That can split a subpath in several or remove it entirely.
+    /// Offsets of different subpaths aren't trimmed against each other and
+    /// open subpaths don't enclose anything so they're left as they are.
+    pub fn offset_subpaths_by<F: Fn(usize) -> f32>(&self, distance: F, tolerance: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        for (index, range) in self.subpath_ranges().into_iter().enumerate() {
+            let subpath = Path { ops: self.ops[range].to_vec(), winding: self.winding };
+            match subpath.flattened_subpaths(tolerance).as_slice() {
+                [(points, true)] => {
+                    for polygon in offset_polygon(points, distance(index), tolerance) {
+                        pb.move_to(polygon[0].x, polygon[0].y);
+                        for pt in &polygon[1..] {
+                            pb.line_to(pt.x, pt.y);
+                        }
+                        pb.close();
+                    }
+                }
+                _ => pb.path.ops.extend_from_slice(&subpath.ops),
+            }
+        }
+        let mut path = pb.finish();
+        path.winding = self.winding;
+        path
+    }
+
+    /// Returns a copy of `self` with the area it fills grown by `distance` in
+    /// every direction, like the shell left around a shape by a cutter of
+    /// radius `distance`. Subpaths nested inside an even number of the others
+    /// are outlines, which are moved outwards, and the rest are holes, which
+    /// are moved inwards. A negative `distance` shrinks the area instead. The
+    /// offsets are made as described for `offset_subpaths_by`.
+    pub fn offset_each_subpath(&self, distance: f32, tolerance: f32) -> Path {
+        let depths = self.nesting_depths(tolerance);
+        self.offset_subpaths_by(|i| match depths[i] {
+            Some(depth) if depth % 2 == 1 => -distance,
+            _ => distance,
+        }, tolerance)
+    }
+
+    /// Returns an approximation of the centerline of the area filled by
+    /// `self`, undoing a stroke, for example to restroke a glyph that was
+    /// imported as an outline at a different weight.
+    ///
+    /// The area is sampled on a grid of cells `tolerance` across, or larger
+    /// if that would take more than 1024 of them across `self`, and thinned
+    /// down to lines of single cells, which are then traced and simplified to
+    /// within `tolerance`. Short branches towards corners are trimmed off.
+    /// This is only meant for simple shapes made of strokes of roughly even
+    /// width. The lines may be up to a cell away from the true centerline and
+    /// they end about half of the width of the stroke short of its ends, so
+    /// that restroking them with square caps covers the original area.
+    pub fn centerline_approx(&self, tolerance: f32) -> Path {
+        let mut pb = PathBuilder::new();
+        let bounds = match ops_bounds(&self.ops) {
+            Some(bounds) => bounds,
+            None => return pb.finish(),
+        };
+        let size = bounds.max - bounds.min;
+        let cell = tolerance.max(size.x.max(size.y) / 1024.);
+        if !cell.is_finite() || cell <= 0. {
+            return pb.finish();
+        }
+        // a cell of padding on every side keeps the area off the edges
+        let origin = bounds.min - Vector::new(cell, cell);
+        let mut grid = CellGrid::fill(self, origin, cell, (size.x / cell) as usize + 3, (size.y / cell) as usize + 3);
+        let distances = grid.distances();
+        grid.thin();
+        grid.prune(&distances);
+
+        let center = |i: usize| {
+            let (x, y) = (i % grid.width, i / grid.width);
+            origin + Vector::new(x as f32 + 0.5, y as f32 + 0.5) * cell
+        };
+        for (cells, closed) in grid.trace() {
+            let points: Vec<Point> = cells.into_iter().map(center).collect();
+            let mut simplified = vec![points[0]];
+            simplify_run(&points, tolerance, &mut simplified);
+            if closed {
+                simplified.pop();
+            }
+            pb.move_to(simplified[0].x, simplified[0].y);
+            for pt in &simplified[1..] {
+                pb.line_to(pt.x, pt.y);
+            }
+            if closed {
+                pb.close();
+            }
+        }
+        pb.finish()
+    }
+}
+
+/// Returns the distinct points of `subpath` and whether it's closed, or None
+/// if it contains curves or has fewer than three points.
+fn polygon_points(subpath: &[PathOp]) -> Option<(Vec<Point>, bool)> {
+    let mut points: Vec<Point> = Vec::with_capacity(subpath.len());
+    let mut closed = false;
+    for (i, op) in subpath.iter().enumerate() {
+        match *op {
+            PathOp::MoveTo(pt) | PathOp::LineTo(pt) => {
+                if points.last() != Some(&pt) {
+                    points.push(pt);
+                }
+            }
+            PathOp::Close if i == subpath.len() - 1 => closed = true,
+            _ => return None,
+        }
+    }
+    if closed && points.len() > 1 && points.first() == points.last() {
+        points.pop();
+    }
+    if points.len() < 3 {
+        return None;
+    }
+    Some((points, closed))
+}
+
+/// Adds the polygon through `points` to `pb`, replacing the corners that have
+/// an arc in `fillets` by that arc.
+fn add_filleted_polygon(pb: &mut PathBuilder, points: &[Point], closed: bool, fillets: &[Option<Arc<f32>>]) {
+    let add_arc = |pb: &mut PathBuilder, a: &Arc<f32>| {
+        pb.arc(a.center.x, a.center.y, a.radii.x, a.start_angle.radians, a.sweep_angle.radians);
+    };
+
+    match fillets[0] {
+        Some(a) if closed => pb.move_to(a.to().x, a.to().y),
+        _ => pb.move_to(points[0].x, points[0].y),
+    }
+    for (i, &pt) in points.iter().enumerate().skip(1) {
+        match fillets[i] {
+            Some(ref a) => add_arc(pb, a),
+            None => pb.line_to(pt.x, pt.y),
+        }
+    }
+    if closed {
+        if let Some(ref a) = fillets[0] {
+            add_arc(pb, a);
+        }
+        pb.close();
+    }
+}
+
+/// Offsets the closed polygon through `points` away from the area it encloses
+/// by `distance`, rounding the corners that it moves away from to within
+/// `tolerance`. The loops where the offset crosses itself are kept only if
+/// they wind the same way as the polygon and stay `distance` from it, which
+/// may leave none at all. Polygons that don't enclose any area are returned
+/// as they are.
+fn offset_polygon(points: &[Point], distance: f32, tolerance: f32) -> Vec<Vec<Point>> {
+    let n = points.len();
+    let area = polygon_area(points);
+    if n < 3 || area == 0. || !area.is_finite() || distance == 0. || !distance.is_finite() {
+        return vec![points.to_vec()];
+    }
+    let orientation = area.signum();
+    // the outward normal of the edge from each point to the next
+    let normals: Option<Vec<UnitVector>> = (0..n).map(|i| {
+        let d = points[(i + 1) % n] - points[i];
+        UnitVector::new(Vector::new(d.y, -d.x) * orientation)
+    }).collect();
+    let normals = match normals {
+        Some(normals) => normals,
+        None => return vec![points.to_vec()],
+    };
+
+    let mut offset: Vec<Point> = Vec::with_capacity(n * 2);
+    for (i, &pt) in points.iter().enumerate() {
+        let (a, b) = (normals[(i + n - 1) % n], normals[i]);
+        offset.push(pt + a * distance);
+        // the offset edges leave a gap around corners that turn away from
+        // them and cross around the others, which trimming takes care of
+        if a.cross(*b) * orientation * distance > 0. {
+            let (from, to) = if distance > 0. { (a, b) } else { (a.flip(), b.flip()) };
+            offset.extend(arc_points(pt, distance.abs(), from, to, tolerance));
+        } else {
+            offset.push(pt + b * distance);
+        }
+    }
+    offset.dedup();
+    if offset.len() > 1 && offset.first() == offset.last() {
+        offset.pop();
+    }
+
+    let min_distance = distance.abs() - tolerance.max(distance.abs() * 1e-3);
+    split_self_intersections(offset).into_iter().filter(|polygon| {
+        polygon_area(polygon) * orientation > 0. && polygon.iter().all(|&p| {
+            (0..n).all(|i| distance_to_segment(p, points[i], points[(i + 1) % n]) >= min_distance)
+        })
+    }).collect()
+}
+
+/// Splits the closed polygon through `points` into loops that don't cross
+/// themselves, by cutting it in two wherever two of its edges cross.
+fn split_self_intersections(points: Vec<Point>) -> Vec<Vec<Point>> {
+    let mut loops = Vec::new();
+    let mut pending = vec![points];
+    'pending: while let Some(points) = pending.pop() {
+        let n = points.len();
+        for i in 0..n {
+            // the edges next to edge i share a point with it
+            let last = if i == 0 { n - 1 } else { n };
+            for j in i + 2..last {
+                let (p0, p1) = (points[i], points[i + 1]);
+                let (q0, q1) = (points[j], points[(j + 1) % n]);
+                let (r, s) = (p1 - p0, q1 - q0);
+                let denom = r.cross(s);
+                if denom == 0. {
+                    continue;
+                }
+                let t = (q0 - p0).cross(s) / denom;
+                let u = (q0 - p0).cross(r) / denom;
+                if t > 0. && t < 1. && u > 0. && u < 1. {
+                    let x = p0 + r * t;
+                    let mut a = vec![x];
+                    a.extend_from_slice(&points[i + 1..=j]);
+                    let mut b = vec![x];
+                    b.extend_from_slice(&points[j + 1..]);
+                    b.extend_from_slice(&points[..=i]);
+                    pending.push(a);
+                    pending.push(b);
+                    continue 'pending;
+                }
+            }
+        }
+        if n >= 3 {
+            loops.push(points);
+        }
+    }
+    loops
+}
+
+/// Returns how many groups the neighbours of a cell that are `inside` make
+/// when the cell itself isn't, counting the neighbours that touch, even just
+/// at a corner, as part of the same group.
+fn ring_components(inside: &[bool; 8]) -> usize {
+    let touch = |a: usize, b: usize| {
+        let (pa, pb) = (NEIGHBOURS[a], NEIGHBOURS[b]);
+        (pa.0 - pb.0).abs() <= 1 && (pa.1 - pb.1).abs() <= 1
+    };
+    let mut group = [usize::MAX; 8];
+    let mut count = 0;
+    for start in 0..8 {
+        if !inside[start] || group[start] != usize::MAX {
+            continue;
+        }
+        let mut pending = vec![start];
+        group[start] = count;
+        while let Some(a) = pending.pop() {
+            for b in 0..8 {
+                if inside[b] && group[b] == usize::MAX && touch(a, b) {
+                    group[b] = count;
+                    pending.push(b);
+                }
+            }
+        }
+        count += 1;
+    }
+    count
+}
+
+/// A grid of cells that are either inside or outside of an area, used to
+/// find its centerline.
+struct CellGrid {
+    width: usize,
+    height: usize,
+    cells: Vec<bool>,
+}
+
+/// The offsets of the neighbours of a cell, clockwise from the one above.
+const NEIGHBOURS: [(isize, isize); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+impl CellGrid {
+    /// Samples the area filled by `path` at the centers of the cells of a grid
+    /// of `width` by `height` cells of size `cell` starting at `origin`.
+    fn fill(path: &Path, origin: Point, cell: f32, width: usize, height: usize) -> CellGrid {
+        let mut cells = vec![false; width * height];
+        let polygons = path.flattened_subpaths(cell / 4.);
+        for row in 0..height {
+            let y = origin.y + (row as f32 + 0.5) * cell;
+            // where the edges cross the middle of the row and which way
+            let mut crossings: Vec<(f32, i32)> = Vec::new();
+            for (points, _) in &polygons {
+                for (i, &p) in points.iter().enumerate() {
+                    let q = points[(i + 1) % points.len()];
+                    if (p.y <= y) != (q.y <= y) {
+                        let x = p.x + (y - p.y) / (q.y - p.y) * (q.x - p.x);
+                        crossings.push((x, if q.y > p.y { 1 } else { -1 }));
+                    }
+                }
+            }
+            crossings.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(Ordering::Equal));
+            let mut winding = 0;
+            for pair in crossings.windows(2) {
+                winding += pair[0].1;
+                let inside = match path.winding {
+                    Winding::EvenOdd => winding & 1 != 0,
+                    Winding::NonZero => winding != 0,
+                };
+                if inside {
+                    let first = ((pair[0].0 - origin.x) / cell - 0.5).ceil().max(0.) as usize;
+                    let last = (((pair[1].0 - origin.x) / cell - 0.5).ceil().max(0.) as usize).min(width);
+                    for col in first..last {
+                        cells[row * width + col] = true;
+                    }
+                }
+            }
+        }
+        CellGrid { width, height, cells }
+    }
+
+    /// Returns which of the neighbours of cell `i` are inside, in the order
+    /// of `NEIGHBOURS`.
+    fn neighbours(&self, i: usize) -> [bool; 8] {
+        let mut inside = [false; 8];
+        for (k, n) in self.neighbour_indices(i).iter().enumerate() {
+            inside[k] = matches!(*n, Some(n) if self.cells[n]);
+        }
+        inside
+    }
+
+    fn neighbour_indices(&self, i: usize) -> [Option<usize>; 8] {
+        let (x, y) = ((i % self.width) as isize, (i / self.width) as isize);
+        let mut indices = [None; 8];
+        for (k, &(dx, dy)) in NEIGHBOURS.iter().enumerate() {
+            let (nx, ny) = (x + dx, y + dy);
+            if nx >= 0 && ny >= 0 && (nx as usize) < self.width && (ny as usize) < self.height {
+                indices[k] = Some(ny as usize * self.width + nx as usize);
+            }
+        }
+        indices
+    }
+
+    /// The cells next to cell `i` that are inside.
+    fn inside_neighbours(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let indices = self.neighbour_indices(i);
+        (0..8).filter_map(move |k| indices[k]).filter(move |&n| self.cells[n])
+    }
+
+    /// Returns how far the center of each cell is from the nearest cell that's
+    /// outside, in cells, using the usual two pass chamfer approximation.
+    fn distances(&self) -> Vec<f32> {
+        let diagonal = std::f32::consts::SQRT_2;
+        let mut distances: Vec<f32> = self.cells.iter().map(|&inside| if inside { f32::MAX } else { 0. }).collect();
+        let passes: [(Vec<usize>, [usize; 4]); 2] = [
+            ((0..self.cells.len()).collect(), [7, 0, 1, 6]),
+            ((0..self.cells.len()).rev().collect(), [3, 4, 5, 2]),
+        ];
+        for (order, before) in passes.iter() {
+            for &i in order {
+                let indices = self.neighbour_indices(i);
+                for &k in before {
+                    if let Some(n) = indices[k] {
+                        let step = if k % 2 == 1 { diagonal } else { 1. };
+                        distances[i] = distances[i].min(distances[n] + step);
+                    }
+                }
+            }
+        }
+        distances
+    }
+
+    /// Thins the inside cells down to lines one cell wide without splitting
+    /// them apart, using the Zhang-Suen algorithm.
+    fn thin(&mut self) {
+        loop {
+            let mut changed = false;
+            for step in 0..2 {
+                let remove: Vec<usize> = (0..self.cells.len()).filter(|&i| {
+                    if !self.cells[i] {
+                        return false;
+                    }
+                    let p = self.neighbours(i);
+                    let count = p.iter().filter(|&&inside| inside).count();
+                    let transitions = (0..8).filter(|&k| !p[k] && p[(k + 1) % 8]).count();
+                    // the first step removes cells from the bottom right and the second from the top left
+                    let kept = if step == 0 {
+                        !(p[2] && p[4] && (p[0] || p[6]))
+                    } else {
+                        !(p[0] && p[6] && (p[2] || p[4]))
+                    };
+                    (2..=6).contains(&count) && transitions == 1 && kept
+                }).collect();
+                changed |= !remove.is_empty();
+                for i in remove {
+                    self.cells[i] = false;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+        // Zhang-Suen leaves diagonal lines as staircases of cells that only
+        // touch along their sides, so remove the corners of the steps
+        // wherever the cells around them stay connected without them.
+        loop {
+            let mut changed = false;
+            for i in 0..self.cells.len() {
+                if !self.cells[i] {
+                    continue;
+                }
+                let p = self.neighbours(i);
+                let corner = (0..4).any(|k| p[2 * k] && p[(2 * k + 2) % 8] && !p[2 * k + 1]);
+                if corner && p.iter().filter(|&&inside| inside).count() > 1 && ring_components(&p) == 1 {
+                    self.cells[i] = false;
+                    changed = true;
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+    }
+
+    /// Removes the branches that run from a junction to an end in fewer
+    /// cells than twice the distance of the junction from the outside. These
+    /// are left behind by the thinning in the corners of the area.
+    fn prune(&mut self, distances: &[f32]) {
+        for (cells, _) in self.trace() {
+            let (first, last) = (cells[0], cells[cells.len() - 1]);
+            let junction = match (self.degree(first), self.degree(last)) {
+                (1, d) if d > 2 => last,
+                (d, 1) if d > 2 => first,
+                _ => continue,
+            };
+            let length: f32 = cells.windows(2).map(|pair| {
+                if pair[0] % self.width == pair[1] % self.width || pair[0] / self.width == pair[1] / self.width {
+                    1.
+                } else {
+                    std::f32::consts::SQRT_2
+                }
+            }).sum();
+            if length < 2. * distances[junction] {
+                for &i in &cells {
+                    if i != junction {
+                        self.cells[i] = false;
+                    }
+                }
+            }
+        }
+    }
+
+    fn degree(&self, i: usize) -> usize {
+        self.inside_neighbours(i).count()
+    }
+
+    /// Follows the lines of inside cells from end to end, and around loops,
+    /// returning the cells along each one and whether it's a loop. Lines are
+    /// split where they meet others.
+    fn trace(&self) -> Vec<(Vec<usize>, bool)> {
+        let mut visited: HashSet<(usize, usize)> = HashSet::new();
+        let edge = |a: usize, b: usize| (a.min(b), a.max(b));
+        let walk = |start: usize, next: usize, visited: &mut HashSet<(usize, usize)>| {
+            let mut cells = vec![start];
+            visited.insert(edge(start, next));
+            let (mut prev, mut cur) = (start, next);
+            loop {
+                cells.push(cur);
+                if cur == start || self.degree(cur) != 2 {
+                    break;
+                }
+                match self.inside_neighbours(cur).find(|&n| n != prev && !visited.contains(&edge(cur, n))) {
+                    Some(n) => {
+                        visited.insert(edge(cur, n));
+                        prev = cur;
+                        cur = n;
+                    }
+                    None => break,
+                }
+            }
+            cells
+        };
+
+        let mut lines = Vec::new();
+        // lines from ends and junctions first, so that only loops are left
+        for loops in [false, true] {
+            for i in 0..self.cells.len() {
+                if !self.cells[i] || (self.degree(i) == 2) != loops {
+                    continue;
+                }
+                let neighbours: Vec<usize> = self.inside_neighbours(i).collect();
+                for n in neighbours {
+                    if !visited.contains(&edge(i, n)) {
+                        let cells = walk(i, n, &mut visited);
+                        let closed = cells.len() > 2 && cells.last() == Some(&i);
+                        lines.push((cells, closed));
+                    }
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Finds the arc of `radius` that rounds off the corner at `pt` between the
+/// lines from `prev` and to `next`. The radius is reduced if the arc would
+/// use more than `max_fraction` of either line. Returns None if the lines
+/// continue straight on or turn straight back.
+fn fillet(prev: Point, pt: Point, next: Point, radius: f32, max_fraction: f32) -> Option<Arc<f32>> {
+    let (d1, d2) = (pt - prev, next - pt);
+    let (len1, len2) = (d1.length(), d2.length());
+    let (d1, d2) = (d1 / len1, d2 / len2);
+    let turn = d1.cross(d2);
+    // the angle the path turns through at the corner
+    let theta = turn.atan2(d1.dot(d2));
+    if radius <= 0. || theta.abs() < 1e-4 || theta.abs() > std::f32::consts::PI - 1e-4 {
+        return None;
+    }
+    let half_tan = (theta.abs() / 2.).tan();
+    // how far back along each line the arc starts
+    let trim = (radius * half_tan).min(len1 * max_fraction).min(len2 * max_fraction);
+    let radius = trim / half_tan;
+    let start = pt - d1 * trim;
+    // the center is on the inside of the turn
+    let inside = Vector::new(-d1.y, d1.x) * turn.signum();
+    let center = start + inside * radius;
+    let from = start - center;
+    Some(Arc {
+        center,
+        radii: Vector::new(radius, radius),
+        start_angle: Angle::radians(from.y.atan2(from.x)),
+        sweep_angle: Angle::radians(theta),
+        x_rotation: Angle::zero(),
+    })
+}
+
+/// Calls `f` with the point where the segments `p0`-`p1` and `q0`-`q1`
+/// intersect or, if they overlap along a line, with both ends of the overlap.
+pub(crate) fn segment_intersection<F: FnMut(Point)>(p0: Point, p1: Point, q0: Point, q1: Point, f: &mut F) {
+    let r = p1 - p0;
+    let s = q1 - q0;
+    let qp = q0 - p0;
+    let denom = r.cross(s);
+    // a relative epsilon so that the test doesn't depend on the scale of the segments
+    let epsilon = 1e-6 * r.length() * s.length();
+    if denom.abs() > epsilon {
+        let t = qp.cross(s) / denom;
+        let u = qp.cross(r) / denom;
+        if (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u) {
+            f(p0 + r * t);
+        }
+        return;
+    }
+
+    // the segments are parallel so they can only meet if they're on the same line
+    if qp.cross(r).abs() > 1e-6 * r.length() * qp.length() {
+        return;
+    }
+    let rr = r.dot(r);
+    let t0 = qp.dot(r) / rr;
+    let t1 = t0 + s.dot(r) / rr;
+    let start = t0.min(t1).max(0.);
+    let end = t0.max(t1).min(1.);
+    if start <= end {
+        f(p0 + r * start);
+        f(p0 + r * end);
+    }
+}
+
+fn cross(a: Point, b: Point) -> f32 {
+    a.x * b.y - a.y * b.x
+}
+
+/* The tight bounds of the points drawn by `ops`, or `None` if there aren't any */
+fn ops_bounds(ops: &[PathOp]) -> Option<Box2D<f32>> {
+    let mut cur_pt = None;
+    let mut bounds: Option<Box2D<f32>> = None;
+    for op in ops {
+        let b = match *op {
+            PathOp::MoveTo(pt) | PathOp::LineTo(pt) => {
+                cur_pt = Some(pt);
+                Box2D::new(pt, pt)
+            }
+            PathOp::Close => continue,
+            PathOp::QuadTo(cpt, pt) => {
+                let c = QuadraticBezierSegment {
+                    from: cur_pt.unwrap_or(cpt),
+                    ctrl: cpt,
+                    to: pt,
+                };
+                cur_pt = Some(pt);
+                c.bounding_box()
+            }
+            PathOp::CubicTo(cpt1, cpt2, pt) => {
+                let c = CubicBezierSegment {
+                    from: cur_pt.unwrap_or(cpt1),
+                    ctrl1: cpt1,
+                    ctrl2: cpt2,
+                    to: pt,
+                };
+                cur_pt = Some(pt);
+                c.bounding_box()
+            }
+        };
+        // Box2D::union ignores empty boxes, like the ones around single points
+        bounds = Some(bounds.map_or(b, |bounds| Box2D::new(bounds.min.min(b.min), bounds.max.max(b.max))));
+    }
+    bounds
+}
+
+/// The signed area of a single subpath. This is Green's theorem applied to
+/// each segment with the integrals for curves worked out from their
+/// Bernstein polynomials.
+fn ops_area(ops: &[PathOp]) -> f32 {
+    let mut area = 0.;
+    let mut start: Option<Point> = None;
+    let mut cur_pt: Option<Point> = None;
+    for op in ops {
+        match *op {
+            PathOp::MoveTo(pt) => {
+                start = Some(pt);
+                cur_pt = Some(pt);
+            }
+            PathOp::LineTo(p1) => {
+                let p0 = cur_pt.unwrap_or(p1);
+                start.get_or_insert(p0);
+                area += cross(p0, p1) / 2.;
+                cur_pt = Some(p1);
+            }
+            PathOp::QuadTo(p1, p2) => {
+                let p0 = cur_pt.unwrap_or(p1);
+                start.get_or_insert(p0);
+                area += (cross(p0, p1) + cross(p1, p2)) / 3. + cross(p0, p2) / 6.;
+                cur_pt = Some(p2);
+            }
+            PathOp::CubicTo(p1, p2, p3) => {
+                let p0 = cur_pt.unwrap_or(p1);
+                start.get_or_insert(p0);
+                area += (cross(p0, p1) + cross(p2, p3)) * (3. / 10.) +
+                    (cross(p0, p2) + cross(p1, p2) + cross(p1, p3)) * (3. / 20.) +
+                    cross(p0, p3) / 20.;
+                cur_pt = Some(p3);
+            }
+            PathOp::Close => {
+                if let (Some(p0), Some(p1)) = (cur_pt, start) {
+                    area += cross(p0, p1) / 2.;
+                }
+                cur_pt = start;
+            }
+        }
+    }
+    // the implicit closing line
+    if let (Some(p0), Some(p1)) = (cur_pt, start) {
+        area += cross(p0, p1) / 2.;
+    }
+    area
+}
+
+fn ops_length(ops: &[PathOp], tolerance: f32) -> f32 {
+    PathSegments::new(ops).map(|segment| segment.length(tolerance)).sum()
+}
+
+/// Reverses the direction of `ops`, which make up a single subpath. A
+/// `Close` in the middle ends a contour and the next one continues from the
+/// start point, so each contour is reversed separately and they're emitted
+/// in reverse order. Closed contours keep their start point.
+fn reverse_ops(ops: &[PathOp]) -> Vec<PathOp> {
+    struct Contour {
+        start: Point,
+        segments: Vec<PathOp>,
+        ends: Vec<Point>,
+        closed: bool,
+    }
+
+    let mut contours: Vec<Contour> = Vec::new();
+    let mut start: Option<Point> = None;
+    let mut current: Option<Contour> = None;
+    for op in ops {
+        let (first, end) = match *op {
+            PathOp::MoveTo(pt) => {
+                contours.extend(current.take());
+                start = Some(pt);
+                current = Some(Contour { start: pt, segments: Vec::new(), ends: Vec::new(), closed: false });
+                continue;
+            }
+            PathOp::Close => {
+                if let Some(mut contour) = current.take() {
+                    contour.closed = true;
+                    contours.push(contour);
+                }
+                continue;
+            }
+            PathOp::LineTo(pt) => (pt, pt),
+            PathOp::QuadTo(cpt, pt) => (cpt, pt),
+            PathOp::CubicTo(cpt, _, pt) => (cpt, pt),
+        };
+        let contour = current.get_or_insert_with(|| {
+            // Like when filling, an op without a current point starts a
+            // new subpath at its first point
+            let start = *start.get_or_insert(first);
+            Contour { start, segments: Vec::new(), ends: Vec::new(), closed: false }
+        });
+        contour.segments.push(*op);
+        contour.ends.push(end);
+    }
+    contours.extend(current);
+
+    let mut reversed = Vec::with_capacity(ops.len());
+    for contour in contours.iter().rev() {
+        let last = contour.ends.last().cloned().unwrap_or(contour.start);
+        let reverse = |i: usize| {
+            let from = if i == 0 { contour.start } else { contour.ends[i - 1] };
+            match contour.segments[i] {
+                PathOp::QuadTo(cpt, _) => PathOp::QuadTo(cpt, from),
+                PathOp::CubicTo(cpt1, cpt2, _) => PathOp::CubicTo(cpt2, cpt1, from),
+                _ => PathOp::LineTo(from),
+            }
+        };
+        if contour.closed {
+            // The implicit closing line becomes the first segment and the
+            // first segment, if it's a line, becomes the implicit closing line
+            reversed.push(PathOp::MoveTo(contour.start));
+            if last != contour.start {
+                reversed.push(PathOp::LineTo(last));
+            }
+            for i in (1..contour.segments.len()).rev() {
+                reversed.push(reverse(i));
+            }
+            match contour.segments.first() {
+                Some(PathOp::LineTo(_)) | None => {}
+                Some(_) => reversed.push(reverse(0)),
+            }
+            reversed.push(PathOp::Close);
+        } else {
+            reversed.push(PathOp::MoveTo(last));
+            for i in (0..contour.segments.len()).rev() {
+                reversed.push(reverse(i));
+            }
+        }
+    }
+    reversed
+}
+
+/// Returns the parameters in (0, 1), in increasing order, where `c` needs to be split
+/// so that flattening it doesn't cut across its cusps, inflections or loops.
+///
+/// Inflections come from lyon. Cusps and loops are where the curve meets itself:
+/// writing the curve as `a t³ + b t² + c t + d`, two parameters `s` and `t` give the
+/// same point when `a (u² - v) + b u + c = 0`, with `u = s + t` and `v = s t`. That's
+/// linear in `v` so crossing with `a` gives `u`, and then `s` and `t` are the roots of
+/// `x² - u x + v`. A double root is a cusp.
+fn cubic_split_ts(c: &CubicBezierSegment<f32>) -> Vec<f32> {
+    let mut ts = Vec::new();
+    c.for_each_inflection_t(&mut |t| ts.push(t));
+
+    let (p0, p1, p2, p3) = (c.from.to_vector(), c.ctrl1.to_vector(), c.ctrl2.to_vector(), c.to.to_vector());
+    let a = p3 - p2 * 3. + p1 * 3. - p0;
+    let b = (p2 - p1 * 2. + p0) * 3.;
+    let c1 = (p1 - p0) * 3.;
+    let ab = a.cross(b);
+    let aa = a.square_length();
+    if ab != 0. && aa != 0. {
+        let u = -a.cross(c1) / ab;
+        let v = u * u + a.dot(b * u + c1) / aa;
+        let discriminant = u * u - 4. * v;
+        // The parameters are unitless so a fixed threshold catches cusps
+        // that rounding has turned into tiny loops or near misses.
+        const CUSP_TOLERANCE: f32 = 1e-4;
+        if discriminant.abs() <= CUSP_TOLERANCE {
+            ts.push(u / 2.);
+        } else if discriminant > 0. {
+            let root = discriminant.sqrt();
+            let (s, t) = ((u - root) / 2., (u + root) / 2.);
+            if s > 0. && t < 1. {
+                // a loop, split in the middle of it as well so that each piece
+                // turns through less than a full circle
+                ts.extend_from_slice(&[s, u / 2., t]);
+            }
+        }
+    }
+
+    ts.retain(|&t| t > 0. && t < 1.);
+    ts.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+    ts.dedup();
+    ts
+}
+
+/// Flattens `c` to within `tolerance`, calling `line_to` with the end of each line.
+/// The curve is split at its cusps, inflections and loops first so that each line
+/// stays close to the curve around them.
+pub(crate) fn flatten_cubic(c: &CubicBezierSegment<f32>, tolerance: f32, line_to: &mut dyn FnMut(Point)) {
+    let mut from = c.from;
+    let mut t0 = 0.;
+    for t1 in cubic_split_ts(c).into_iter().chain(Some(1.)) {
+        let mut piece = c.split_range(t0..t1);
+        // keep the pieces connected exactly and ending at the end of `c`
+        piece.from = from;
+        if t1 == 1. {
+            piece.to = c.to;
+        }
+        for pt in piece.flattened(tolerance) {
+            line_to(pt);
+        }
+        from = piece.to;
+        t0 = t1;
+    }
+}
+
+/// Adds the points of the polyline `points` that the Ramer–Douglas–Peucker
+/// algorithm keeps for `tolerance` to `out`, except for the first one.
+fn simplify_run(points: &[Point], tolerance: f32, out: &mut Vec<Point>) {
+    let (first, last) = (points[0], points[points.len() - 1]);
+    let farthest = (1..points.len().saturating_sub(1))
+        .map(|i| (i, distance_to_segment(points[i], first, last)))
+        .fold(None, |best: Option<(usize, f32)>, (i, d)| match best {
+            Some((_, best_d)) if best_d >= d => best,
+            _ => Some((i, d)),
+        });
+    match farthest {
+        Some((i, d)) if d > tolerance => {
+            simplify_run(&points[..=i], tolerance, out);
+            simplify_run(&points[i..], tolerance, out);
+        }
+        _ => out.push(last),
+    }
+}
+
+/* Replaces each run of consecutive `LineTo`s in `ops` with a single line when every point
+ * along the run is within `epsilon` of that line. */
+fn merge_collinear_lines(ops: &[PathOp], epsilon: f32) -> Vec<PathOp> {
+    let mut merged: Vec<PathOp> = Vec::with_capacity(ops.len());
+    // where the last line in `merged` starts and the points in the middle of it
+    let mut line_start = None;
+    let mut run = Vec::new();
+    let mut start = None;
+    let mut cur_pt = None;
+    for op in ops {
+        match *op {
+            PathOp::LineTo(pt) => {
+                if let (Some(PathOp::LineTo(last)), Some(from)) = (merged.last().copied(), line_start) {
+                    run.push(last);
+                    if run.iter().all(|p| distance_to_segment(*p, from, pt) <= epsilon) {
+                        merged.pop();
+                        merged.push(*op);
+                        cur_pt = Some(pt);
+                        continue;
+                    }
+                }
+                run.clear();
+                line_start = cur_pt;
+                start.get_or_insert(pt);
+                cur_pt = Some(pt);
+            }
+            PathOp::MoveTo(pt) => {
+                run.clear();
+                line_start = None;
+                start = Some(pt);
+                cur_pt = Some(pt);
+            }
+            PathOp::QuadTo(_, pt) | PathOp::CubicTo(_, _, pt) => {
+                run.clear();
+                line_start = None;
+                cur_pt = Some(pt);
+            }
+            PathOp::Close => {
+                run.clear();
+                line_start = None;
+                cur_pt = start;
+            }
+        }
+        merged.push(*op);
+    }
+    merged
+}
+
+pub(crate) fn distance_to_segment(pt: Point, p1: Point, p2: Point) -> f32 {
+    let d = p2 - p1;
+    let len2 = d.square_length();
+    let t = if len2 > 0. { ((pt - p1).dot(d) / len2).clamp(0., 1.) } else { 0. };
+    (pt - (p1 + d * t)).length()
+}
+
+/// A segment of a `Path` along with the point that it starts from.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathSegment {
+    Line { from: Point, to: Point },
+    Quad { from: Point, ctrl: Point, to: Point },
+    Cubic { from: Point, ctrl1: Point, ctrl2: Point, to: Point },
+    /// The straight line that `PathOp::Close` draws back to the start of the
+    /// subpath. It has no length when the subpath already ended there.
+    Close { from: Point, to: Point },
+}
+
+impl PathSegment {
+    pub fn from(&self) -> Point {
+        match *self {
+            PathSegment::Line { from, .. }
+            | PathSegment::Quad { from, .. }
+            | PathSegment::Cubic { from, .. }
+            | PathSegment::Close { from, .. } => from,
+        }
+    }
+
+    pub fn to(&self) -> Point {
+        match *self {
+            PathSegment::Line { to, .. }
+            | PathSegment::Quad { to, .. }
+            | PathSegment::Cubic { to, .. }
+            | PathSegment::Close { to, .. } => to,
+        }
+    }
+
+    /// Returns the point at `t` along the segment, where 0 is `from` and 1 is
+    /// `to`. Curves are evaluated exactly from their polynomials.
+    pub fn eval(&self, t: f32) -> Point {
+        match *self {
+            PathSegment::Line { from, to } | PathSegment::Close { from, to } => from.lerp(to, t),
+            PathSegment::Quad { from, ctrl, to } => QuadraticBezierSegment { from, ctrl, to }.sample(t),
+            PathSegment::Cubic { from, ctrl1, ctrl2, to } => CubicBezierSegment { from, ctrl1, ctrl2, to }.sample(t),
+        }
+    }
+
+    /// Returns the derivative of `eval` at `t`, which points along the segment
+    /// and is as long as the speed at which `eval` moves as `t` increases.
+    pub fn deriv(&self, t: f32) -> Vector {
+        match *self {
+            PathSegment::Line { from, to } | PathSegment::Close { from, to } => to - from,
+            PathSegment::Quad { from, ctrl, to } => QuadraticBezierSegment { from, ctrl, to }.derivative(t),
+            PathSegment::Cubic { from, ctrl1, ctrl2, to } => CubicBezierSegment { from, ctrl1, ctrl2, to }.derivative(t),
+        }
+    }
+
+    /// Returns the length of the segment. Cubic curves are approximated to
+    /// within `tolerance` and everything else is measured exactly.
+    pub fn length(&self, tolerance: f32) -> f32 {
+        match *self {
+            PathSegment::Line { from, to } | PathSegment::Close { from, to } => (to - from).length(),
+            PathSegment::Quad { from, ctrl, to } => QuadraticBezierSegment { from, ctrl, to }.length(),
+            PathSegment::Cubic { from, ctrl1, ctrl2, to } => {
+                CubicBezierSegment { from, ctrl1, ctrl2, to }.approximate_length(tolerance)
+            }
+        }
+    }
+}
+
+/// An iterator over the segments of a `Path`, returned by `Path::segments`.
+#[derive(Clone, Debug)]
+pub struct PathSegments<'a> {
+    ops: std::slice::Iter<'a, PathOp>,
+    start: Option<Point>,
+    cur_pt: Option<Point>,
+}
+
+impl<'a> PathSegments<'a> {
+    fn new(ops: &'a [PathOp]) -> PathSegments<'a> {
+        PathSegments { ops: ops.iter(), start: None, cur_pt: None }
+    }
+}
+
+impl<'a> Iterator for PathSegments<'a> {
+    type Item = PathSegment;
+
+    fn next(&mut self) -> Option<PathSegment> {
+        for op in &mut self.ops {
+            let segment = match *op {
+                PathOp::MoveTo(pt) => {
+                    self.start = Some(pt);
+                    self.cur_pt = Some(pt);
+                    continue;
+                }
+                PathOp::LineTo(to) => PathSegment::Line { from: self.cur_pt.unwrap_or(to), to },
+                PathOp::QuadTo(ctrl, to) => PathSegment::Quad { from: self.cur_pt.unwrap_or(ctrl), ctrl, to },
+                PathOp::CubicTo(ctrl1, ctrl2, to) => {
+                    PathSegment::Cubic { from: self.cur_pt.unwrap_or(ctrl1), ctrl1, ctrl2, to }
+                }
+                PathOp::Close => match (self.cur_pt, self.start) {
+                    (Some(from), Some(to)) => PathSegment::Close { from, to },
+                    _ => continue,
+                },
+            };
+            self.start.get_or_insert(segment.from());
+            self.cur_pt = Some(segment.to());
+            return Some(segment);
+        }
+        None
+    }
+}
+
+/// A `Path` that has been flattened once with a fixed tolerance so that it
+/// can be stroked or filled repeatedly without flattening it again.
+///
+/// This trades memory for time: the flattened path usually has many more
+/// ops than the original, so it's only worth keeping around when the same
+/// path is drawn many times at the same scale.
+#[derive(Clone, PartialEq, Debug)]
+pub struct FlattenedPath {
+    path: Path,
+    tolerance: f32,
+}
+
+impl FlattenedPath {
+    /// Flattens `path` using `tolerance`
+    pub fn new(path: &Path, tolerance: f32) -> FlattenedPath {
+        FlattenedPath {
+            path: path.flatten(tolerance),
+            tolerance,
+        }
+    }
+
+    /// The tolerance that the path was flattened with
+    pub fn tolerance(&self) -> f32 {
+        self.tolerance
+    }
+
+    /// The flattened path. Stroking it with `DrawTarget::stroke` or
+    /// `stroke_to_path` won't flatten it again.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the flattened path without copying it
+    pub fn into_path(self) -> Path {
+        self.path
+    }
+}
+
+/// The number of ops of each kind in a path, from `Path::count_ops`
+#[derive(Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub struct OpCounts {
+    pub moves: usize,
+    pub lines: usize,
+    pub quads: usize,
+    pub cubics: usize,
+    pub closes: usize,
+}
+
+impl OpCounts {
+    /// Returns the number of quadratic and cubic curves
+    pub fn curves(&self) -> usize {
+        self.quads + self.cubics
+    }
+}
+
+/// The reasons a path can't be built, the counterpart of `StrokeError` for
+/// the functions that make paths:
+///
+/// * `PathBuilder::try_extend_from_slice` returns `MissingMoveTo` and
+///   `NonFiniteCoordinate`
+/// * `PathBuilder::from_svg_points` returns `InvalidNumber` and
+///   `OddNumberOfCoordinates`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PathError {
+    /// The ops started drawing without a current point to draw from
+    MissingMoveTo,
+    /// The op at `op_index` has a coordinate that's NaN or infinite
+    NonFiniteCoordinate { op_index: usize },
+    /// There was something other than a number or separator at this byte offset
+    InvalidNumber(usize),
+    /// The coordinates didn't pair up into points
+    OddNumberOfCoordinates,
+}
+
+impl std::fmt::Display for PathError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            PathError::MissingMoveTo => write!(f, "ops don't start with a move to"),
+            PathError::NonFiniteCoordinate { op_index } => write!(f, "non-finite coordinate in op {}", op_index),
+            PathError::InvalidNumber(offset) => write!(f, "invalid number at offset {}", offset),
+            PathError::OddNumberOfCoordinates => write!(f, "odd number of coordinates"),
+        }
+    }
+}
+
+impl std::error::Error for PathError {}
+
+/// Parses the numbers in an SVG `points` attribute. Numbers are separated by
+/// whitespace and/or a single comma, or by nothing at all when the next number
+/// can't be mistaken for part of the previous one (e.g. "1-2" or ".5.5").
+fn parse_svg_numbers(s: &str) -> Result<Vec<f32>, PathError> {
+    let bytes = s.as_bytes();
+    let mut numbers = Vec::new();
+    let mut i = 0;
+    let skip_whitespace = |i: &mut usize| {
+        while *i < bytes.len() && bytes[*i].is_ascii_whitespace() {
+            *i += 1;
+        }
+    };
+    let skip_digits = |i: &mut usize| {
+        let start = *i;
+        while *i < bytes.len() && bytes[*i].is_ascii_digit() {
+            *i += 1;
+        }
+        *i > start
+    };
+
+    skip_whitespace(&mut i);
+    while i < bytes.len() {
+        let start = i;
+        if bytes[i] == b'+' || bytes[i] == b'-' {
+            i += 1;
+        }
+        let mut has_digits = skip_digits(&mut i);
+        if i < bytes.len() && bytes[i] == b'.' {
+            i += 1;
+            has_digits |= skip_digits(&mut i);
+        }
+        if !has_digits {
+            return Err(PathError::InvalidNumber(start));
+        }
+        if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+            let mut j = i + 1;
+            if j < bytes.len() && (bytes[j] == b'+' || bytes[j] == b'-') {
+                j += 1;
+            }
+            // an 'e' that isn't followed by digits isn't part of this number
+            if skip_digits(&mut j) {
+                i = j;
+            }
+        }
+        let number = s[start..i].parse().map_err(|_| PathError::InvalidNumber(start))?;
+        numbers.push(number);
+
+        skip_whitespace(&mut i);
+        if i < bytes.len() && bytes[i] == b',' {
+            i += 1;
+            skip_whitespace(&mut i);
+            if i == bytes.len() {
+                return Err(PathError::InvalidNumber(i));
+            }
+        }
+    }
+    Ok(numbers)
+}
+
+/// A helper struct used for constructing a `Path`.
+pub struct PathBuilder {
+    path: Path,
+}
+
+impl From<Path> for PathBuilder {
+    fn from(path: Path) -> Self {
+        PathBuilder {
+            path
+        }
+    }
+}
+
+impl PathBuilder {
+    pub fn new() -> PathBuilder {
+        PathBuilder {
+            path: Path {
+                ops: Vec::new(),
+                winding: Winding::NonZero,
+            },
+        }
+    }
+
+    /// Creates a builder with room for `capacity` ops before it needs to
+    /// reallocate. Every call to `move_to`, `line_to`, `quad_to`, `cubic_to`
+    /// and `close` adds a single op no matter how many coordinates it takes,
+    /// so a polyline of n points needs n ops, or n + 1 if it's closed. A
+    /// `rect` adds 5 ops.
+    pub fn with_capacity(capacity: usize) -> PathBuilder {
+        PathBuilder {
+            path: Path {
+                ops: Vec::with_capacity(capacity),
+                winding: Winding::NonZero,
+            },
+        }
+    }
+
+    /// Reserves room for at least `additional` more ops. See `with_capacity`
+    /// for how many ops each method adds.
+    pub fn reserve(&mut self, additional: usize) {
+        self.path.ops.reserve(additional)
+    }
+
+    /// Appends `ops` as they are, which is much faster than adding them one
+    /// at a time for bulk data, like the chunks of a huge path decoded while
+    /// streaming it in. The ops aren't checked; `try_extend_from_slice` is a
+    /// version that does.
+    pub fn extend_from_slice(&mut self, ops: &[PathOp]) {
+        self.path.ops.extend_from_slice(ops)
+    }
+
+    /// Like `extend_from_slice` but appends nothing and returns an error if
+    /// `ops` would draw from a current point that doesn't exist, because the
+    /// builder has no current point and they don't start with a `MoveTo`
+    /// (`PathError::MissingMoveTo`), or if any of them has a coordinate
+    /// that's NaN or infinite (`PathError::NonFiniteCoordinate` with its
+    /// index in `ops`).
+    pub fn try_extend_from_slice(&mut self, ops: &[PathOp]) -> Result<(), PathError> {
+        match ops.first() {
+            None | Some(PathOp::MoveTo(_)) => {}
+            Some(_) => {
+                if self.current_point().is_none() {
+                    return Err(PathError::MissingMoveTo);
+                }
+            }
+        }
+        if let Some(op_index) = ops.iter().position(|op| !op.is_finite()) {
+            return Err(PathError::NonFiniteCoordinate { op_index });
+        }
+        self.extend_from_slice(ops);
+        Ok(())
+    }
+
+    /// Builds a polyline from the `points` attribute of an SVG `<polyline>`
+    /// or `<polygon>` element, e.g. "0,0 10,0 10 10". Pass `close` as true
+    /// for polygons. Returns `PathError::InvalidNumber` with the byte offset
+    /// of anything that isn't a number or a separator and
+    /// `PathError::OddNumberOfCoordinates` if the numbers don't pair up.
+    pub fn from_svg_points(s: &str, close: bool) -> Result<Path, PathError> {
+        let numbers = parse_svg_numbers(s)?;
+        if numbers.len() % 2 != 0 {
+            return Err(PathError::OddNumberOfCoordinates);
+        }
+        let mut pb = PathBuilder::new();
+        for (i, point) in numbers.chunks(2).enumerate() {
+            if i == 0 {
+                pb.move_to(point[0], point[1]);
+            } else {
+                pb.line_to(point[0], point[1]);
+            }
+        }
+        if close && !numbers.is_empty() {
+            pb.close();
+        }
+        Ok(pb.finish())
+    }
+
+    /// Moves the current point to `x`, `y`
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.path.ops.push(PathOp::MoveTo(Point::new(x, y)))
+    }
+
+    /// Starts a new subpath at `dx`, `dy` from the current point, which is
+    /// where the last subpath ended or, if it was closed, where it started.
+    /// Before anything has been added the current point is the origin, just
+    /// like a relative move at the start of SVG path data.
+    pub fn move_by(&mut self, dx: f32, dy: f32) {
+        let pt = self.current_point().unwrap_or_else(Point::origin);
+        self.move_to(pt.x + dx, pt.y + dy)
+    }
+
+    /// The point that the next op will start from, if there is one
+    fn current_point(&self) -> Option<Point> {
+        match *self.path.ops.last()? {
+            PathOp::MoveTo(pt) | PathOp::LineTo(pt) | PathOp::QuadTo(_, pt) | PathOp::CubicTo(_, _, pt) => Some(pt),
+            PathOp::Close => {
+                // the start of the subpath that was closed, which without a
+                // MoveTo is the first point, like when filling
+                let start = self.path.ops.iter().rev().find_map(|op| match *op {
+                    PathOp::MoveTo(pt) => Some(pt),
+                    _ => None,
+                });
+                start.or_else(|| match *self.path.ops.first()? {
+                    PathOp::LineTo(pt) | PathOp::QuadTo(pt, _) | PathOp::CubicTo(pt, _, _) => Some(pt),
+                    _ => None,
+                })
+            }
+        }
+    }
+
+    /// Adds a line segment from the current point to `x`, `y`
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        self.path.ops.push(PathOp::LineTo(Point::new(x, y)))
+    }
+
+    /// Adds a quadratic bezier from the current point to `x`, `y`,
+    /// using a control point of `cx`, `cy`
+    pub fn quad_to(&mut self, cx: f32, cy: f32, x: f32, y: f32) {
+        self.path
+            .ops
+            .push(PathOp::QuadTo(Point::new(cx, cy), Point::new(x, y)))
+    }
+
+    /// Adds a rect to the path
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.move_to(x, y);
+        self.line_to(x + width, y);
+        self.line_to(x + width, y + height);
+        self.line_to(x, y + height);
+        self.close();
+    }
+
+    /// Adds a closed regular polygon with `sides` corners on the circle of
+    /// `radius` around `cx`, `cy`. The first corner is at the angle `rotation`
+    /// in radians and the rest follow clockwise, like a positive sweep of
+    /// `arc`, so the polygon winds the same way as `rect`. Nothing is added
+    /// for fewer than three sides.
+    pub fn regular_polygon(&mut self, cx: f32, cy: f32, radius: f32, sides: usize, rotation: f32) {
+        if sides < 3 {
+            return;
+        }
+        self.radial_polygon(cx, cy, &[radius], sides, rotation);
+    }
+
+    /// Adds a closed star with `points` points on the circle of `outer_radius`
+    /// around `cx`, `cy`, alternating with the corners between them on the
+    /// circle of `inner_radius`. The first point is at the angle `rotation` in
+    /// radians and the rest follow clockwise like in `regular_polygon`.
+    /// Nothing is added for fewer than two points.
+    pub fn star(&mut self, cx: f32, cy: f32, outer_radius: f32, inner_radius: f32, points: usize, rotation: f32) {
+        if points < 2 {
+            return;
+        }
+        self.radial_polygon(cx, cy, &[outer_radius, inner_radius], points * 2, rotation);
+    }
+
+    /// Adds a closed polygon with `corners` corners evenly spaced around
+    /// `cx`, `cy`, taking their distances from the center from `radii` in turn.
+    fn radial_polygon(&mut self, cx: f32, cy: f32, radii: &[f32], corners: usize, rotation: f32) {
+        for i in 0..corners {
+            let angle = rotation + 2. * std::f32::consts::PI * i as f32 / corners as f32;
+            let r = radii[i % radii.len()];
+            let (x, y) = (cx + r * angle.cos(), cy + r * angle.sin());
+            if i == 0 {
+                self.move_to(x, y);
+            } else {
+                self.line_to(x, y);
+            }
+        }
         self.close();
     }
 
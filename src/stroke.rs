@@ -1,7 +1,13 @@
 // This is a simple path stroker. It flattens the path and strokes each segment individually.
 // For a recent survey of stroking approaches see "Converting stroked primitives to filled primitives" by Diego Nehab
 
-use crate::path_builder::{Path, PathBuilder, PathOp};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use euclid::default::Box2D;
+use lyon_geom::{CubicBezierSegment, QuadraticBezierSegment};
+
+use crate::path_builder::{distance_to_segment, flatten_cubic, Path, PathBuilder, PathOp, UnitVector};
 use crate::{Point, Vector};
 
 #[derive(Clone, PartialEq, Debug)]
@@ -32,6 +38,10 @@ pub enum LineCap {
     Round,
     Square,
     Butt,
+    /// A square cap with its outer corners rounded off with this radius.
+    /// A radius of 0 gives the same shape as `Square` and half of the width
+    /// of the stroke, or more, the same half circle as `Round`.
+    Rounded(f32),
 }
 
 #[derive(Clone, Copy, PartialEq, Debug)]
@@ -39,25 +49,127 @@ pub enum LineJoin {
     Round,
     Miter,
     Bevel,
+    /// A miter join that falls back to a round join instead of a bevel
+    /// when it would be longer than the miter limit.
+    MiterOrRound,
+}
+
+/// How `Path::dash_with_caps` fits dashes to the caps drawn at their ends.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DashCapFit {
+    /// Shortens the dashes so that they, including their caps, cover
+    /// exactly the lengths in the dash array. Dashes with no gap between
+    /// them meet flush instead of overlapping.
+    Flush,
+    /// Lengthens the dashes into the gaps by the length of the caps, so
+    /// that caps of neighbouring dashes overlap into a continuous line
+    /// when the gaps are shorter than the width of the stroke.
+    Overlap,
+}
+
+/// Options that control how `stroke_to_path_with_options` generates geometry
+/// without changing the style of the stroke.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct StrokeOptions {
+    /// The tolerance used to flatten any curves in the path being stroked.
+    pub tolerance: f32,
+    /// The tolerance used for the round caps and joins the stroker adds.
+    /// Round caps and joins are built from enough curves to stay within this
+    /// distance of a true circle, and outputs made of only lines, like
+    /// `stroke_to_path_non_overlapping`, flatten caps and joins to within it.
+    /// Keeping it separate from `tolerance` allows coarse caps on finely
+    /// flattened curves or the other way around.
+    pub round_tolerance: f32,
+    /// How far round, square and rounded caps protrude past the end of the path
+    /// in addition to half the width of the stroke.
+    pub cap_extension: f32,
+    /// How far caps and joins reach into the segments they're attached to.
+    /// A small bleed, like 0.25, makes the pieces overlap so that no
+    /// anti-aliasing seams show between them. The overlap stays inside the
+    /// outline of the stroke.
+    pub bleed: f32,
+    /// Rounds the segment normals to a fixed precision of 1/65536 so that
+    /// identical inputs produce byte-identical output on every platform.
+    /// This moves the outline by at most `width / 65536`.
+    pub reference: bool,
+    /// When positive, rounds the offsets of the outline from the path, half
+    /// of the width of the stroke along each segment's normal, to multiples
+    /// of this distance. When a path is animated the offsets then stay the
+    /// same from frame to frame unless a segment turns far enough, instead
+    /// of shifting slightly every frame and making the edges shimmer. The
+    /// path itself isn't snapped so it still moves smoothly. This moves the
+    /// outline by up to `offset_snap` so it should be much smaller than the
+    /// width, like 1/16 of a pixel.
+    pub offset_snap: f32,
+}
+
+impl StrokeOptions {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl Default for StrokeOptions {
+    fn default() -> Self {
+        StrokeOptions {
+            tolerance: 0.1,
+            round_tolerance: 0.1,
+            cap_extension: 0.,
+            bleed: 0.,
+            reference: false,
+            offset_snap: 0.,
+        }
+    }
 }
 
-fn compute_normal(p0: Point, p1: Point) -> Option<Vector> {
-    let ux = p1.x - p0.x;
-    let uy = p1.y - p0.y;
+fn compute_normal(p0: Point, p1: Point) -> Option<UnitVector> {
+    let ux = p1.x as f64 - p0.x as f64;
+    let uy = p1.y as f64 - p0.y as f64;
 
-    // this could overflow f32. Skia in SkPoint::Normalize used to
-    // checks for this and used a double in that situation, but was
-    // simplified to always use doubles.
-    let ulen = ux.hypot(uy);
+    // Computing the length in f32 could overflow. Skia in SkPoint::Normalize used to
+    // check for this and use a double in that situation, but was simplified to always
+    // use doubles. We do the same and use sqrt instead of hypot because hypot
+    // isn't correctly rounded and can differ between platforms.
+    let ulen = (ux * ux + uy * uy).sqrt();
     if ulen == 0. {
         return None;
     }
     // the normal is perpendicular to the *unit* vector
-    Some(Vector::new(-uy / ulen, ux / ulen))
+    Some(UnitVector::new_unchecked(Vector::new((-uy / ulen) as f32, (ux / ulen) as f32)))
 }
 
-fn flip(v: Vector) -> Vector {
-    Vector::new(-v.x, -v.y)
+fn quantize_normal(v: UnitVector) -> UnitVector {
+    const PRECISION: f32 = 65536.;
+    // close enough to unit length for the stroker, and the same everywhere
+    UnitVector::new_unchecked(Vector::new((v.x * PRECISION).round() / PRECISION, (v.y * PRECISION).round() / PRECISION))
+}
+
+/* Where the stroker draws. Every piece it produces is a convex polygon started with
+ * `move_to` and finished with `close`, with `arc_segment` adding an arc of at most a
+ * quarter circle around the piece's corner from the current point. */
+trait StrokeDest {
+    fn move_to(&mut self, x: f32, y: f32);
+    fn line_to(&mut self, x: f32, y: f32);
+    fn arc_segment(&mut self, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector);
+    fn close(&mut self);
+}
+
+impl StrokeDest for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        PathBuilder::move_to(self, x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        PathBuilder::line_to(self, x, y);
+    }
+
+    fn arc_segment(&mut self, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector) {
+        arc_segment(self, xc, yc, radius, a, b);
+    }
+
+    fn close(&mut self) {
+        PathBuilder::close(self);
+    }
 }
 
 /* Compute a spline approximation of the arc
@@ -82,125 +194,285 @@ A different way to do this is covered in "Approximation of a cubic bezier
 curve by circular arcs and vice versa" by Alekas Riškus. However, the method
 presented there doesn't handle arcs with angles close to 0 because it
 divides by the perp dot product of the two angle vectors.
+
+That curve goes through the middle of the arc and bulges slightly outside
+of the circle everywhere else, by up to e = 2 sin(angle/4)^6 / (27 cos(angle/4)^2)
+of the radius, 0.027% for a quarter circle. A stroke filled over something
+lighter shows that as overshoot past the true outline, so we keep h and pull
+the two middle control points toward the center by a factor of
+1 / (1 + 9/4 e), which with t = 3/4 h is 1 / (1 + t^6 / (6 (1 + t^2)^2)).
+That's just enough to keep the whole curve inside of the circle, at the cost
+of a bend in the tangents at the ends too small to see. The curve then dips
+inside by at most radius * angle^6 / 27648, twice the error of the usual curve.
 */
-fn arc_segment(path: &mut PathBuilder, xc: f32, yc: f32, radius: f32, a: Vector, b: Vector) {
+fn arc_segment(path: &mut PathBuilder, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector) {
     let r_sin_a = radius * a.y;
     let r_cos_a = radius * a.x;
     let r_sin_b = radius * b.y;
     let r_cos_b = radius * b.x;
 
     /* bisect the angle between 'a' and 'b' with 'mid' */
-    let mut mid = a + b;
-    mid /= mid.length();
+    let mid = normalize(*a + *b);
 
     /* bisect the angle between 'a' and 'mid' with 'mid2' this is parallel to a
      * line with angle (B - A)/4 */
-    let mid2 = a + mid;
+    let mid2 = *a + mid;
 
-    let h = (4. / 3.) * dot(perp(a), mid2) / dot(a, mid2);
+    let h = (4. / 3.) * dot(*a.perp(), mid2) / dot(*a, mid2);
 
-    path.cubic_to(
-        xc + r_cos_a - h * r_sin_a,
-        yc + r_sin_a + h * r_cos_a,
-        xc + r_cos_b + h * r_sin_b,
-        yc + r_sin_b - h * r_cos_b,
+    /* keep the curve inside the circle, see above */
+    let t2 = (0.75 * h) * (0.75 * h);
+    let scale = 1. / (1. + t2 * t2 * t2 / (6. * (1. + t2) * (1. + t2)));
+
+    let points = [
+        xc + scale * (r_cos_a - h * r_sin_a),
+        yc + scale * (r_sin_a + h * r_cos_a),
+        xc + scale * (r_cos_b + h * r_sin_b),
+        yc + scale * (r_sin_b - h * r_cos_b),
         xc + r_cos_b,
         yc + r_sin_b,
-    );
+    ];
+    let [x1, y1, x2, y2, x, y] = if points.iter().all(|v| v.is_finite()) {
+        points
+    } else {
+        // A huge radius far from the origin can push the control points past the
+        // range of f32. Like compute_normal we redo the computation with doubles,
+        // and then clamp the points to the largest finite values.
+        let (xc, yc, radius, h, scale) = (xc as f64, yc as f64, radius as f64, h as f64, scale as f64);
+        let (r_sin_a, r_cos_a) = (radius * a.y as f64, radius * a.x as f64);
+        let (r_sin_b, r_cos_b) = (radius * b.y as f64, radius * b.x as f64);
+        let clamp = |v: f64| v.clamp(-f32::MAX as f64, f32::MAX as f64) as f32;
+        [
+            clamp(xc + scale * (r_cos_a - h * r_sin_a)),
+            clamp(yc + scale * (r_sin_a + h * r_cos_a)),
+            clamp(xc + scale * (r_cos_b + h * r_sin_b)),
+            clamp(yc + scale * (r_sin_b - h * r_cos_b)),
+            clamp(xc + r_cos_b),
+            clamp(yc + r_sin_b),
+        ]
+    };
+    path.cubic_to(x1, y1, x2, y2, x, y);
 }
 
 /* The angle between the vectors must be <= pi */
-fn bisect(a: Vector, b: Vector) -> Vector {
+fn bisect(a: UnitVector, b: UnitVector) -> UnitVector {
     let mut mid;
-    if dot(a, b) >= 0. {
+    if dot(*a, *b) >= 0. {
         /* if the angle between a and b is accute, then we can
          * just add the vectors and normalize */
-        mid = a + b;
+        mid = *a + *b;
     } else {
         /* otherwise, we can flip a, add it
          * and then use the perpendicular of the result */
-        mid = flip(a) + b;
-        mid = perp(mid);
+        mid = *a.flip() + *b;
+        mid = Vector::new(-mid.y, mid.x);
     }
 
-    /* normalize */
-    /* because we assume that 'a' and 'b' are normalized, we can use
-     * sqrt instead of hypot because the range of mid is limited */
-    let mid_len = mid.x * mid.x + mid.y * mid.y;
-    let len = mid_len.sqrt();
-    return mid / len;
+    UnitVector::new_unchecked(normalize(mid))
+}
+
+/* because we assume that the vectors we normalize are the sum of unit vectors, we can use
+ * sqrt instead of hypot because the range is limited. Both arc_segment and bisect go through
+ * here so that the operations happen in the same order everywhere. */
+fn normalize(v: Vector) -> Vector {
+    let len = (v.x * v.x + v.y * v.y).sqrt();
+    v / len
 }
 
-fn arc(path: &mut PathBuilder, xc: f32, yc: f32, radius: f32, a: Vector, b: Vector) {
+fn arc(path: &mut dyn StrokeDest, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector) {
     /* find a vector that bisects the angle between a and b */
     let mid_v = bisect(a, b);
 
     /* construct the arc using two curve segments */
-    arc_segment(path, xc, yc, radius, a, mid_v);
-    arc_segment(path, xc, yc, radius, mid_v, b);
+    path.arc_segment(xc, yc, radius, a, mid_v);
+    path.arc_segment(xc, yc, radius, mid_v, b);
 }
 
-fn join_round(path: &mut PathBuilder, center: Point, a: Vector, b: Vector, radius: f32) {
+/* Adds the half circle from `normal` to its flip, going through the direction of travel
+ * (normal.unperp()), using enough curve segments to stay within `tolerance` of the true
+ * circle. The error of a cubic approximating an arc of angle t is at most
+ * radius * t^6 / 27648 so the largest angle each segment can cover shrinks slowly as the
+ * radius grows. At least two segments are needed because each one can cover at most a
+ * quarter circle. */
+fn cap_arc(path: &mut dyn StrokeDest, center: Point, radius: f32, normal: UnitVector, tolerance: f32) {
+    let max_angle = (27648. * tolerance / radius).powf(1. / 6.);
+    let segments = (std::f32::consts::PI / max_angle).ceil().max(2.);
+    if segments <= 2. {
+        arc(path, center.x, center.y, radius, normal, normal.flip());
+        return;
+    }
+    // don't let a tiny tolerance produce an absurd number of segments
+    let segments = segments.min(64.) as usize;
+    let v = normal.unperp();
+    let mut a = normal;
+    for i in 1..=segments {
+        let b = if i == segments {
+            normal.flip()
+        } else {
+            let angle = std::f32::consts::PI * i as f32 / segments as f32;
+            UnitVector::new_unchecked(normal * angle.cos() + v * angle.sin())
+        };
+        path.arc_segment(center.x, center.y, radius, a, b);
+        a = b;
+    }
+}
+
+/* Adds the quarter circle from `a` to `b`, which is `a` turned a quarter turn, within
+ * `tolerance` of the true circle like `cap_arc`. A radius of 0 adds nothing. */
+fn quarter_arc(path: &mut dyn StrokeDest, center: Point, radius: f32, a: UnitVector, b: UnitVector, tolerance: f32) {
+    if radius <= 0. {
+        return;
+    }
+    let max_angle = (27648. * tolerance / radius).powf(1. / 6.);
+    let segments = (std::f32::consts::FRAC_PI_2 / max_angle).ceil().clamp(1., 32.) as usize;
+    let mut from = a;
+    for i in 1..=segments {
+        let to = if i == segments {
+            b
+        } else {
+            let angle = std::f32::consts::FRAC_PI_2 * i as f32 / segments as f32;
+            UnitVector::new_unchecked(a * angle.cos() + b * angle.sin())
+        };
+        path.arc_segment(center.x, center.y, radius, from, to);
+        from = to;
+    }
+}
+
+/* Adds the arc from `a` to `b` split in half `depth` times by bisecting it. */
+fn split_arc(path: &mut dyn StrokeDest, center: Point, radius: f32, a: UnitVector, b: UnitVector, depth: u32) {
+    if depth == 0 {
+        path.arc_segment(center.x, center.y, radius, a, b);
+        return;
+    }
+    let mid_v = bisect(a, b);
+    split_arc(path, center, radius, a, mid_v, depth - 1);
+    split_arc(path, center, radius, mid_v, b, depth - 1);
+}
+
+/* Adds the arc from `a` to `b`, within `tolerance` of the true circle like `cap_arc`. The
+ * arc is halved until the pieces are small enough, and at least once like `arc` does. */
+fn join_round(path: &mut dyn StrokeDest, center: Point, a: UnitVector, b: UnitVector, radius: f32, tolerance: f32) {
     /*
     int ccw = dot (perp (b), a) >= 0; // XXX: is this always true?
     yes, otherwise we have an interior angle.
     assert (ccw);
     */
-    arc(path, center.x, center.y, radius, a, b);
+    let angle = dot(*a, *b).clamp(-1., 1.).acos();
+    let max_angle = (27648. * tolerance / radius).powf(1. / 6.);
+    // don't let a tiny tolerance produce an absurd number of segments
+    let depth = (angle / max_angle).log2().ceil().clamp(1., 6.) as u32;
+    split_arc(path, center, radius, a, b, depth);
+}
+
+/* Caps and joins start and end on the edges they share with the segments. When there's a
+ * bleed they're extended a little past those edges, into the segments, so that the
+ * pieces overlap. `v` is the direction pointing out of the segment. */
+fn begin_cap(dest: &mut dyn StrokeDest, options: &StrokeOptions, start: Point, v: Vector) {
+    if options.bleed > 0. {
+        let bleed = start - v * options.bleed;
+        dest.move_to(bleed.x, bleed.y);
+        dest.line_to(start.x, start.y);
+    } else {
+        dest.move_to(start.x, start.y);
+    }
 }
 
-fn cap_line(dest: &mut PathBuilder, style: &StrokeStyle, pt: Point, normal: Vector) {
+fn end_cap(dest: &mut dyn StrokeDest, options: &StrokeOptions, end: Point, pt: Point, v: Vector) {
+    if options.bleed > 0. {
+        let end = end - v * options.bleed;
+        let pt = pt - v * options.bleed;
+        dest.line_to(end.x, end.y);
+        dest.line_to(pt.x, pt.y);
+    } else {
+        dest.line_to(pt.x, pt.y);
+    }
+    dest.close();
+}
+
+/* For joins normal.perp() points back along the direction of travel, into the first segment,
+ * and its flip points forward into the second. This also holds for the flipped and swapped
+ * normals of interior angles. */
+fn begin_join(dest: &mut dyn StrokeDest, options: &StrokeOptions, start: Point, s1_normal: UnitVector) {
+    if options.bleed > 0. {
+        let bleed = start + s1_normal.perp() * options.bleed;
+        dest.move_to(bleed.x, bleed.y);
+        dest.line_to(start.x, start.y);
+    } else {
+        dest.move_to(start.x, start.y);
+    }
+}
+
+fn end_join(dest: &mut dyn StrokeDest, options: &StrokeOptions, end: Point, pt: Point, s1_normal: UnitVector, s2_normal: UnitVector) {
+    if options.bleed > 0. {
+        let v1 = s1_normal.perp() * options.bleed;
+        let v2 = s2_normal.perp().flip() * options.bleed;
+        dest.line_to(end.x + v2.x, end.y + v2.y);
+        dest.line_to(pt.x + v2.x, pt.y + v2.y);
+        dest.line_to(pt.x + v1.x, pt.y + v1.y);
+    } else {
+        dest.line_to(pt.x, pt.y);
+    }
+    dest.close();
+}
+
+fn cap_line(dest: &mut dyn StrokeDest, style: &StrokeStyle, options: &StrokeOptions, pt: Point, normal: UnitVector) {
     let offset = style.width / 2.;
+    // parallel vector. Rotating the normal to the right gives the direction of
+    // travel at an end point and, because start caps are given a flipped
+    // normal, the opposite direction at a start point. Either way the cap
+    // extends away from the segment.
+    let v = *normal.unperp();
     match style.cap {
         LineCap::Butt => { /* nothing to do */ }
         LineCap::Round => {
-            dest.move_to(pt.x + normal.x * offset, pt.y + normal.y * offset);
-            arc(dest, pt.x, pt.y, offset, normal, flip(normal));
-            dest.line_to(pt.x, pt.y);
-            dest.close();
+            let center = pt + v * options.cap_extension;
+            begin_cap(dest, options, pt + normal * offset, v);
+            if options.cap_extension != 0. {
+                dest.line_to(center.x + normal.x * offset, center.y + normal.y * offset);
+            }
+            cap_arc(dest, center, offset, normal, options.round_tolerance);
+            if options.cap_extension != 0. {
+                dest.line_to(pt.x - normal.x * offset, pt.y - normal.y * offset);
+            }
+            end_cap(dest, options, pt - normal * offset, pt, v);
         }
         LineCap::Square => {
-            // parallel vector
-            let v = Vector::new(normal.y, -normal.x);
-            let end = pt + v * offset;
-            dest.move_to(pt.x + normal.x * offset, pt.y + normal.y * offset);
+            let end = pt + v * (offset + options.cap_extension);
+            begin_cap(dest, options, pt + normal * offset, v);
             dest.line_to(end.x + normal.x * offset, end.y + normal.y * offset);
             dest.line_to(end.x + -normal.x * offset, end.y + -normal.y * offset);
             dest.line_to(pt.x - normal.x * offset, pt.y - normal.y * offset);
-            dest.line_to(pt.x, pt.y);
-            dest.close();
+            end_cap(dest, options, pt - normal * offset, pt, v);
+        }
+        LineCap::Rounded(radius) => {
+            let radius = if radius > 0. { radius.min(offset) } else { 0. };
+            let end = pt + v * (offset + options.cap_extension - radius);
+            let inset = offset - radius;
+            begin_cap(dest, options, pt + normal * offset, v);
+            dest.line_to(end.x + normal.x * offset, end.y + normal.y * offset);
+            quarter_arc(dest, end + normal * inset, radius, normal, UnitVector::new_unchecked(v), options.round_tolerance);
+            let tip = end + v * radius - normal * inset;
+            dest.line_to(tip.x, tip.y);
+            quarter_arc(dest, end - normal * inset, radius, UnitVector::new_unchecked(v), normal.flip(), options.round_tolerance);
+            dest.line_to(pt.x - normal.x * offset, pt.y - normal.y * offset);
+            end_cap(dest, options, pt - normal * offset, pt, v);
         }
     }
 }
 
-fn bevel(
-    dest: &mut PathBuilder,
-    style: &StrokeStyle,
-    pt: Point,
-    s1_normal: Vector,
-    s2_normal: Vector,
-) {
-    let offset = style.width / 2.;
-    dest.move_to(pt.x + s1_normal.x * offset, pt.y + s1_normal.y * offset);
-    dest.line_to(pt.x + s2_normal.x * offset, pt.y + s2_normal.y * offset);
-    dest.line_to(pt.x, pt.y);
-    dest.close();
-}
-
-/* given a normal rotate the vector 90 degrees to the right clockwise
- * This function has a period of 4. e.g. swap(swap(swap(swap(x) == x */
-fn swap(a: Vector) -> Vector {
-    /* one of these needs to be negative. We choose a.x so that we rotate to the right instead of negating */
-    Vector::new(a.y, -a.x)
-}
-
-fn unperp(a: Vector) -> Vector {
-    swap(a)
+/* Draws a subpath that doesn't go anywhere as a pair of caps oriented along the x axis,
+ * giving a circle for round caps, a square for square caps, a square with rounded corners
+ * for rounded caps and nothing for butt caps. */
+fn cap_dot(dest: &mut dyn StrokeDest, style: &StrokeStyle, options: &StrokeOptions, pt: Point) {
+    let normal = UnitVector::new_unchecked(Vector::new(0., 1.));
+    cap_line(dest, style, options, pt, normal);
+    cap_line(dest, style, options, pt, normal.flip());
 }
 
-/* rotate a vector 90 degrees to the left */
-fn perp(v: Vector) -> Vector {
-    Vector::new(-v.y, v.x)
+fn bevel(dest: &mut dyn StrokeDest, options: &StrokeOptions, pt: Point, s1: SegmentEnd, s2: SegmentEnd) {
+    begin_join(dest, options, s1.plus, s1.normal);
+    dest.line_to(s2.plus.x, s2.plus.y);
+    end_join(dest, options, s2.plus, pt, s1.normal, s2.normal);
 }
 
 fn dot(a: Vector, b: Vector) -> f32 {
@@ -211,181 +483,1908 @@ fn dot(a: Vector, b: Vector) -> f32 {
 From "Example 2: Find the intersection of two lines" of
 "The Pleasures of "Perp Dot" Products"
 F. S. Hill, Jr. */
-fn line_intersection(a: Point, a_perp: Vector, b: Point, b_perp: Vector) -> Option<Point> {
-    let a_parallel = unperp(a_perp);
+fn line_intersection(a: Point, a_perp: UnitVector, b: Point, b_perp: UnitVector) -> Option<Point> {
+    let a_parallel = a_perp.unperp();
     let c = b - a;
-    let denom = dot(b_perp, a_parallel);
+    let denom = dot(*b_perp, *a_parallel);
     if denom == 0.0 {
         return None;
     }
 
-    let t = dot(b_perp, c) / denom;
+    let t = dot(*b_perp, c) / denom;
 
     let intersection = Point::new(a.x + t * (a_parallel.x), a.y + t * (a_parallel.y));
 
     Some(intersection)
 }
 
-fn is_interior_angle(a: Vector, b: Vector) -> bool {
+fn is_interior_angle(a: UnitVector, b: UnitVector) -> bool {
     /* angles of 180 and 0 degrees will evaluate to 0, however
      * we to treat 180 as an interior angle and 180 as an exterior angle */
-    dot(perp(a), b) > 0. || a == b /* 0 degrees is interior */
+    dot(*a.perp(), *b) > 0. || a == b /* 0 degrees is interior */
+}
+
+/* The normal of a segment along with the corners of its quad on either side of one of its
+ * end points. Joins are built from these exact points, instead of recomputing them, so that
+ * they share their vertices with the quads bit for bit. */
+#[derive(Clone, Copy)]
+struct SegmentEnd {
+    normal: UnitVector,
+    // pt + normal * half_width
+    plus: Point,
+    // pt - normal * half_width
+    minus: Point,
+}
+
+impl SegmentEnd {
+    fn new(pt: Point, normal: UnitVector, half_width: f32) -> SegmentEnd {
+        SegmentEnd {
+            normal,
+            plus: Point::new(pt.x + normal.x * half_width, pt.y + normal.y * half_width),
+            minus: Point::new(pt.x - normal.x * half_width, pt.y - normal.y * half_width),
+        }
+    }
+
+    fn flip(self) -> SegmentEnd {
+        SegmentEnd { normal: self.normal.flip(), plus: self.minus, minus: self.plus }
+    }
 }
 
 fn join_line(
-    dest: &mut PathBuilder,
+    dest: &mut dyn StrokeDest,
     style: &StrokeStyle,
+    options: &StrokeOptions,
+    join: LineJoin,
     pt: Point,
-    mut s1_normal: Vector,
-    mut s2_normal: Vector,
+    s1: SegmentEnd,
+    s2: SegmentEnd,
 ) {
-    if is_interior_angle(s1_normal, s2_normal) {
-        s2_normal = flip(s2_normal);
-        s1_normal = flip(s1_normal);
-        std::mem::swap(&mut s1_normal, &mut s2_normal);
-    }
+    let (s1, s2) = if is_interior_angle(s1.normal, s2.normal) {
+        (s2.flip(), s1.flip())
+    } else {
+        (s1, s2)
+    };
 
     // XXX: joining uses `pt` which can cause seams because it lies halfway on a line and the
     // rasterizer may not find exactly the same spot
     let offset = style.width / 2.;
-    match style.join {
-        LineJoin::Round => {
-            dest.move_to(pt.x + s1_normal.x * offset, pt.y + s1_normal.y * offset);
-            join_round(dest, pt, s1_normal, s2_normal, offset);
-            dest.line_to(pt.x, pt.y);
-            dest.close();
-        }
-        LineJoin::Miter => {
-            let in_dot_out = -s1_normal.x * s2_normal.x + -s1_normal.y * s2_normal.y;
+    let start = s1.plus;
+    let end = s2.plus;
+    let round = |dest: &mut dyn StrokeDest| {
+        begin_join(dest, options, start, s1.normal);
+        join_round(dest, pt, s1.normal, s2.normal, offset, options.round_tolerance);
+        // the arc computes its own end point so finish on the exact corner
+        dest.line_to(end.x, end.y);
+        end_join(dest, options, end, pt, s1.normal, s2.normal);
+    };
+    match join {
+        LineJoin::Round => round(dest),
+        LineJoin::Miter | LineJoin::MiterOrRound => {
+            let in_dot_out = -s1.normal.x * s2.normal.x + -s1.normal.y * s2.normal.y;
             if 2. <= style.miter_limit * style.miter_limit * (1. - in_dot_out) {
-                let start = pt + s1_normal * offset;
-                let end = pt + s2_normal * offset;
-                if let Some(intersection) = line_intersection(start, s1_normal, end, s2_normal) {
+                if let Some(intersection) = line_intersection(start, s1.normal, end, s2.normal) {
                     // We won't have an intersection if the segments are parallel
-                    dest.move_to(pt.x + s1_normal.x * offset, pt.y + s1_normal.y * offset);
+                    begin_join(dest, options, start, s1.normal);
                     dest.line_to(intersection.x, intersection.y);
-                    dest.line_to(pt.x + s2_normal.x * offset, pt.y + s2_normal.y * offset);
-                    dest.line_to(pt.x, pt.y);
-                    dest.close();
+                    dest.line_to(end.x, end.y);
+                    end_join(dest, options, end, pt, s1.normal, s2.normal);
                 }
+            } else if join == LineJoin::MiterOrRound {
+                round(dest);
             } else {
-                bevel(dest, style, pt, s1_normal, s2_normal);
+                bevel(dest, options, pt, s1, s2);
             }
         }
         LineJoin::Bevel => {
-            bevel(dest, style, pt, s1_normal, s2_normal);
+            bevel(dest, options, pt, s1, s2);
         }
     }
 }
 
+/// Adds the cap that the stroker draws at the end of a line to `out`, for
+/// building stroke-like geometry such as connectors or arrows. `normal` is
+/// the normal of the line at `pt`, its direction of travel rotated by
+/// `UnitVector::perp`. Flip it for a cap at the start of a line so that the
+/// cap extends away from the line. The cap follows `style.cap` and
+/// `style.width` and is added as closed subpaths, or not at all for butt caps.
+pub fn build_cap(out: &mut PathBuilder, style: &StrokeStyle, pt: Point, normal: UnitVector) {
+    cap_line(out, style, &StrokeOptions::default(), pt, normal);
+}
+
+/// Adds the join that the stroker draws where two lines meet at `pt` to `out`.
+/// `n1` and `n2` are the normals, as described for `build_cap`, of the line
+/// that ends at `pt` and of the line that starts there. The join follows
+/// `style.join`, `style.width` and `style.miter_limit` and only covers the
+/// outside of the turn, where the quads of the two lines leave a gap. Nothing
+/// is added when the lines continue straight on.
+pub fn build_join(out: &mut PathBuilder, style: &StrokeStyle, pt: Point, n1: UnitVector, n2: UnitVector) {
+    if n1 == n2 {
+        return;
+    }
+    let half_width = style.width / 2.;
+    let (s1, s2) = (SegmentEnd::new(pt, n1, half_width), SegmentEnd::new(pt, n2, half_width));
+    join_line(out, style, &StrokeOptions::default(), style.join, pt, s1, s2);
+}
+
+/// Strokes a path that is a single closed axis aligned rectangle as the outer rectangle
+/// minus the inner rectangle. This avoids the overlapping pieces of the general
+/// stroker and gives the same result for both fill rules.
+fn stroke_rect(path: &Path, style: &StrokeStyle) -> Option<Path> {
+    let (p0, p1, p2, p3) = match path.ops[..] {
+        [PathOp::MoveTo(p0), PathOp::LineTo(p1), PathOp::LineTo(p2), PathOp::LineTo(p3), PathOp::Close] => (p0, p1, p2, p3),
+        [PathOp::MoveTo(p0), PathOp::LineTo(p1), PathOp::LineTo(p2), PathOp::LineTo(p3), PathOp::LineTo(p4), PathOp::Close]
+            if p4 == p0 => (p0, p1, p2, p3),
+        _ => return None,
+    };
+
+    let horizontal_first = p0.y == p1.y && p1.x == p2.x && p2.y == p3.y && p3.x == p0.x;
+    let vertical_first = p0.x == p1.x && p1.y == p2.y && p2.x == p3.x && p3.y == p0.y;
+    if !(horizontal_first || vertical_first) || p0.x == p2.x || p0.y == p2.y {
+        return None;
+    }
+
+    // Only miter joins give square corners
+    let miter = style.join == LineJoin::Miter || style.join == LineJoin::MiterOrRound;
+    if !miter || style.miter_limit * style.miter_limit < 2. {
+        return None;
+    }
+
+    let half_width = style.width / 2.;
+    let (min_x, max_x) = (p0.x.min(p2.x), p0.x.max(p2.x));
+    let (min_y, max_y) = (p0.y.min(p2.y), p0.y.max(p2.y));
+
+    let mut pb = PathBuilder::new();
+    pb.rect(min_x - half_width, min_y - half_width, max_x - min_x + style.width, max_y - min_y + style.width);
+    if max_x - min_x > style.width && max_y - min_y > style.width {
+        // the inner rectangle winds in the opposite direction
+        pb.move_to(min_x + half_width, min_y + half_width);
+        pb.line_to(min_x + half_width, max_y - half_width);
+        pb.line_to(max_x - half_width, max_y - half_width);
+        pb.line_to(max_x - half_width, min_y + half_width);
+        pb.close();
+    }
+    Some(pb.finish())
+}
+
+/// Returns the outline of `path` stroked with `style`, to be filled with the
+/// nonzero winding rule. Subpaths that don't go anywhere, like a `MoveTo`
+/// followed by a `LineTo` to the same point or by a `Close`, are drawn as a
+/// dot in the shape of the caps, like in SVG: a circle for round caps, a
+/// square for square caps and nothing for butt caps. A `MoveTo` on its own
+/// isn't drawn.
 pub fn stroke_to_path(path: &Path, style: &StrokeStyle) -> Path {
-    let mut stroked_path = PathBuilder::new();
+    stroke_to_path_with_options(path, style, &StrokeOptions::default())
+}
+
+/// Like `stroke_to_path` but with explicit `options`.
+///
+/// Paths containing curves are flattened using `options.tolerance` before stroking.
+/// Outlines with fine detail, like small glyphs, may need a tolerance smaller
+/// than the default to avoid wobbly strokes.
+pub fn stroke_to_path_with_options(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> Path {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    stroke_flattened(path, style, options)
+}
+
+/// Like `stroke_to_path` but for a stroke that will be drawn scaled by
+/// `view_scale`, like the zoom of a map. The tolerances are chosen to keep
+/// curves and round caps and joins within a quarter of a pixel once scaled,
+/// so the stroke stays smooth when zoomed in and takes fewer points when
+/// zoomed out. A `view_scale` that isn't positive and finite uses the default
+/// tolerances.
+pub fn stroke_to_path_lod(path: &Path, style: &StrokeStyle, view_scale: f32) -> Path {
+    let mut options = StrokeOptions::default();
+    if view_scale.is_finite() && view_scale > 0. {
+        options.tolerance = 0.25 / view_scale;
+        options.round_tolerance = options.tolerance;
+    }
+    stroke_to_path_with_options(path, style, &options)
+}
+
+/// What the stroker found in one subpath of the path it stroked, from
+/// `stroke_to_path_with_info`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct SubpathInfo {
+    /// Whether the subpath ended with a `Close`, so it was stroked with a join
+    /// where it started instead of caps at both ends
+    pub closed: bool,
+    /// The number of lines and curves in the subpath, not counting the line
+    /// drawn by `Close`
+    pub segments: usize,
+    /// Whether the subpath doesn't go anywhere, so it was stroked as a dot or,
+    /// when it's only a `MoveTo` or has butt caps, not at all
+    pub degenerate: bool,
+}
+
+/// Like `stroke_to_path_with_options` but also describes each subpath of
+/// `path`, in order. A subpath starts at each `MoveTo` and, after a `Close`,
+/// at the next line or curve, which continues from where the closed subpath
+/// started. Ops before the first `MoveTo` aren't stroked and aren't counted.
+pub fn stroke_to_path_with_info(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> (Path, Vec<SubpathInfo>) {
+    let mut infos = Vec::new();
+    let mut current: Option<SubpathInfo> = None;
+    let mut start = None;
+    for op in &path.ops {
+        let points: &[Point] = match op {
+            PathOp::MoveTo(pt) => {
+                infos.extend(current.take());
+                current = Some(SubpathInfo { closed: false, segments: 0, degenerate: true });
+                start = Some(*pt);
+                continue;
+            }
+            PathOp::Close => {
+                if let Some(mut info) = current.take() {
+                    info.closed = true;
+                    infos.push(info);
+                }
+                continue;
+            }
+            PathOp::LineTo(pt) => &[*pt],
+            PathOp::QuadTo(ctrl, pt) => &[*ctrl, *pt],
+            PathOp::CubicTo(ctrl1, ctrl2, pt) => &[*ctrl1, *ctrl2, *pt],
+        };
+        if let Some(start) = start {
+            let info = current.get_or_insert(SubpathInfo { closed: false, segments: 0, degenerate: true });
+            info.segments += 1;
+            if points.iter().any(|&p| p != start) {
+                info.degenerate = false;
+            }
+        }
+    }
+    infos.extend(current);
+
+    (stroke_to_path_with_options(path, style, options), infos)
+}
+
+/// Like `stroke_to_path_with_options` but checks `path` and `style` first and
+/// returns an error instead of a stroke made from NaNs or infinities:
+///
+/// * `StrokeError::NonFiniteCoordinate` for the first op in `path` with a
+///   coordinate that's NaN or infinite
+/// * `StrokeError::InvalidWidth` if `style.width` is negative, NaN or infinite
+/// * `StrokeError::InvalidMiterLimit` if `style.miter_limit` is less than 1 or NaN
+/// * `StrokeError::InvalidDash` if an entry of `style.dash_array` is negative,
+///   NaN or infinite or if `style.dash_offset` is NaN or infinite
+///
+/// The dash array is only checked, like `stroke_to_path_with_options` it
+/// isn't applied.
+pub fn try_stroke_to_path(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> Result<Path, StrokeError> {
+    if let Some(op_index) = path.ops.iter().position(|op| !op.is_finite()) {
+        return Err(StrokeError::NonFiniteCoordinate { op_index });
+    }
+    if !style.width.is_finite() || style.width < 0. {
+        return Err(StrokeError::InvalidWidth);
+    }
+    if style.miter_limit.is_nan() || style.miter_limit < 1. {
+        return Err(StrokeError::InvalidMiterLimit);
+    }
+    if !style.dash_offset.is_finite() || style.dash_array.iter().any(|dash| !dash.is_finite() || *dash < 0.) {
+        return Err(StrokeError::InvalidDash);
+    }
+    Ok(stroke_to_path_with_options(path, style, options))
+}
+
+/// The reasons a path can't be stroked, returned by `try_stroke_to_path`.
+/// Errors from building paths are `PathError`s, which report non-finite
+/// coordinates with the same `NonFiniteCoordinate { op_index }`.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum StrokeError {
+    /// The op at `op_index` in the path has a coordinate that's NaN or infinite
+    NonFiniteCoordinate { op_index: usize },
+    /// The width of the stroke is negative, NaN or infinite
+    InvalidWidth,
+    /// The miter limit is less than 1 or NaN
+    InvalidMiterLimit,
+    /// A dash length is negative, NaN or infinite, or the dash offset is NaN or infinite
+    InvalidDash,
+}
+
+impl std::fmt::Display for StrokeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match *self {
+            StrokeError::NonFiniteCoordinate { op_index } => write!(f, "non-finite coordinate in op {}", op_index),
+            StrokeError::InvalidWidth => write!(f, "invalid stroke width"),
+            StrokeError::InvalidMiterLimit => write!(f, "invalid miter limit"),
+            StrokeError::InvalidDash => write!(f, "invalid dash array or offset"),
+        }
+    }
+}
+
+impl std::error::Error for StrokeError {}
+
+/// Like `stroke_to_path_with_options` but uses the join returned by
+/// `join_for_vertex` at each vertex instead of `style.join`. It's given the
+/// index into `path.ops` of the op that ends at the vertex, with the joins
+/// at the start of closed subpaths using the index of their `MoveTo`.
+/// Paths with curves are flattened first and the indices are then those
+/// of the flattened path.
+pub fn stroke_to_path_with_joins<F: Fn(usize) -> LineJoin>(
+    path: &Path,
+    style: &StrokeStyle,
+    options: &StrokeOptions,
+    join_for_vertex: F,
+) -> Path {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    let mut out = StrokeOutput { body: PathBuilder::new(), parts: None };
+    if style.width > 0. {
+        stroke_into(path, style, options, &join_for_vertex, &mut out);
+    }
+    out.body.finish()
+}
+
+/// Strokes `path` with both `inner` and `outer` and returns the two
+/// outlines as `(inner, outer)`. This is useful for drawing a line with a
+/// contrasting border by filling the outer outline and then the inner one
+/// on top of it.
+///
+/// Curves are only flattened once and both styles walk the same segments
+/// so the two outlines are guaranteed to be concentric.
+pub fn stroke_outlined(path: &Path, inner: &StrokeStyle, outer: &StrokeStyle, options: &StrokeOptions) -> (Path, Path) {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    (stroke_flattened(path, inner, options), stroke_flattened(path, outer, options))
+}
+
+/// Like `stroke_to_path_with_options` but also returns the centerline that the
+/// stroke was built around as `(stroke, centerline)`. The centerline is `path`
+/// flattened using `options.tolerance`, exactly as the stroker walked it, so
+/// drawing it as a hairline over the stroke shows where the caps and joins sit
+/// relative to it. This is mostly useful for debugging.
+pub fn stroke_with_centerline(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> (Path, Path) {
+    let centerline = if path.has_curves() {
+        path.flatten(options.tolerance)
+    } else {
+        path.clone()
+    };
 
+    (stroke_flattened(&centerline, style, options), centerline)
+}
+
+/// Returns a box that contains the outline `stroke_to_path` would give for
+/// `path` and `style`. This is cheap to compute because it just grows the
+/// bounds of `path` by how far the stroke could reach past it, assuming
+/// the worst case for every cap and join. Use `stroke_outline_bounds` when
+/// a tight box is needed.
+pub fn stroke_bounds(path: &Path, style: &StrokeStyle) -> Box2D<f32> {
+    let bounds = path.bounds();
+    if path.ops.is_empty() || style.width <= 0. {
+        return bounds;
+    }
+    let mut reach: f32 = 1.;
+    if matches!(style.cap, LineCap::Square | LineCap::Rounded(_)) {
+        reach = reach.max(std::f32::consts::SQRT_2);
+    }
+    if style.join == LineJoin::Miter || style.join == LineJoin::MiterOrRound {
+        reach = reach.max(style.miter_limit);
+    }
+    bounds.inflate(reach * style.width / 2., reach * style.width / 2.)
+}
+
+/// Returns the smallest box that contains the outline `stroke_to_path` would
+/// give for `path` and `style`. Unlike `stroke_bounds` this accounts for where
+/// each cap and join actually reaches, like the real length of each miter, but
+/// it's much more expensive because it strokes the path.
+pub fn stroke_outline_bounds(path: &Path, style: &StrokeStyle) -> Box2D<f32> {
+    stroke_to_path(path, style).bounds()
+}
+
+/// Strokes only the part of `path` between the distances `start_len` and
+/// `end_len` along it, as given by `Path::slice_by_length`, with caps where
+/// it's cut. Growing `end_len` over time animates the path drawing itself.
+/// Curves are flattened using the default tolerance.
+pub fn stroke_range(path: &Path, style: &StrokeStyle, start_len: f32, end_len: f32) -> Path {
+    let options = StrokeOptions::default();
+    stroke_flattened(&path.slice_by_length(start_len, end_len, options.tolerance), style, &options)
+}
+
+/// Strokes `path` with `style` once for each of `widths` and returns the
+/// outlines ordered from the widest to the narrowest, ready to be filled
+/// back to front for glow effects. Curves are only flattened once for all
+/// of the outlines.
+pub fn stroke_concentric(path: &Path, style: &StrokeStyle, widths: &[f32], options: &StrokeOptions) -> Vec<Path> {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    let mut widths = widths.to_vec();
+    widths.sort_by(|a, b| b.partial_cmp(a).unwrap_or(Ordering::Equal));
+    let mut style = style.clone();
+    widths.into_iter().map(|width| {
+        style.width = width;
+        stroke_flattened(path, &style, options)
+    }).collect()
+}
+
+fn stroke_flattened(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> Path {
     if style.width <= 0. {
-        return stroked_path.finish();
+        return PathBuilder::new().finish();
     }
 
-    let mut cur_pt = None;
-    let mut last_normal = Vector::zero();
-    let half_width = style.width / 2.;
-    let mut start_point = None;
+    // the fast path doesn't snap or use the reference normals, and rectangles
+    // would then jump when they pass through being axis aligned
+    if options.offset_snap <= 0. && !options.reference {
+        if let Some(stroked) = stroke_rect(path, style) {
+            return stroked;
+        }
+    }
+
+    let mut pb = PathBuilder::new();
+    stroke_flattened_to_sink(path, style, options, &mut pb);
+    pb.finish()
+}
+
+/// Strokes `path` with a calligraphic pen: a flat nib of `width` held at a fixed
+/// `angle`, in radians from the x axis, instead of a round pen. The result is
+/// thick where the path crosses the nib and thin where it runs along it.
+///
+/// The outline is the area swept by the nib as it's dragged along `path`. Because
+/// the nib doesn't turn, the area swept at each vertex is already covered by the
+/// segments on either side of it so no joins or caps are needed. Curves are
+/// flattened using `options.tolerance`.
+pub fn stroke_calligraphic(path: &Path, width: f32, angle: f32, options: &StrokeOptions) -> Path {
+    let nib = Vector::new(angle.cos(), angle.sin()) * (width / 2.);
+    let mut pb = PathBuilder::new();
+    for (p0, p1) in path.flatten(options.tolerance).line_segments() {
+        // give every parallelogram the same winding so that the overlaps
+        // between them don't cancel out when filled
+        let nib = if (p1 - p0).cross(nib) < 0. { -nib } else { nib };
+        pb.move_to(p0.x + nib.x, p0.y + nib.y);
+        pb.line_to(p1.x + nib.x, p1.y + nib.y);
+        pb.line_to(p1.x - nib.x, p1.y - nib.y);
+        pb.line_to(p0.x - nib.x, p0.y - nib.y);
+        pb.close();
+    }
+    pb.finish()
+}
+
+/// Strokes `path` with a width that varies along it, like the strokes of a
+/// pressure sensitive pen. `widths[i]` is the half width at the `i`th vertex of
+/// `path`, counting the end point of every op except `Close`, so there should
+/// be one entry per op that isn't a `Close`. Missing entries repeat the last
+/// one and extra entries are ignored.
+///
+/// The width changes linearly along each line, making each segment a trapezoid
+/// instead of a rectangle, and along curves by distance. Joins and caps use the
+/// width at their vertex along with `style.join`, `style.cap` and
+/// `style.miter_limit`. `style.width` and the dash pattern are ignored. Curves
+/// are flattened using the default tolerance.
+pub fn stroke_variable_width(path: &Path, widths: &[f32], style: &StrokeStyle) -> Path {
+    let options = StrokeOptions::default();
+    let mut pb = PathBuilder::new();
+    let width_at = |i: usize| match widths.get(i).or_else(|| widths.last()) {
+        Some(&width) => width.max(0.),
+        None => 0.,
+    };
+
+    // the flattened vertices of the current subpath with their half widths
+    let mut vertices: Vec<(Point, f32)> = Vec::new();
+    let mut start = None;
+    let mut local = StrokeStyle { dash_array: Vec::new(), ..style.clone() };
+    let mut i = 0;
     for op in &path.ops {
+        let width = width_at(i);
         match *op {
             PathOp::MoveTo(pt) => {
-                if let (Some(cur_pt), Some((point, normal))) = (cur_pt, start_point) {
-                    // cap end
-                    cap_line(&mut stroked_path, style, cur_pt, last_normal);
-                    // cap beginning
-                    cap_line(&mut stroked_path, style, point, flip(normal));
-                }
-                start_point = None;
-                cur_pt = Some(pt);
+                variable_width_subpath(&mut pb, &mut local, &options, &vertices, false);
+                vertices.clear();
+                vertices.push((pt, width));
+                start = Some((pt, width));
             }
             PathOp::LineTo(pt) => {
-                if cur_pt.is_none() {
-                    start_point = None;
-                } else if let Some(cur_pt) = cur_pt {
-                    if let Some(normal) = compute_normal(cur_pt, pt) {
-                        if start_point.is_none() {
-                            start_point = Some((cur_pt, normal));
-                        } else {
-                            join_line(&mut stroked_path, style, cur_pt, last_normal, normal);
-                        }
+                if vertices.is_empty() {
+                    vertices.extend(start);
+                }
+                vertices.push((pt, width));
+            }
+            PathOp::QuadTo(..) | PathOp::CubicTo(..) => {
+                if vertices.is_empty() {
+                    vertices.extend(start);
+                }
+                let (from, from_width) = match vertices.last() {
+                    Some(&last) => last,
+                    None => (Point::zero(), width),
+                };
+                let mut curve = Path { ops: vec![PathOp::MoveTo(from), *op], winding: path.winding }
+                    .flatten(options.tolerance)
+                    .ops;
+                let points: Vec<Point> = curve.drain(1..).filter_map(|op| match op {
+                    PathOp::LineTo(pt) => Some(pt),
+                    _ => None,
+                }).collect();
+                // spread the change in width over the curve by distance
+                let mut lengths = Vec::with_capacity(points.len());
+                let mut total = 0.;
+                let mut prev = from;
+                for &pt in &points {
+                    total += (pt - prev).length();
+                    lengths.push(total);
+                    prev = pt;
+                }
+                for (&pt, &length) in points.iter().zip(&lengths) {
+                    let t = if total > 0. { length / total } else { 1. };
+                    vertices.push((pt, from_width + (width - from_width) * t));
+                }
+            }
+            PathOp::Close => {
+                variable_width_subpath(&mut pb, &mut local, &options, &vertices, true);
+                vertices.clear();
+                continue;
+            }
+        }
+        i += 1;
+    }
+    variable_width_subpath(&mut pb, &mut local, &options, &vertices, false);
+    pb.finish()
+}
+
+/* Strokes one subpath for `stroke_variable_width`. `style.width` is set to the local
+ * width for each join and cap. */
+fn variable_width_subpath(
+    dest: &mut PathBuilder,
+    style: &mut StrokeStyle,
+    options: &StrokeOptions,
+    vertices: &[(Point, f32)],
+    closed: bool,
+) {
+    let mut segments = Vec::new();
+    let closing = if closed { vertices.first() } else { None };
+    for pair in vertices.windows(2).map(|w| (w[0], w[1])).chain(vertices.last().copied().zip(closing.copied())) {
+        let ((p0, w0), (p1, w1)) = pair;
+        if let Some(normal) = compute_normal(p0, p1) {
+            segments.push((p0, p1, SegmentEnd::new(p0, normal, w0), SegmentEnd::new(p1, normal, w1), w0, w1));
+        }
+    }
+    let (first, last) = match (segments.first(), segments.last()) {
+        (Some(&first), Some(&last)) => (first, last),
+        _ => return,
+    };
+
+    let mut prev: Option<(Point, SegmentEnd)> = None;
+    for &(p0, p1, start, end, w0, _) in &segments {
+        if let Some((pt, last_end)) = prev {
+            style.width = w0 * 2.;
+            join_line(dest, style, options, style.join, pt, last_end, start);
+        }
+        segment(dest, p0, p1, start, end);
+        prev = Some((p1, end));
+    }
+    let (p0, _, start, _, w0, _) = first;
+    let (_, p1, _, end, _, w1) = last;
+    if closed {
+        style.width = w0 * 2.;
+        join_line(dest, style, options, style.join, p0, end, start);
+    } else {
+        style.width = w0 * 2.;
+        cap_line(dest, style, options, p0, start.normal.flip());
+        style.width = w1 * 2.;
+        cap_line(dest, style, options, p1, end.normal);
+    }
+}
 
-                        stroked_path.move_to(
-                            cur_pt.x + normal.x * half_width,
-                            cur_pt.y + normal.y * half_width,
-                        );
-                        stroked_path.line_to(pt.x + normal.x * half_width, pt.y + normal.y * half_width);
-                        // we add a point at the midpoint of the line so that our edge has matching
-                        // end points with the edges used for joining. This avoids seams during
-                        // rasterization caused by precision differences in the slope and endpoints
-                        stroked_path.line_to(pt.x, pt.y);
-                        stroked_path.line_to(pt.x + -normal.x * half_width, pt.y + -normal.y * half_width);
-                        stroked_path.line_to(
-                            cur_pt.x - normal.x * half_width,
-                            cur_pt.y - normal.y * half_width,
-                        );
-                        stroked_path.line_to(cur_pt.x, cur_pt.y);
-
-                        stroked_path.close();
-
-                        last_normal = normal;
+/// Like `stroke_to_path_with_options` but keeps the curves of `path` as curves.
+/// Each side of a curve is offset by half the width and approximated with
+/// `cubic_to` segments, instead of flattening the curve to lines, which gives
+/// much smaller outlines for exporting to formats like SVG.
+///
+/// This is a best-effort approximation. A curve is split in half until each
+/// piece's offsets are within `options.tolerance` of the true offset at a few
+/// sample points along it. Pieces that still don't fit after a number of splits,
+/// like those around cusps or where the curve bends more tightly than half the
+/// width, are flattened and stroked as lines. Joins are only added where the
+/// direction of the path changes so a smooth curve is a single band.
+/// Paths without curves give the same result as `stroke_to_path_with_options`.
+pub fn stroke_to_path_with_curves(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> Path {
+    if !path.has_curves() {
+        return stroke_to_path_with_options(path, style, options);
+    }
+    let mut pb = PathBuilder::new();
+    if style.width <= 0. {
+        return pb.finish();
+    }
 
+    let half_width = style.width / 2.;
+    let mut pieces = Vec::new();
+    let mut cur_pt = None;
+    let mut start_pt = None;
+    // whether we've moved to a new subpath that hasn't been closed yet
+    let mut moved = false;
+    // whether the current subpath has any lines or curves, even if they don't go anywhere
+    let mut drawn = false;
+    let open_subpath = |pb: &mut PathBuilder, pieces: &[StrokePiece], cur_pt: Option<Point>, drawn: bool| {
+        match cur_pt {
+            // drawn as a dot, like `stroke_to_path` does
+            Some(pt) if drawn && pieces.is_empty() => cap_dot(pb, style, options, pt),
+            _ => stroke_pieces(pb, style, options, pieces, false),
+        }
+    };
+    for op in &path.ops {
+        if let (PathOp::LineTo(..) | PathOp::QuadTo(..) | PathOp::CubicTo(..), Some(_)) = (op, cur_pt) {
+            drawn = true;
+        }
+        match *op {
+            PathOp::MoveTo(pt) => {
+                open_subpath(&mut pb, &pieces, cur_pt, drawn);
+                pieces.clear();
+                drawn = false;
+                cur_pt = Some(pt);
+                start_pt = Some(pt);
+                moved = true;
+            }
+            PathOp::LineTo(pt) => {
+                match cur_pt {
+                    Some(from) => push_line_piece(&mut pieces, from, pt),
+                    None => start_pt = Some(pt),
+                }
+                cur_pt = Some(pt);
+            }
+            PathOp::QuadTo(ctrl, pt) => {
+                match cur_pt {
+                    Some(from) => {
+                        let curve = QuadraticBezierSegment { from, ctrl, to: pt }.to_cubic();
+                        push_curve_pieces(&mut pieces, &curve, half_width, options.tolerance);
                     }
+                    None => start_pt = Some(pt),
+                }
+                cur_pt = Some(pt);
+            }
+            PathOp::CubicTo(ctrl1, ctrl2, pt) => {
+                match cur_pt {
+                    Some(from) => {
+                        let curve = CubicBezierSegment { from, ctrl1, ctrl2, to: pt };
+                        push_curve_pieces(&mut pieces, &curve, half_width, options.tolerance);
+                    }
+                    None => start_pt = Some(pt),
                 }
                 cur_pt = Some(pt);
-
             }
             PathOp::Close => {
-                if let (Some(cur_pt), Some((end_point, start_normal))) = (cur_pt, start_point) {
-                    if let Some(normal) = compute_normal(cur_pt, end_point) {
-                        join_line(&mut stroked_path, style, cur_pt, last_normal, normal);
-
-                        // the closing line segment
-                        stroked_path.move_to(
-                            cur_pt.x + normal.x * half_width,
-                            cur_pt.y + normal.y * half_width,
-                        );
-                        stroked_path.line_to(
-                            end_point.x + normal.x * half_width,
-                            end_point.y + normal.y * half_width,
-                        );
-                        stroked_path.line_to(
-                            end_point.x,
-                            end_point.y,
-                        );
-                        stroked_path.line_to(
-                            end_point.x + -normal.x * half_width,
-                            end_point.y + -normal.y * half_width,
-                        );
-                        stroked_path.line_to(
-                            cur_pt.x - normal.x * half_width,
-                            cur_pt.y - normal.y * half_width,
-                        );
-                        stroked_path.line_to(
-                            cur_pt.x,
-                            cur_pt.y,
-                        );
-                        stroked_path.close();
-
-                        join_line(&mut stroked_path, style, end_point, normal, start_normal);
-                    } else {
-                        join_line(&mut stroked_path, style, end_point, last_normal, start_normal);
+                if let (Some(from), Some(to)) = (cur_pt, start_pt) {
+                    push_line_piece(&mut pieces, from, to);
+                }
+                if pieces.is_empty() {
+                    if let (Some(pt), true) = (cur_pt, moved) {
+                        // drawn as a dot, like `stroke_to_path` does
+                        cap_dot(&mut pb, style, options, pt);
                     }
                 }
-                cur_pt = start_point.map(|x| x.0);
-                start_point = None;
+                stroke_pieces(&mut pb, style, options, &pieces, true);
+                pieces.clear();
+                cur_pt = start_pt;
+                moved = false;
+                drawn = false;
             }
-            PathOp::QuadTo(..) => panic!("Only flat paths handled"),
-            PathOp::CubicTo(..) => panic!("Only flat paths handled"),
         }
     }
-    if let (Some(cur_pt), Some((point, normal))) = (cur_pt, start_point) {
-        // cap end
-        cap_line(&mut stroked_path, style, cur_pt, last_normal);
-        // cap beginning
-        cap_line(&mut stroked_path, style, point, flip(normal));
+    open_subpath(&mut pb, &pieces, cur_pt, drawn);
+    pb.finish()
+}
+
+/// How many times a curve is split in half looking for pieces whose offsets
+/// fit a single cubic before giving up and flattening it.
+const MAX_OFFSET_DEPTH: u32 = 8;
+
+/* A line or curve of a subpath stroked by `stroke_to_path_with_curves`. Curves carry
+ * the approximations of their offsets on the plus and minus sides. */
+struct StrokePiece {
+    from: Point,
+    to: Point,
+    start_normal: UnitVector,
+    end_normal: UnitVector,
+    offsets: Option<(CubicBezierSegment<f32>, CubicBezierSegment<f32>)>,
+}
+
+fn push_line_piece(pieces: &mut Vec<StrokePiece>, from: Point, to: Point) {
+    if let Some(normal) = compute_normal(from, to) {
+        pieces.push(StrokePiece { from, to, start_normal: normal, end_normal: normal, offsets: None });
+    }
+}
+
+/* The normals at the ends of `curve`, using the nearest distinct control point when
+ * a control point sits on an end point. A curve that doesn't go anywhere has none. */
+fn cubic_normals(curve: &CubicBezierSegment<f32>) -> Option<(UnitVector, UnitVector)> {
+    let start = compute_normal(curve.from, curve.ctrl1)
+        .or_else(|| compute_normal(curve.from, curve.ctrl2))
+        .or_else(|| compute_normal(curve.from, curve.to))?;
+    let end = compute_normal(curve.ctrl2, curve.to)
+        .or_else(|| compute_normal(curve.ctrl1, curve.to))
+        .or_else(|| compute_normal(curve.from, curve.to))?;
+    Some((start, end))
+}
+
+fn push_curve_pieces(pieces: &mut Vec<StrokePiece>, curve: &CubicBezierSegment<f32>, half_width: f32, tolerance: f32) {
+    if let Some((start, end)) = cubic_normals(curve) {
+        push_offset_pieces(pieces, curve, start, end, half_width, tolerance, 0);
+    }
+}
+
+fn push_offset_pieces(
+    pieces: &mut Vec<StrokePiece>,
+    curve: &CubicBezierSegment<f32>,
+    start_normal: UnitVector,
+    end_normal: UnitVector,
+    half_width: f32,
+    tolerance: f32,
+    depth: u32,
+) {
+    let start = SegmentEnd::new(curve.from, start_normal, half_width);
+    let end = SegmentEnd::new(curve.to, end_normal, half_width);
+    let plus = offset_cubic(curve, start.plus, end.plus, half_width);
+    let minus = offset_cubic(curve, start.minus, end.minus, -half_width);
+    if offset_fits(curve, &plus, half_width, tolerance) && offset_fits(curve, &minus, -half_width, tolerance) {
+        pieces.push(StrokePiece {
+            from: curve.from,
+            to: curve.to,
+            start_normal,
+            end_normal,
+            offsets: Some((plus, minus)),
+        });
+    } else if depth < MAX_OFFSET_DEPTH {
+        let (left, right) = curve.split(0.5);
+        // both halves share the normal at the split so that their offsets meet exactly
+        let mid_normal = compute_normal(left.ctrl2, right.ctrl1)
+            .or_else(|| cubic_normals(&right).map(|n| n.0))
+            .unwrap_or(start_normal);
+        push_offset_pieces(pieces, &left, start_normal, mid_normal, half_width, tolerance, depth + 1);
+        push_offset_pieces(pieces, &right, mid_normal, end_normal, half_width, tolerance, depth + 1);
+    } else {
+        let mut from = curve.from;
+        flatten_cubic(curve, tolerance, &mut |to| {
+            push_line_piece(pieces, from, to);
+            from = to;
+        });
+    }
+}
+
+/* Approximates the offset of `curve` by `distance` along its normal, from `from` to `to`,
+ * by moving the control points with the end points and scaling them by how much the
+ * offset shrinks or grows the curvature at each end. */
+fn offset_cubic(curve: &CubicBezierSegment<f32>, from: Point, to: Point, distance: f32) -> CubicBezierSegment<f32> {
+    let scale = |d1: Vector, d2: Vector| {
+        let len = d1.length();
+        if len == 0. {
+            return 1.;
+        }
+        // the curve bends towards the normal when the curvature is positive
+        let curvature = d1.cross(d2) / (len * len * len);
+        (1. - distance * curvature).max(0.)
+    };
+    let start_scale = scale((curve.ctrl1 - curve.from) * 3., (curve.ctrl2 - curve.ctrl1 * 2. + curve.from.to_vector()) * 6.);
+    let end_scale = scale((curve.to - curve.ctrl2) * 3., (curve.to - curve.ctrl2 * 2. + curve.ctrl1.to_vector()) * 6.);
+    CubicBezierSegment {
+        from,
+        ctrl1: from + (curve.ctrl1 - curve.from) * start_scale,
+        ctrl2: to + (curve.ctrl2 - curve.to) * end_scale,
+        to,
+    }
+}
+
+/* Whether `offset` stays within `tolerance` of `curve` moved by `distance` along its
+ * normal, and runs in the same direction as it, at some samples along them */
+fn offset_fits(curve: &CubicBezierSegment<f32>, offset: &CubicBezierSegment<f32>, distance: f32, tolerance: f32) -> bool {
+    const SAMPLES: u32 = 8;
+    (1..SAMPLES).all(|i| {
+        let t = i as f32 / SAMPLES as f32;
+        let derivative = curve.derivative(t);
+        let normal = match compute_normal(Point::zero(), derivative.to_point()) {
+            Some(normal) => normal,
+            // a cusp, which can't be offset
+            None => return false,
+        };
+        let expected = curve.sample(t) + normal * distance;
+        (offset.sample(t) - expected).length() <= tolerance && dot(offset.derivative(t), derivative) > 0.
+    })
+}
+
+/* Strokes the pieces of one subpath, adding joins where the direction changes between
+ * them and caps at the ends unless it's `closed` */
+fn stroke_pieces(dest: &mut PathBuilder, style: &StrokeStyle, options: &StrokeOptions, pieces: &[StrokePiece], closed: bool) {
+    let (first, last) = match (pieces.first(), pieces.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return,
+    };
+    let half_width = style.width / 2.;
+    let mut last_end: Option<SegmentEnd> = None;
+    for piece in pieces {
+        let start = SegmentEnd::new(piece.from, piece.start_normal, half_width);
+        let end = SegmentEnd::new(piece.to, piece.end_normal, half_width);
+        if let Some(last_end) = last_end {
+            if last_end.normal != start.normal {
+                join_line(dest, style, options, style.join, piece.from, last_end, start);
+            }
+        }
+        match piece.offsets {
+            Some((plus, minus)) => {
+                dest.move_to(start.plus.x, start.plus.y);
+                dest.cubic_to(plus.ctrl1.x, plus.ctrl1.y, plus.ctrl2.x, plus.ctrl2.y, end.plus.x, end.plus.y);
+                dest.line_to(piece.to.x, piece.to.y);
+                dest.line_to(end.minus.x, end.minus.y);
+                dest.cubic_to(minus.ctrl2.x, minus.ctrl2.y, minus.ctrl1.x, minus.ctrl1.y, start.minus.x, start.minus.y);
+                dest.line_to(piece.from.x, piece.from.y);
+                dest.close();
+            }
+            None => segment(dest, piece.from, piece.to, start, end),
+        }
+        last_end = Some(end);
+    }
+    if closed {
+        if last.end_normal != first.start_normal {
+            let start = SegmentEnd::new(first.from, first.start_normal, half_width);
+            let end = SegmentEnd::new(last.to, last.end_normal, half_width);
+            join_line(dest, style, options, style.join, first.from, end, start);
+        }
+    } else {
+        cap_line(dest, style, options, last.to, last.end_normal);
+        cap_line(dest, style, options, first.from, first.start_normal.flip());
+    }
+}
+
+/// The pieces of a stroke grouped by what produced them so that they
+/// can be filled with different sources. Filling all three with the same
+/// source gives the same result as filling the output of `stroke_to_path`.
+#[derive(Clone, Debug)]
+pub struct StrokeParts {
+    /// The quads along each segment of the path
+    pub body: Path,
+    /// The caps at the ends of open subpaths
+    pub caps: Path,
+    /// The joins between segments
+    pub joins: Path,
+}
+
+/// Like `stroke_to_path_with_options` but keeps the body, caps and joins of
+/// the stroke separate.
+pub fn stroke_to_parts(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> StrokeParts {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    let mut out = StrokeOutput {
+        body: PathBuilder::new(),
+        parts: Some((PathBuilder::new(), PathBuilder::new())),
+    };
+    if style.width > 0. {
+        stroke_into(path, style, options, &|_| style.join, &mut out);
+    }
+    let (caps, joins) = out.parts.unwrap();
+    StrokeParts {
+        body: out.body.finish(),
+        caps: caps.finish(),
+        joins: joins.finish(),
+    }
+}
+
+/// Where the stroker puts the geometry it generates. Unless the parts
+/// are kept separate the caps and joins go in the body.
+struct StrokeOutput<D> {
+    body: D,
+    parts: Option<(D, D)>,
+}
+
+impl<D: StrokeDest> StrokeOutput<D> {
+    fn caps(&mut self) -> &mut D {
+        match &mut self.parts {
+            Some((caps, _)) => caps,
+            None => &mut self.body,
+        }
+    }
+
+    fn joins(&mut self) -> &mut D {
+        match &mut self.parts {
+            Some((_, joins)) => joins,
+            None => &mut self.body,
+        }
+    }
+}
+
+/// Receives the geometry of a stroke from `stroke_to_sink` as it's generated.
+/// The stroke is the union of every triangle and sector passed to the sink so
+/// they can be drawn in any order without regard to winding.
+pub trait StrokeSink {
+    /// A filled triangle
+    fn triangle(&mut self, a: Point, b: Point, c: Point);
+    /// A filled sector of the circle around `center` with `radius`, from the
+    /// direction `from` to the direction `to`. They're never more than a
+    /// quarter turn apart and the sector is the smaller side between them.
+    fn arc(&mut self, center: Point, radius: f32, from: UnitVector, to: UnitVector);
+    /// A convex piece of the stroke, like a segment's quad or a cap or a join,
+    /// starting at `start` and closed after the last of `edges`. By default
+    /// it's split into the sectors of its arcs and a fan of triangles around
+    /// `start`, leaving out the empty ones, and passed to `arc` and
+    /// `triangle`. Sinks that can take whole polygons can override this.
+    fn piece(&mut self, start: Point, edges: &[PieceEdge]) {
+        for edge in edges {
+            if let PieceEdge::Arc { center, radius, from, to } = *edge {
+                self.arc(center, radius, from, to);
+            }
+        }
+        let mut last = start;
+        for edge in edges {
+            let end = edge.end();
+            // the pieces have points in the middle of their edges which
+            // would give empty triangles
+            if (last - start).cross(end - start) != 0. {
+                self.triangle(start, last, end);
+            }
+            last = end;
+        }
+    }
+}
+
+/// An edge of a piece of a stroke passed to `StrokeSink::piece`
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum PieceEdge {
+    /// A straight edge to this point
+    Line(Point),
+    /// An arc of at most a quarter circle around `center` with `radius`, from
+    /// the direction `from` to the direction `to`
+    Arc { center: Point, radius: f32, from: UnitVector, to: UnitVector },
+}
+
+impl PieceEdge {
+    /// Returns the point the edge ends at
+    pub fn end(&self) -> Point {
+        match *self {
+            PieceEdge::Line(pt) => pt,
+            PieceEdge::Arc { center, radius, to, .. } => center + to * radius,
+        }
+    }
+}
+
+/// Collects the pieces as the path `stroke_to_path` returns: a closed
+/// polygon for each piece, with cubics for its arcs. Triangles and sectors
+/// passed on their own are added as pieces of their own.
+impl StrokeSink for PathBuilder {
+    fn triangle(&mut self, a: Point, b: Point, c: Point) {
+        self.move_to(a.x, a.y);
+        self.line_to(b.x, b.y);
+        self.line_to(c.x, c.y);
+        self.close();
+    }
+
+    fn arc(&mut self, center: Point, radius: f32, from: UnitVector, to: UnitVector) {
+        let start = center + from * radius;
+        self.move_to(center.x, center.y);
+        self.line_to(start.x, start.y);
+        arc_segment(self, center.x, center.y, radius, from, to);
+        self.close();
+    }
+
+    fn piece(&mut self, start: Point, edges: &[PieceEdge]) {
+        self.move_to(start.x, start.y);
+        for edge in edges {
+            match *edge {
+                PieceEdge::Line(pt) => self.line_to(pt.x, pt.y),
+                PieceEdge::Arc { center, radius, from, to } => arc_segment(self, center.x, center.y, radius, from, to),
+            }
+        }
+        self.close();
+    }
+}
+
+/* Passes each of the convex polygons the stroker draws to a `StrokeSink` as a piece. */
+struct SinkDest<'a> {
+    sink: &'a mut dyn StrokeSink,
+    start: Point,
+    edges: Vec<PieceEdge>,
+}
+
+impl<'a> SinkDest<'a> {
+    fn new(sink: &'a mut dyn StrokeSink) -> SinkDest<'a> {
+        SinkDest { sink, start: Point::origin(), edges: Vec::new() }
+    }
+}
+
+impl<'a> StrokeDest for SinkDest<'a> {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.start = Point::new(x, y);
+        self.edges.clear();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.edges.push(PieceEdge::Line(Point::new(x, y)));
+    }
+
+    fn arc_segment(&mut self, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector) {
+        self.edges.push(PieceEdge::Arc { center: Point::new(xc, yc), radius, from: a, to: b });
+    }
+
+    fn close(&mut self) {
+        self.sink.piece(self.start, &self.edges);
+        self.edges.clear();
+    }
+}
+
+/// Strokes `path` with `style` like `stroke_to_path_with_options` but passes
+/// the geometry to `sink` as it's generated instead of building a `Path`.
+/// Curves are flattened using `options.tolerance`. `stroke_to_path` is built
+/// on this with a `PathBuilder` as the sink, except for rectangles, which it
+/// strokes directly.
+pub fn stroke_to_sink(path: &Path, style: &StrokeStyle, options: &StrokeOptions, sink: &mut dyn StrokeSink) {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    if style.width > 0. {
+        stroke_flattened_to_sink(path, style, options, sink);
+    }
+}
+
+fn stroke_flattened_to_sink(path: &Path, style: &StrokeStyle, options: &StrokeOptions, sink: &mut dyn StrokeSink) {
+    let mut out = StrokeOutput { body: SinkDest::new(sink), parts: None };
+    stroke_into(path, style, options, &|_| style.join, &mut out);
+}
+
+/// Strokes `path` with `style` into an indexed triangle list for uploading to a
+/// GPU: a list of vertices and a list of indices into it, three per triangle.
+/// Vertices closer than `merge_distance` to one already in the list are merged
+/// with it so that the corners shared by neighbouring pieces are only stored
+/// once. A `merge_distance` of zero only merges vertices that are identical.
+///
+/// All of the triangles wind the same way. Round caps and joins are split into
+/// triangles that stay within the default tolerance of the true arc.
+pub fn stroke_to_indexed(path: &Path, style: &StrokeStyle, merge_distance: f32) -> (Vec<Point>, Vec<u32>) {
+    let mut sink = IndexedSink {
+        vertices: Vec::new(),
+        indices: Vec::new(),
+        grid: HashMap::new(),
+        merge_distance,
+        tolerance: StrokeOptions::default().round_tolerance,
+    };
+    stroke_to_sink(path, style, &StrokeOptions::default(), &mut sink);
+    (sink.vertices, sink.indices)
+}
+
+/* Collects triangles for `stroke_to_indexed`, using a grid of cells `merge_distance` wide
+ * to find vertices to merge with. */
+struct IndexedSink {
+    vertices: Vec<Point>,
+    indices: Vec<u32>,
+    grid: HashMap<(i64, i64), Vec<u32>>,
+    merge_distance: f32,
+    tolerance: f32,
+}
+
+impl IndexedSink {
+    fn index(&mut self, pt: Point) -> u32 {
+        if self.merge_distance <= 0. {
+            let key = (pt.x.to_bits() as i64, pt.y.to_bits() as i64);
+            if let Some(&i) = self.grid.get(&key).and_then(|cell| cell.first()) {
+                return i;
+            }
+            let i = self.vertices.len() as u32;
+            self.vertices.push(pt);
+            self.grid.insert(key, vec![i]);
+            return i;
+        }
+
+        let cell = ((pt.x / self.merge_distance).floor() as i64, (pt.y / self.merge_distance).floor() as i64);
+        // a vertex within the distance can be in any of the neighbouring cells
+        for dx in -1..=1 {
+            for dy in -1..=1 {
+                if let Some(candidates) = self.grid.get(&(cell.0 + dx, cell.1 + dy)) {
+                    for &i in candidates {
+                        if (self.vertices[i as usize] - pt).length() <= self.merge_distance {
+                            return i;
+                        }
+                    }
+                }
+            }
+        }
+        let i = self.vertices.len() as u32;
+        self.vertices.push(pt);
+        self.grid.entry(cell).or_default().push(i);
+        i
+    }
+}
+
+impl StrokeSink for IndexedSink {
+    fn triangle(&mut self, a: Point, b: Point, c: Point) {
+        // give every triangle the same winding so that they can be culled
+        let (b, c) = if (b - a).cross(c - a) < 0. { (c, b) } else { (b, c) };
+        let (a, b, c) = (self.index(a), self.index(b), self.index(c));
+        // merging can collapse small triangles
+        if a != b && b != c && a != c {
+            self.indices.extend_from_slice(&[a, b, c]);
+        }
+    }
+
+    fn arc(&mut self, center: Point, radius: f32, from: UnitVector, to: UnitVector) {
+        let mut prev = center + from * radius;
+        for pt in arc_points(center, radius, from, to, self.tolerance) {
+            self.triangle(center, prev, pt);
+            prev = pt;
+        }
+    }
+}
+
+/// Like `stroke_to_indexed` but also gives the distance along `path` to each
+/// vertex, for mapping a texture or gradient along the stroke in a shader.
+/// The three lists are the vertices, their distances and the indices. The
+/// quad covering each line has the distance to its start at one end and the
+/// distance to its end at the other, while caps and joins have the distance
+/// to the point they're attached to. The join where a closed subpath meets
+/// itself has the length of the subpath. Vertices are only shared when both
+/// their positions and distances are identical.
+///
+/// The distances restart from zero at the start of every subpath. Subpaths
+/// that don't go anywhere are drawn as dots, like by `stroke_to_path`, with
+/// a distance of zero.
+pub fn stroke_to_indexed_with_lengths(path: &Path, style: &StrokeStyle) -> (Vec<Point>, Vec<f32>, Vec<u32>) {
+    let options = StrokeOptions::default();
+    let mut sink = LengthSink {
+        vertices: Vec::new(),
+        lengths: Vec::new(),
+        indices: Vec::new(),
+        seen: HashMap::new(),
+        length: 0.,
+        tolerance: options.round_tolerance,
+    };
+    if style.width.is_nan() || style.width <= 0. {
+        return (sink.vertices, sink.lengths, sink.indices);
+    }
+    let half_width = style.width / 2.;
+    for (points, closed) in path.flattened_subpaths_with_dots(options.tolerance) {
+        if closed && points.len() == 1 || points.len() == 2 && points[0] == points[1] {
+            // a subpath that doesn't go anywhere is a dot, like with `stroke_to_path`
+            sink.length = 0.;
+            cap_dot(&mut sink.dest(), style, &options, points[0]);
+            continue;
+        }
+        if points.len() < 2 {
+            continue;
+        }
+        let count = if closed { points.len() } else { points.len() - 1 };
+        let mut walked = 0.;
+        let mut first: Option<SegmentEnd> = None;
+        let mut last_end: Option<SegmentEnd> = None;
+        for i in 0..count {
+            let (p0, p1) = (points[i], points[(i + 1) % points.len()]);
+            let normal = match segment_normal(&options, half_width, p0, p1) {
+                Some(normal) => normal,
+                None => continue,
+            };
+            let length = walked + (p1 - p0).length();
+            let start = SegmentEnd::new(p0, normal, half_width);
+            let end = SegmentEnd::new(p1, normal, half_width);
+            sink.length = walked;
+            match last_end {
+                Some(last_end) => join_line(&mut sink.dest(), style, &options, style.join, p0, last_end, start),
+                None => {
+                    if !closed {
+                        cap_line(&mut sink.dest(), style, &options, p0, normal.flip());
+                    }
+                    first = Some(start);
+                }
+            }
+            sink.triangle_with_lengths([(start.plus, walked), (end.plus, length), (end.minus, length)]);
+            sink.triangle_with_lengths([(start.plus, walked), (end.minus, length), (start.minus, walked)]);
+            last_end = Some(end);
+            walked = length;
+        }
+        sink.length = walked;
+        if let (Some(first), Some(last_end)) = (first, last_end) {
+            if closed {
+                join_line(&mut sink.dest(), style, &options, style.join, points[0], last_end, first);
+            } else {
+                cap_line(&mut sink.dest(), style, &options, points[points.len() - 1], last_end.normal);
+            }
+        }
+    }
+    (sink.vertices, sink.lengths, sink.indices)
+}
+
+/* Collects triangles for `stroke_to_indexed_with_lengths`. The triangles of caps and joins
+ * come through `StrokeSink` and get `length` as their distance. */
+struct LengthSink {
+    vertices: Vec<Point>,
+    lengths: Vec<f32>,
+    indices: Vec<u32>,
+    seen: HashMap<(u32, u32, u32), u32>,
+    length: f32,
+    tolerance: f32,
+}
+
+impl LengthSink {
+    fn dest(&mut self) -> SinkDest<'_> {
+        SinkDest::new(self)
+    }
+
+    fn index(&mut self, pt: Point, length: f32) -> u32 {
+        let vertices = &mut self.vertices;
+        let lengths = &mut self.lengths;
+        *self.seen.entry((pt.x.to_bits(), pt.y.to_bits(), length.to_bits())).or_insert_with(|| {
+            vertices.push(pt);
+            lengths.push(length);
+            vertices.len() as u32 - 1
+        })
+    }
+
+    fn triangle_with_lengths(&mut self, corners: [(Point, f32); 3]) {
+        let [a, b, c] = corners;
+        // give every triangle the same winding, like `stroke_to_indexed`
+        let (b, c) = if (b.0 - a.0).cross(c.0 - a.0) < 0. { (c, b) } else { (b, c) };
+        let indices = [self.index(a.0, a.1), self.index(b.0, b.1), self.index(c.0, c.1)];
+        self.indices.extend_from_slice(&indices);
+    }
+}
+
+impl StrokeSink for LengthSink {
+    fn triangle(&mut self, a: Point, b: Point, c: Point) {
+        let length = self.length;
+        self.triangle_with_lengths([(a, length), (b, length), (c, length)]);
+    }
+
+    fn arc(&mut self, center: Point, radius: f32, from: UnitVector, to: UnitVector) {
+        let mut prev = center + from * radius;
+        for pt in arc_points(center, radius, from, to, self.tolerance) {
+            self.triangle(center, prev, pt);
+            prev = pt;
+        }
+    }
+}
+
+/* The points after the first along the arc around `center` from the unit vector `from` to
+ * the unit vector `to`, the short way around, such that the chords between them stay within
+ * `tolerance` of the arc. A chord of angle t is at most radius * (1 - cos(t / 2)) from it. */
+pub(crate) fn arc_points(center: Point, radius: f32, from: UnitVector, to: UnitVector, tolerance: f32) -> Vec<Point> {
+    let angle = from.cross(*to).atan2(from.dot(*to));
+    let max_angle = if tolerance < radius {
+        2. * (1. - tolerance / radius).acos()
+    } else {
+        std::f32::consts::PI
+    };
+    let steps = (angle.abs() / max_angle).ceil().clamp(1., 64.) as usize;
+    (1..=steps).map(|i| {
+        if i == steps {
+            center + to * radius
+        } else {
+            let (sin, cos) = (angle * i as f32 / steps as f32).sin_cos();
+            center + Vector::new(from.x * cos - from.y * sin, from.x * sin + from.y * cos) * radius
+        }
+    }).collect()
+}
+
+/// Like `stroke_to_path_with_options` but none of the pieces of the stroke
+/// overlap, so that each piece can be drawn on its own with a translucent
+/// source, or by a rasterizer that adds up the coverage of every polygon,
+/// without the overlaps being drawn twice. The pieces are convex polygons
+/// cut against all of the pieces before them, not only their neighbours, so
+/// where a path crosses itself, like a figure eight, the crossing is covered
+/// once. Round caps and joins are flattened using `options.round_tolerance`.
+/// `options.bleed` is ignored because it makes the pieces overlap on purpose.
+///
+/// This is more expensive than `stroke_to_path_with_options` and makes
+/// more pieces, mostly on the inside of joins where neighbouring segments
+/// cross.
+pub fn stroke_to_path_non_overlapping(path: &Path, style: &StrokeStyle, options: &StrokeOptions) -> Path {
+    let flattened;
+    let path = if path.has_curves() {
+        flattened = path.flatten(options.tolerance);
+        &flattened
+    } else {
+        path
+    };
+
+    let options = StrokeOptions { bleed: 0., ..*options };
+    let mut out = StrokeOutput {
+        body: TilingDest {
+            tolerance: options.round_tolerance,
+            points: Vec::new(),
+            placed: Vec::new(),
+            pb: PathBuilder::new(),
+        },
+        parts: None,
+    };
+    if style.width > 0. {
+        stroke_into(path, style, &options, &|_| style.join, &mut out);
+    }
+    out.body.pb.finish()
+}
+
+/* Cuts each polygon the stroker draws against the ones before it for
+ * `stroke_to_path_non_overlapping`. `placed` keeps the uncut polygons, whose union is the
+ * same as the union of the pieces, along with their bounds for skipping the ones that
+ * are too far away to overlap. */
+struct TilingDest {
+    tolerance: f32,
+    points: Vec<Point>,
+    placed: Vec<(Box2D<f32>, Vec<Point>)>,
+    pb: PathBuilder,
+}
+
+impl StrokeDest for TilingDest {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.points.clear();
+        self.points.push(Point::new(x, y));
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.points.push(Point::new(x, y));
+    }
+
+    fn arc_segment(&mut self, xc: f32, yc: f32, radius: f32, a: UnitVector, b: UnitVector) {
+        let points = arc_points(Point::new(xc, yc), radius, a, b, self.tolerance);
+        self.points.extend(points);
+    }
+
+    fn close(&mut self) {
+        let polygon = std::mem::take(&mut self.points);
+        if polygon.len() < 3 {
+            return;
+        }
+        let bounds = Box2D::from_points(&polygon);
+        let mut pieces = vec![polygon.clone()];
+        for (other_bounds, other) in &self.placed {
+            if other_bounds.intersects(&bounds) {
+                pieces = pieces.into_iter().flat_map(|piece| convex_difference(piece, other)).collect();
+            }
+        }
+        for mut piece in pieces {
+            if polygon_area(&piece) < 0. {
+                piece.reverse();
+            }
+            self.pb.move_to(piece[0].x, piece[0].y);
+            for pt in &piece[1..] {
+                self.pb.line_to(pt.x, pt.y);
+            }
+            self.pb.close();
+        }
+        self.placed.push((bounds, polygon));
+    }
+}
+
+pub(crate) fn polygon_area(points: &[Point]) -> f32 {
+    let n = points.len();
+    (0..n).map(|i| points[i].to_vector().cross(points[(i + 1) % n].to_vector())).sum::<f32>() / 2.
+}
+
+/* The part of `polygon` on the side of the line through `pt` that `normal` points to, or
+ * the other side when `inside` is false. Points on the line are kept either way. */
+fn clip_convex(polygon: &[Point], pt: Point, normal: Vector, inside: bool) -> Vec<Point> {
+    let side = |p: Point| {
+        let d = dot(p - pt, normal);
+        if inside { d } else { -d }
+    };
+    let mut out = Vec::with_capacity(polygon.len() + 1);
+    for i in 0..polygon.len() {
+        let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+        let (da, db) = (side(a), side(b));
+        if da >= 0. {
+            out.push(a);
+        }
+        if (da > 0. && db < 0.) || (da < 0. && db > 0.) {
+            out.push(a.lerp(b, da / (da - db)));
+        }
+    }
+    out
+}
+
+/* Splits the parts of the convex `polygon` outside of the convex `other` into convex pieces.
+ * Each piece is what's outside of one edge of `other` and inside all of the edges before it,
+ * so the pieces don't overlap. */
+fn convex_difference(polygon: Vec<Point>, other: &[Point]) -> Vec<Vec<Point>> {
+    let area = polygon_area(&polygon).abs();
+    // ignore the slivers left by rounding where pieces share an edge
+    let negligible = |points: &[Point]| points.len() < 3 || polygon_area(points).abs() <= area * 1e-6;
+    let orientation = if polygon_area(other) < 0. { -1. } else { 1. };
+    let mut pieces = Vec::new();
+    let mut rest = polygon;
+    for i in 0..other.len() {
+        let (a, b) = (other[i], other[(i + 1) % other.len()]);
+        if a == b {
+            continue;
+        }
+        // points into `other`
+        let normal = Vector::new(a.y - b.y, b.x - a.x) * orientation;
+        let outside = clip_convex(&rest, a, normal, false);
+        if !negligible(&outside) {
+            pieces.push(outside);
+        }
+        rest = clip_convex(&rest, a, normal, true);
+        if negligible(&rest) {
+            // nothing left to cut, so no overlap with what remains of `other`
+            return pieces;
+        }
+    }
+    // `rest` is the overlap, which `other` already covers
+    pieces
+}
+
+/* The quad covering the line segment from `p0` to `p1` */
+fn segment(dest: &mut dyn StrokeDest, p0: Point, p1: Point, start: SegmentEnd, end: SegmentEnd) {
+    dest.move_to(start.plus.x, start.plus.y);
+    dest.line_to(end.plus.x, end.plus.y);
+    // we add a point at the midpoint of the line so that our edge has matching
+    // end points with the edges used for joining. This avoids seams during
+    // rasterization caused by precision differences in the slope and endpoints
+    dest.line_to(p1.x, p1.y);
+    dest.line_to(end.minus.x, end.minus.y);
+    dest.line_to(start.minus.x, start.minus.y);
+    dest.line_to(p0.x, p0.y);
+    dest.close();
+}
+
+fn segment_normal(options: &StrokeOptions, half_width: f32, p0: Point, p1: Point) -> Option<UnitVector> {
+    let mut normal = compute_normal(p0, p1)?;
+    if options.reference {
+        normal = quantize_normal(normal);
+    }
+    if options.offset_snap > 0. && half_width > 0. {
+        normal = snap_normal(normal, half_width, options.offset_snap);
+    }
+    Some(normal)
+}
+
+/* Rounds `v * half_width` to multiples of `grid`. The result is only roughly unit length
+ * but, like with `quantize_normal`, that's close enough for the stroker. */
+fn snap_normal(v: UnitVector, half_width: f32, grid: f32) -> UnitVector {
+    let snap = |c: f32| (c * half_width / grid).round() * grid / half_width;
+    let snapped = Vector::new(snap(v.x), snap(v.y));
+    if snapped == Vector::zero() || !snapped.x.is_finite() || !snapped.y.is_finite() {
+        v
+    } else {
+        UnitVector::new_unchecked(snapped)
+    }
+}
+
+/* Merges runs of lines into single lines when every point along the run is within
+ * `epsilon` of the merged line, like `Path::join_collinear` does for closed subpaths, and
+ * drops zero length lines, except for one right after a `MoveTo` which marks a dot. This
+ * avoids emitting a quad per line and degenerate joins between them when stroking something
+ * like a flattened, nearly straight curve. Returns the new path along with the index in
+ * `path.ops` of the op each new op ends at. */
+fn coalesce_collinear(path: &Path, epsilon: f32) -> (Path, Vec<usize>) {
+    let mut ops: Vec<PathOp> = Vec::with_capacity(path.ops.len());
+    let mut indices = Vec::with_capacity(path.ops.len());
+    let mut start = None;
+    let mut cur_pt = None;
+    // where the last line in `ops` starts and the points in the middle of it
+    let mut line_start = None;
+    let mut run = Vec::new();
+    for (index, op) in path.ops.iter().enumerate() {
+        match *op {
+            PathOp::MoveTo(pt) => {
+                start = Some(pt);
+                cur_pt = Some(pt);
+                line_start = None;
+            }
+            PathOp::LineTo(pt) => match cur_pt {
+                None => {
+                    start = Some(pt);
+                    cur_pt = Some(pt);
+                    line_start = None;
+                }
+                Some(from) => match compute_normal(from, pt) {
+                    // the first one is kept so that the subpath can be drawn as a dot
+                    None if matches!(ops.last(), Some(PathOp::MoveTo(_))) => {}
+                    None => continue,
+                    Some(_) => {
+                        if let (Some(&PathOp::LineTo(last)), Some(line_start)) = (ops.last(), line_start) {
+                            run.push(last);
+                            if run.iter().all(|p| distance_to_segment(*p, line_start, pt) <= epsilon) {
+                                // extend the last line instead of adding another
+                                ops.pop();
+                                indices.pop();
+                                ops.push(*op);
+                                indices.push(index);
+                                cur_pt = Some(pt);
+                                continue;
+                            }
+                        }
+                        run.clear();
+                        line_start = Some(from);
+                        cur_pt = Some(pt);
+                    }
+                },
+            },
+            PathOp::Close => {
+                cur_pt = start;
+                line_start = None;
+            }
+            PathOp::QuadTo(_, pt) | PathOp::CubicTo(_, _, pt) => {
+                cur_pt = Some(pt);
+                line_start = None;
+            }
+        }
+        run.clear();
+        ops.push(*op);
+        indices.push(index);
+    }
+    (Path { ops, winding: path.winding }, indices)
+}
+
+/* `join_for_vertex` is given the index of the op that ends at each joined vertex */
+fn stroke_into<D: StrokeDest>(
+    path: &Path,
+    style: &StrokeStyle,
+    options: &StrokeOptions,
+    join_for_vertex: &dyn Fn(usize) -> LineJoin,
+    out: &mut StrokeOutput<D>,
+) {
+    let compute_normal = |p0, p1| segment_normal(options, style.width / 2., p0, p1);
+
+    let mut cur_pt = None;
+    // the indices of the ops that end at `cur_pt` and at the start of the subpath
+    let mut cur_index = 0;
+    let mut start_index = 0;
+    let half_width = style.width / 2.;
+    // replaced by the first segment before it's used
+    let mut last_end = SegmentEnd::new(Point::zero(), UnitVector::new_unchecked(Vector::new(0., 1.)), half_width);
+    let mut start_point: Option<(Point, SegmentEnd)> = None;
+    // whether we've moved to a new subpath that hasn't been closed yet
+    let mut moved = false;
+    // whether the current subpath has lines that don't go anywhere, which
+    // are drawn as a dot if it has nothing else, like in SVG
+    let mut zero_length = false;
+    // a tenth of the flattening tolerance so that merging adds little to the error
+    let (path, indices) = coalesce_collinear(path, options.tolerance / 10.);
+    for (&index, op) in indices.iter().zip(&path.ops) {
+        match *op {
+            PathOp::MoveTo(pt) => {
+                if let (Some(cur_pt), Some((point, start))) = (cur_pt, start_point) {
+                    // cap end
+                    cap_line(out.caps(), style, options, cur_pt, last_end.normal);
+                    // cap beginning
+                    cap_line(out.caps(), style, options, point, start.normal.flip());
+                } else if let (Some(cur_pt), true) = (cur_pt, zero_length) {
+                    cap_dot(out.caps(), style, options, cur_pt);
+                }
+                start_point = None;
+                cur_pt = Some(pt);
+                cur_index = index;
+                moved = true;
+                zero_length = false;
+            }
+            PathOp::LineTo(pt) => {
+                if cur_pt.is_none() {
+                    start_point = None;
+                } else if let Some(cur_pt) = cur_pt {
+                    // Zero length segments are skipped without touching `last_end` so
+                    // that the next segment is joined with the one before the duplicate point.
+                    if let Some(normal) = compute_normal(cur_pt, pt) {
+                        let start = SegmentEnd::new(cur_pt, normal, half_width);
+                        let end = SegmentEnd::new(pt, normal, half_width);
+                        if start_point.is_none() {
+                            start_point = Some((cur_pt, start));
+                            start_index = cur_index;
+                        } else {
+                            let join = join_for_vertex(cur_index);
+                            join_line(out.joins(), style, options, join, cur_pt, last_end, start);
+                        }
+
+                        segment(&mut out.body, cur_pt, pt, start, end);
+
+                        last_end = end;
+
+                    } else {
+                        zero_length = true;
+                    }
+                }
+                cur_pt = Some(pt);
+                cur_index = index;
+
+            }
+            PathOp::Close => {
+                if let (Some(cur_pt), Some((end_point, first))) = (cur_pt, start_point) {
+                    if let Some(normal) = compute_normal(cur_pt, end_point) {
+                        let start = SegmentEnd::new(cur_pt, normal, half_width);
+                        let end = SegmentEnd::new(end_point, normal, half_width);
+                        let join = join_for_vertex(cur_index);
+                        join_line(out.joins(), style, options, join, cur_pt, last_end, start);
+
+                        // the closing line segment
+                        segment(&mut out.body, cur_pt, end_point, start, end);
+
+                        let join = join_for_vertex(start_index);
+                        join_line(out.joins(), style, options, join, end_point, end, first);
+                    } else {
+                        // The last segment already returned to the start so there's no
+                        // closing segment, just the join between it and the first one.
+                        let join = join_for_vertex(start_index);
+                        join_line(out.joins(), style, options, join, end_point, last_end, first);
+                    }
+                } else if let (Some(pt), true) = (cur_pt, moved) {
+                    // A closed subpath that doesn't go anywhere is drawn as a dot
+                    cap_dot(out.caps(), style, options, pt);
+                }
+                cur_pt = start_point.map(|x| x.0);
+                cur_index = start_index;
+                start_point = None;
+                moved = false;
+                zero_length = false;
+            }
+            PathOp::QuadTo(..) => panic!("Only flat paths handled"),
+            PathOp::CubicTo(..) => panic!("Only flat paths handled"),
+        }
+    }
+    if let (Some(cur_pt), Some((point, start))) = (cur_pt, start_point) {
+        // cap end
+        cap_line(out.caps(), style, options, cur_pt, last_end.normal);
+        // cap beginning
+        cap_line(out.caps(), style, options, point, start.normal.flip());
+    } else if let (Some(cur_pt), true) = (cur_pt, zero_length) {
+        cap_dot(out.caps(), style, options, cur_pt);
+    }
+}
+
+/// The geometry produced by one call to an `IncrementalStroker`.
+#[derive(Clone, Debug)]
+pub struct StrokeUpdate {
+    /// Geometry that is now a permanent part of the stroke. It should be
+    /// kept along with the geometry added by earlier updates.
+    pub added: Path,
+    /// The cap at the current end of the stroke. This replaces the `end_cap`
+    /// of the previous update: once the stroke is extended the old end is
+    /// covered by a join instead.
+    pub end_cap: Path,
+}
+
+impl StrokeUpdate {
+    fn new(added: PathBuilder, end_cap: PathBuilder) -> StrokeUpdate {
+        StrokeUpdate { added: added.finish(), end_cap: end_cap.finish() }
+    }
+}
+
+/// Strokes a path as its points arrive, like when drawing freehand with a pointer.
+///
+/// Each call only produces the geometry for the newly added segment: its quad,
+/// the join to the previous segment and a provisional cap at the new end of the
+/// stroke. This keeps the cost of each point constant no matter how long the
+/// stroke gets. The geometry is the same as `stroke_to_path_with_options` would
+/// give for the whole path, except where that merges a run of lines that stay
+/// within a tenth of `options.tolerance` of a single line. Points that haven't
+/// arrived yet can't be merged with, so here each line keeps its own quad and
+/// join. Only open subpaths made of lines are supported.
+pub struct IncrementalStroker {
+    style: StrokeStyle,
+    options: StrokeOptions,
+    cur_pt: Option<Point>,
+    // whether the current subpath has any segments yet
+    started: bool,
+    // whether it only has segments that don't go anywhere, which are drawn as a dot
+    zero_length: bool,
+    last_end: SegmentEnd,
+}
+
+impl IncrementalStroker {
+    pub fn new(style: &StrokeStyle, options: &StrokeOptions) -> IncrementalStroker {
+        IncrementalStroker {
+            style: style.clone(),
+            options: *options,
+            cur_pt: None,
+            started: false,
+            zero_length: false,
+            last_end: SegmentEnd::new(Point::zero(), UnitVector::new_unchecked(Vector::new(0., 1.)), style.width / 2.),
+        }
+    }
+
+    /// Lifts the pen and puts it down again at `x`, `y`. The end cap of the
+    /// previous subpath, if any, is made permanent.
+    pub fn move_to(&mut self, x: f32, y: f32) -> StrokeUpdate {
+        let update = self.finish();
+        self.cur_pt = Some(Point::new(x, y));
+        update
+    }
+
+    /// Extends the stroke with a line to `x`, `y`.
+    pub fn line_to(&mut self, x: f32, y: f32) -> StrokeUpdate {
+        let pt = Point::new(x, y);
+        let mut added = PathBuilder::new();
+        let mut end_cap = PathBuilder::new();
+        let cur_pt = match self.cur_pt {
+            Some(cur_pt) => cur_pt,
+            None => {
+                self.cur_pt = Some(pt);
+                return StrokeUpdate::new(added, end_cap);
+            }
+        };
+        if self.style.width <= 0. {
+            self.cur_pt = Some(pt);
+            return StrokeUpdate::new(added, end_cap);
+        }
+
+        match segment_normal(&self.options, self.style.width / 2., cur_pt, pt) {
+            Some(normal) => {
+                let half_width = self.style.width / 2.;
+                let start = SegmentEnd::new(cur_pt, normal, half_width);
+                let end = SegmentEnd::new(pt, normal, half_width);
+                if self.started {
+                    join_line(&mut added, &self.style, &self.options, self.style.join, cur_pt, self.last_end, start);
+                } else {
+                    cap_line(&mut added, &self.style, &self.options, cur_pt, normal.flip());
+                    self.started = true;
+                }
+                segment(&mut added, cur_pt, pt, start, end);
+                cap_line(&mut end_cap, &self.style, &self.options, pt, normal);
+                self.last_end = end;
+                self.cur_pt = Some(pt);
+            }
+            None => {
+                // A zero length segment changes nothing but the end cap still
+                // has to be given again because it replaces the previous one.
+                // Until there's a segment that goes somewhere the end cap is a dot.
+                if self.started {
+                    cap_line(&mut end_cap, &self.style, &self.options, cur_pt, self.last_end.normal);
+                } else {
+                    cap_dot(&mut end_cap, &self.style, &self.options, cur_pt);
+                    self.zero_length = true;
+                }
+            }
+        }
+        StrokeUpdate::new(added, end_cap)
+    }
+
+    /// Lifts the pen, making the end cap of the current subpath permanent.
+    pub fn finish(&mut self) -> StrokeUpdate {
+        let mut added = PathBuilder::new();
+        if let (Some(cur_pt), true) = (self.cur_pt, self.started) {
+            cap_line(&mut added, &self.style, &self.options, cur_pt, self.last_end.normal);
+        } else if let (Some(cur_pt), true) = (self.cur_pt, self.zero_length) {
+            cap_dot(&mut added, &self.style, &self.options, cur_pt);
+        }
+        self.cur_pt = None;
+        self.started = false;
+        self.zero_length = false;
+        StrokeUpdate::new(added, PathBuilder::new())
+    }
+}
+
+fn ribbon_join(
+    dest: &mut PathBuilder,
+    join: LineJoin,
+    pt: Point,
+    near: f32,
+    far: f32,
+    mut s1_normal: UnitVector,
+    mut s2_normal: UnitVector,
+) {
+    // Find the part of the band that lies on the outside of the turn.
+    // The inside is already covered by the overlapping segments.
+    let (r0, r1) = if is_interior_angle(s1_normal, s2_normal) {
+        s2_normal = s2_normal.flip();
+        s1_normal = s1_normal.flip();
+        std::mem::swap(&mut s1_normal, &mut s2_normal);
+        ((-far).max(0.), -near)
+    } else {
+        (near.max(0.), far)
+    };
+    if r1 <= r0 {
+        return;
+    }
+
+    let outer_start = pt + s1_normal * r1;
+    let outer_end = pt + s2_normal * r1;
+    let inner_start = pt + s1_normal * r0;
+    let inner_end = pt + s2_normal * r0;
+    let miter_limit = StrokeStyle::default().miter_limit;
+    let in_dot_out = -s1_normal.x * s2_normal.x + -s1_normal.y * s2_normal.y;
+    let within_limit = 2. <= miter_limit * miter_limit * (1. - in_dot_out);
+    let join = match join {
+        LineJoin::MiterOrRound if within_limit => LineJoin::Miter,
+        LineJoin::MiterOrRound => LineJoin::Round,
+        join => join,
+    };
+    match join {
+        LineJoin::Round => {
+            dest.move_to(outer_start.x, outer_start.y);
+            arc(dest, pt.x, pt.y, r1, s1_normal, s2_normal);
+            dest.line_to(inner_end.x, inner_end.y);
+            if r0 > 0. {
+                arc(dest, pt.x, pt.y, r0, s2_normal, s1_normal);
+            }
+            dest.close();
+        }
+        _ => {
+            let mitered = join == LineJoin::Miter && within_limit;
+            dest.move_to(outer_start.x, outer_start.y);
+            match line_intersection(outer_start, s1_normal, outer_end, s2_normal) {
+                Some(apex) if mitered => {
+                    let inner_apex = pt + (apex - pt) * (r0 / r1);
+                    dest.line_to(apex.x, apex.y);
+                    dest.line_to(outer_end.x, outer_end.y);
+                    dest.line_to(inner_end.x, inner_end.y);
+                    dest.line_to(inner_apex.x, inner_apex.y);
+                }
+                _ => {
+                    dest.line_to(outer_end.x, outer_end.y);
+                    dest.line_to(inner_end.x, inner_end.y);
+                }
+            }
+            dest.line_to(inner_start.x, inner_start.y);
+            dest.close();
+        }
+    }
+}
+
+fn ribbon_subpath(dest: &mut PathBuilder, points: &[Point], closed: bool, near: f32, far: f32, join: LineJoin) {
+    let mut segments = Vec::new();
+    for w in points.windows(2) {
+        if let Some(normal) = compute_normal(w[0], w[1]) {
+            segments.push((w[0], w[1], normal));
+        }
+    }
+    if let (true, Some(&first), Some(&last)) = (closed, points.first(), points.last()) {
+        if let Some(normal) = compute_normal(last, first) {
+            segments.push((last, first, normal));
+        }
+    }
+
+    for (i, &(p0, p1, normal)) in segments.iter().enumerate() {
+        dest.move_to(p0.x + normal.x * far, p0.y + normal.y * far);
+        dest.line_to(p1.x + normal.x * far, p1.y + normal.y * far);
+        dest.line_to(p1.x + normal.x * near, p1.y + normal.y * near);
+        dest.line_to(p0.x + normal.x * near, p0.y + normal.y * near);
+        dest.close();
+
+        if i > 0 {
+            ribbon_join(dest, join, p0, near, far, segments[i - 1].2, normal);
+        }
+    }
+    if let (true, Some(first), Some(last)) = (closed, segments.first(), segments.last()) {
+        if segments.len() > 1 {
+            ribbon_join(dest, join, first.0, near, far, last.2, first.2);
+        }
+    }
+}
+
+/// Builds the band between the offsets of `path` at the signed distances
+/// `inner` and `outer`. Positive distances are in the direction of the normal
+/// used by the stroker, i.e. to the right of the direction of travel.
+pub(crate) fn ribbon_to_path(path: &Path, inner: f32, outer: f32, join: LineJoin) -> Path {
+    let (near, far) = if inner <= outer { (inner, outer) } else { (outer, inner) };
+    let path = path.flatten(StrokeOptions::default().tolerance);
+
+    let mut dest = PathBuilder::new();
+    let mut points = Vec::new();
+    for op in &path.ops {
+        match *op {
+            PathOp::MoveTo(pt) => {
+                ribbon_subpath(&mut dest, &points, false, near, far, join);
+                points.clear();
+                points.push(pt);
+            }
+            PathOp::LineTo(pt) => points.push(pt),
+            PathOp::Close => {
+                ribbon_subpath(&mut dest, &points, true, near, far, join);
+                let start = points.first().cloned();
+                points.clear();
+                points.extend(start);
+            }
+            PathOp::QuadTo(..) | PathOp::CubicTo(..) => unreachable!(),
+        }
     }
-    stroked_path.finish()
+    ribbon_subpath(&mut dest, &points, false, near, far, join);
+    dest.finish()
 }
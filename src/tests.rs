@@ -616,8 +616,27 @@ mod tests {
         let tri = pb.finish();
 
         assert!(tri.contains_point(0.1, 0.5, 0.5));
-        assert!(!tri.contains_point(0.1, 0.6, 0.5));
+        assert!(!tri.contains_point(0.1, 0.7, 0.5));
         assert!(tri.contains_point(0.1, 0.4, 0.5));
+        // points within the tolerance of the outline count as inside
+        assert!(tri.contains_point(0.1, 0.55, 0.5));
+        assert!(!tri.contains_point(0.01, 0.55, 0.5));
+    }
+
+    #[test]
+    fn path_contains_point_tolerance() {
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 100., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let circle = pb.finish();
+        assert_eq!(circle.winding_number(0.01, 0., 0.).abs(), 1);
+        assert_eq!(circle.winding_number(0.01, 200., 0.), 0);
+
+        // a point just outside the circle is only found to be outside
+        // when the curve is flattened finely enough
+        let (x, y) = (100.05 * 0.7071, 100.05 * 0.7071);
+        assert!(circle.contains_point(5., x, y));
+        assert!(!circle.contains_point(0.01, x, y));
     }
 
     #[test]
@@ -956,4 +975,3297 @@ mod tests {
             &checkerboard[..]
         );
     }
+
+    #[test]
+    fn path_map_coords() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(1., 2.);
+        pb.quad_to(3., 4., 5., 6.);
+        pb.close();
+        let path = pb.finish().map_coords(|p| Point::new(p.x * p.x, -p.y));
+
+        match path.ops[..] {
+            [PathOp::MoveTo(p), PathOp::QuadTo(c, p2), PathOp::Close] => {
+                assert_eq!(p, Point::new(1., -2.));
+                assert_eq!(c, Point::new(9., -4.));
+                assert_eq!(p2, Point::new(25., -6.));
+            }
+            _ => panic!("unexpected ops {:?}", path.ops),
+        }
+    }
+
+    #[test]
+    fn reference_stroke() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(3., 1.);
+        pb.line_to(5., 7.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            join: LineJoin::Bevel,
+            ..Default::default()
+        };
+        let options = StrokeOptions {
+            reference: true,
+            ..Default::default()
+        };
+        let stroked = stroke_to_path_with_options(&path, &style, &options);
+
+        // The first vertex is the start point offset by the normal
+        // so it must lie on the reference grid.
+        match stroked.ops[0] {
+            PathOp::MoveTo(p) => {
+                assert_eq!((p.x * 65536.).fract(), 0.);
+                assert_eq!((p.y * 65536.).fract(), 0.);
+            }
+            _ => panic!(),
+        }
+
+        let plain = stroke_to_path(&path, &style);
+        assert_eq!(stroked.ops.len(), plain.ops.len());
+    }
+
+    #[test]
+    fn stroke_glyph_outline() {
+        // A small capital "A" with a curved apex and a counter
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 10.);
+        pb.line_to(4., 1.);
+        pb.quad_to(5., -1., 6., 1.);
+        pb.line_to(10., 10.);
+        pb.line_to(8., 10.);
+        pb.line_to(7., 7.);
+        pb.line_to(3., 7.);
+        pb.line_to(2., 10.);
+        pb.close();
+        pb.move_to(3.6, 5.5);
+        pb.line_to(5., 2.);
+        pb.line_to(6.4, 5.5);
+        pb.close();
+        let glyph = pb.finish();
+
+        let style = StrokeStyle {
+            width: 0.5,
+            ..Default::default()
+        };
+        let options = StrokeOptions {
+            tolerance: 0.01,
+            ..Default::default()
+        };
+        let stroked = stroke_to_path_with_options(&glyph, &style, &options);
+        for op in &stroked.ops {
+            match *op {
+                PathOp::MoveTo(p) | PathOp::LineTo(p) => assert!(p.x.is_finite() && p.y.is_finite()),
+                PathOp::CubicTo(c1, c2, p) => {
+                    assert!(c1.x.is_finite() && c2.x.is_finite() && p.x.is_finite())
+                }
+                PathOp::QuadTo(..) => panic!(),
+                PathOp::Close => {}
+            }
+        }
+
+        // the serif corners and the apex are covered
+        assert!(stroked.contains_point(0.01, 2., 10.1));
+        assert!(stroked.contains_point(0.01, 3., 7.));
+        assert!(stroked.contains_point(0.01, 5., 0.));
+        // the middle of the counter and the space under the crossbar are not
+        assert!(!stroked.contains_point(0.01, 5., 4.5));
+        assert!(!stroked.contains_point(0.01, 5., 9.));
+
+        let mut dt = DrawTarget::new(12, 12);
+        dt.stroke(&glyph, &WHITE_SOURCE, &style, &DrawOptions::new());
+    }
+
+    #[test]
+    fn path_convex_hull() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(4., 0.);
+        pb.line_to(2., 1.);
+        pb.line_to(4., 4.);
+        pb.line_to(0., 4.);
+        pb.close();
+        pb.move_to(1., 1.);
+        pb.line_to(2., 2.);
+        let hull = pb.finish().convex_hull(0.1);
+
+        let points: Vec<Point> = hull.ops.iter().filter_map(|op| match *op {
+            PathOp::MoveTo(p) | PathOp::LineTo(p) => Some(p),
+            _ => None,
+        }).collect();
+        assert_eq!(points, vec![
+            Point::new(0., 0.),
+            Point::new(4., 0.),
+            Point::new(4., 4.),
+            Point::new(0., 4.),
+        ]);
+        assert!(matches!(hull.ops.last(), Some(PathOp::Close)));
+
+        // curves bulge out past their end points
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(1., 2., 2., 0.);
+        let hull = pb.finish().convex_hull(0.01);
+        assert!(hull.contains_point(0.01, 1., 0.9));
+        assert!(!hull.contains_point(0.01, 1., 1.1));
+
+        assert!(PathBuilder::new().finish().convex_hull(0.1).ops.is_empty());
+    }
+
+    #[test]
+    fn dash_zero_sum() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0.5, 0.5, 2., 2.);
+        let path = pb.finish();
+
+        let mut solid = DrawTarget::new(3, 3);
+        solid.stroke(&path, &WHITE_SOURCE, &StrokeStyle::default(), &DrawOptions::new());
+
+        for dash_array in [vec![0.], vec![0., 0.]].iter() {
+            let mut dt = DrawTarget::new(3, 3);
+            dt.stroke(
+                &path,
+                &WHITE_SOURCE,
+                &StrokeStyle {
+                    dash_array: dash_array.clone(),
+                    dash_offset: 3.,
+                    ..Default::default()
+                },
+                &DrawOptions::new(),
+            );
+            assert_eq!(dt.get_data(), solid.get_data());
+        }
+    }
+
+    #[test]
+    fn path_ribbon() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        let line = pb.finish();
+        let ribbon = line.ribbon(4., 2., LineJoin::Miter);
+        assert!(ribbon.contains_point(0.01, 5., 3.));
+        assert!(!ribbon.contains_point(0.01, 5., 1.));
+        assert!(!ribbon.contains_point(0.01, 5., -3.));
+
+        // a band on the outside of a right angle turn
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let corner = pb.finish();
+
+        let round = corner.ribbon(-2., -4., LineJoin::Round);
+        assert!(round.contains_point(0.01, 12.1, -2.1));
+        assert!(!round.contains_point(0.01, 13.5, -3.5));
+        assert!(!round.contains_point(0.01, 10.5, -0.5));
+
+        let miter = corner.ribbon(-2., -4., LineJoin::Miter);
+        assert!(miter.contains_point(0.01, 13.5, -3.5));
+        assert!(!miter.contains_point(0.01, 11., -1.));
+
+        let bevel = corner.ribbon(-2., -4., LineJoin::Bevel);
+        assert!(bevel.contains_point(0.01, 11.5, -1.5));
+        assert!(!bevel.contains_point(0.01, 13.5, -3.5));
+    }
+
+    #[test]
+    fn path_is_closed_and_convex() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 2., 2.);
+        let rect = pb.finish();
+        assert!(rect.is_closed());
+        assert!(rect.is_convex(0.1));
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(2., 0.);
+        pb.line_to(1., 1.);
+        pb.line_to(2., 2.);
+        pb.line_to(0., 0.);
+        let concave = pb.finish();
+        assert!(concave.is_closed());
+        assert!(!concave.is_convex(0.1));
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(2., 0.);
+        pb.line_to(2., 2.);
+        let open = pb.finish();
+        assert!(!open.is_closed());
+        assert!(open.is_convex(0.1));
+
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 10., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        assert!(pb.finish().is_convex(0.1));
+
+        // a convex curve whose control polygon turns the wrong way at (7, 9)
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(9., 10., 7., 9., -9., 2.);
+        pb.close();
+        assert!(pb.finish().is_convex(0.1));
+
+        // while an S curve turns both ways
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(3., 8., 7., -8., 10., 0.);
+        pb.line_to(10., 20.);
+        pb.line_to(0., 20.);
+        pb.close();
+        assert!(!pb.finish().is_convex(0.1));
+
+        // a pentagram turns the same way at every point
+        let mut pb = PathBuilder::new();
+        for i in 0..5 {
+            let angle = i as f32 * 4. * std::f32::consts::PI / 5.;
+            pb.line_to(angle.cos(), angle.sin());
+        }
+        pb.close();
+        let star = pb.finish();
+        assert!(star.is_closed());
+        assert!(!star.is_convex(0.1));
+    }
+
+    #[test]
+    fn square_cap_direction() {
+        let mut dt = DrawTarget::new(10, 4);
+        let mut pb = PathBuilder::new();
+        pb.move_to(3., 2.);
+        pb.line_to(7., 2.);
+        dt.stroke(
+            &pb.finish(),
+            &WHITE_SOURCE,
+            &StrokeStyle {
+                width: 2.,
+                cap: LineCap::Square,
+                ..Default::default()
+            },
+            &DrawOptions::new(),
+        );
+        let white = 0xffffffff;
+        for y in 0..4 {
+            let row = &dt.get_data()[y * 10..(y + 1) * 10];
+            if y == 1 || y == 2 {
+                assert_eq!(row, &[0, 0, white, white, white, white, white, white, 0, 0][..]);
+            } else {
+                assert_eq!(row, &[0; 10][..]);
+            }
+        }
+    }
+
+    #[test]
+    fn path_approx_eq() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(1., 2., 3., 4., 5., 6.);
+        pb.close();
+        let path = pb.finish();
+
+        let nudged = path.clone().transform(&Transform::translation(0.001, -0.001));
+        assert!(path.approx_eq(&nudged, 0.01));
+        assert!(!path.approx_eq(&nudged, 0.0001));
+        assert!(path != nudged);
+        assert!(path == path.clone());
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(5., 6.);
+        pb.close();
+        assert!(!path.approx_eq(&pb.finish(), 100.));
+    }
+
+    #[test]
+    fn cap_extension() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(20., 10.);
+        let path = pb.finish();
+
+        for &cap in [LineCap::Round, LineCap::Square].iter() {
+            let style = StrokeStyle {
+                width: 4.,
+                cap,
+                ..Default::default()
+            };
+            let options = StrokeOptions {
+                cap_extension: 3.,
+                ..Default::default()
+            };
+            let stroked = stroke_to_path_with_options(&path, &style, &options);
+            assert!(stroked.contains_point(0.01, 5.5, 10.));
+            assert!(stroked.contains_point(0.01, 24.5, 10.));
+            assert!(stroked.contains_point(0.01, 22.5, 11.5));
+            assert!(!stroked.contains_point(0.01, 4.5, 10.));
+            assert!(!stroked.contains_point(0.01, 25.5, 10.));
+            // the cap keeps the width of the stroke
+            assert!(!stroked.contains_point(0.01, 22.5, 12.5));
+        }
+    }
+
+    #[test]
+    fn stroke_rect_fast_path() {
+        let mut pb = PathBuilder::new();
+        pb.rect(2., 2., 10., 6.);
+        let rect = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            ..Default::default()
+        };
+        let stroked = stroke_to_path(&rect, &style);
+        // an outer and an inner rectangle
+        assert_eq!(stroked.ops.len(), 10);
+        assert!(stroked.contains_point(0.01, 1.5, 1.5));
+        assert!(stroked.contains_point(0.01, 12.5, 8.5));
+        assert!(!stroked.contains_point(0.01, 7., 5.));
+
+        let mut even_odd = stroked.clone();
+        even_odd.winding = Winding::EvenOdd;
+        assert!(even_odd.contains_point(0.01, 1.5, 1.5));
+        assert!(!even_odd.contains_point(0.01, 7., 5.));
+
+        // a rectangle with the corner moved isn't a rectangle
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 2.);
+        pb.line_to(12., 2.);
+        pb.line_to(12., 8.);
+        pb.line_to(2., 8.5);
+        pb.close();
+        assert!(stroke_to_path(&pb.finish(), &style).ops.len() > 10);
+
+        // round joins still go through the general stroker
+        let round = StrokeStyle {
+            join: LineJoin::Round,
+            ..style
+        };
+        assert!(!stroke_to_path(&rect, &round).contains_point(0.01, 1.1, 1.1));
+    }
+
+    #[test]
+    fn flattened_path() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(10., 0., 10., 10., 0., 10.);
+        let path = pb.finish();
+
+        let flattened = FlattenedPath::new(&path, 0.1);
+        assert_eq!(flattened.tolerance(), 0.1);
+        assert!(flattened.path().ops.iter().all(|op| match op {
+            PathOp::MoveTo(..) | PathOp::LineTo(..) | PathOp::Close => true,
+            _ => false,
+        }));
+
+        let style = StrokeStyle::default();
+        let mut a = DrawTarget::new(12, 12);
+        a.stroke(flattened.path(), &WHITE_SOURCE, &style, &DrawOptions::new());
+        let mut b = DrawTarget::new(12, 12);
+        b.stroke(&path, &WHITE_SOURCE, &style, &DrawOptions::new());
+        assert_eq!(a.get_data(), b.get_data());
+    }
+
+    #[test]
+    fn path_tangents() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(0., 0., 0., 5., 5., 5.);
+        pb.quad_to(10., 5., 10., 10.);
+        let path = pb.finish();
+        assert_eq!(path.start_tangent().map(UnitVector::get), Some(Vector::new(0., 1.)));
+        assert_eq!(path.end_tangent().map(UnitVector::get), Some(Vector::new(0., 1.)));
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(4., 0.);
+        pb.line_to(4., 3.);
+        pb.close();
+        let triangle = pb.finish();
+        assert_eq!(triangle.start_tangent().map(UnitVector::get), Some(Vector::new(1., 0.)));
+        assert_eq!(triangle.end_tangent().map(UnitVector::get), Some(Vector::new(-0.8, -0.6)));
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(1., 1.);
+        assert_eq!(pb.finish().start_tangent(), None);
+    }
+
+    #[test]
+    fn stroke_parts() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 2.);
+        pb.line_to(8., 2.);
+        pb.line_to(8., 8.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            cap: LineCap::Square,
+            join: LineJoin::Round,
+            ..Default::default()
+        };
+        let parts = stroke_to_parts(&path, &style, &StrokeOptions::default());
+        assert!(parts.caps.contains_point(0.01, 1.5, 2.));
+        assert!(!parts.body.contains_point(0.01, 1.5, 2.));
+        assert!(parts.joins.contains_point(0.01, 8.5, 1.5));
+        assert!(!parts.body.contains_point(0.01, 8.5, 1.5));
+        assert!(parts.body.contains_point(0.01, 5., 2.5));
+
+        let mut merged = DrawTarget::new(10, 10);
+        merged.stroke(&path, &WHITE_SOURCE, &style, &DrawOptions::new());
+
+        let mut all = parts.body.clone();
+        all.ops.extend(&parts.caps.ops);
+        all.ops.extend(&parts.joins.ops);
+        let mut separate = DrawTarget::new(10, 10);
+        separate.fill(&all, &WHITE_SOURCE, &DrawOptions::new());
+        assert_eq!(merged.get_data(), separate.get_data());
+    }
+
+    #[test]
+    fn stroke_dot() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(5., 5.);
+        pb.close();
+        let dot = pb.finish();
+
+        let round = StrokeStyle {
+            width: 4.,
+            cap: LineCap::Round,
+            ..Default::default()
+        };
+        let stroked = stroke_to_path(&dot, &round);
+        assert!(stroked.contains_point(0.01, 5., 6.9));
+        assert!(stroked.contains_point(0.01, 3.1, 5.));
+        assert!(stroked.contains_point(0.01, 6.9, 5.));
+        assert!(!stroked.contains_point(0.01, 6.8, 6.8));
+
+        let square = StrokeStyle {
+            cap: LineCap::Square,
+            ..round.clone()
+        };
+        let stroked = stroke_to_path(&dot, &square);
+        assert!(stroked.contains_point(0.01, 6.8, 6.8));
+        assert!(stroked.contains_point(0.01, 3.2, 3.2));
+        assert!(!stroked.contains_point(0.01, 7.2, 5.));
+
+        let butt = StrokeStyle {
+            cap: LineCap::Butt,
+            ..round.clone()
+        };
+        assert!(stroke_to_path(&dot, &butt).ops.is_empty());
+
+        // so is an open subpath that doesn't go anywhere, but not a lone move
+        let mut pb = PathBuilder::new();
+        pb.move_to(20., 20.);
+        pb.move_to(5., 5.);
+        pb.line_to(5., 5.);
+        pb.move_to(30., 30.);
+        let scatter = pb.finish();
+        let stroked = stroke_to_path(&scatter, &round);
+        assert!(stroked.contains_point(0.01, 5., 6.9));
+        assert!(!stroked.contains_point(0.01, 6.8, 6.8));
+        assert!(!stroked.contains_point(0.1, 20., 20.) && !stroked.contains_point(0.1, 30., 30.));
+        assert!(stroke_to_path(&scatter, &square).contains_point(0.01, 6.8, 6.8));
+        assert!(stroke_to_path(&scatter, &butt).ops.is_empty());
+        // ending the path and stroking with curves do the same
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        pb.move_to(5., 20.);
+        pb.line_to(5., 20.);
+        let stroked = stroke_to_path_with_curves(&pb.finish(), &round, &StrokeOptions::default());
+        assert!(stroked.contains_point(0.01, 5., 21.9));
+        let mut stroker = IncrementalStroker::new(&round, &StrokeOptions::default());
+        stroker.move_to(5., 5.);
+        assert!(stroker.line_to(5., 5.).end_cap.contains_point(0.01, 5., 6.9));
+        assert!(stroker.finish().added.contains_point(0.01, 5., 6.9));
+
+        // closing a subpath twice doesn't add a dot
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.close();
+        pb.close();
+        let parts = stroke_to_parts(&pb.finish(), &round, &StrokeOptions::default());
+        assert!(parts.caps.ops.is_empty());
+    }
+
+    #[test]
+    fn path_bounding_circle() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 6., 8.);
+        pb.move_to(3., 4.);
+        pb.line_to(2., 3.);
+        let (center, radius) = pb.finish().bounding_circle();
+        assert!((center - Point::new(3., 4.)).length() < 1e-5);
+        assert!((radius - 5.).abs() < 1e-5);
+
+        // an obtuse triangle is bounded by its longest side
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(5., 1.);
+        pb.close();
+        let (center, radius) = pb.finish().bounding_circle();
+        assert!((center - Point::new(5., 0.)).length() < 1e-5);
+        assert!((radius - 5.).abs() < 1e-5);
+
+        let mut pb = PathBuilder::new();
+        pb.arc(10., 10., 5., 0., 2. * std::f32::consts::PI);
+        let circle = pb.finish();
+        let (center, radius) = circle.bounding_circle();
+        for op in &circle.flatten(0.01).ops {
+            if let PathOp::LineTo(p) = *op {
+                assert!((p - center).length() <= radius);
+            }
+        }
+
+        assert_eq!(PathBuilder::new().finish().bounding_circle(), (Point::zero(), 0.));
+    }
+
+    #[test]
+    fn stroke_join_across_duplicate_point() {
+        let style = StrokeStyle {
+            width: 4.,
+            ..Default::default()
+        };
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let duplicate = stroke_to_path(&pb.finish(), &style);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let distinct = stroke_to_path(&pb.finish(), &style);
+
+        assert_eq!(duplicate, distinct);
+        // the miter corner
+        assert!(duplicate.contains_point(0.01, 11.5, -1.5));
+    }
+
+    #[test]
+    fn path_split_at_extrema() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(5., 10., 10., 0.);
+        pb.cubic_to(20., -10., 0., -10., 10., 10.);
+        pb.line_to(0., 0.);
+        let path = pb.finish();
+        let split = path.split_at_extrema();
+
+        let quads = split.ops.iter().filter(|op| matches!(op, PathOp::QuadTo(..))).count();
+        let cubics = split.ops.iter().filter(|op| matches!(op, PathOp::CubicTo(..))).count();
+        assert_eq!(quads, 2);
+        assert!(cubics >= 3);
+        assert!(matches!(split.ops.last(), Some(PathOp::LineTo(..))));
+
+        // every curve is monotonic
+        let mut cur = Point::zero();
+        for op in &split.ops {
+            match *op {
+                PathOp::QuadTo(c, p) => {
+                    assert!((c.x - cur.x) * (p.x - c.x) >= 0. && (c.y - cur.y) * (p.y - c.y) >= 0.);
+                    cur = p;
+                }
+                PathOp::CubicTo(c1, c2, p) => {
+                    let c = lyon_geom::CubicBezierSegment { from: cur, ctrl1: c1, ctrl2: c2, to: p };
+                    for i in 1..100 {
+                        let t = i as f32 / 100.;
+                        let (a, b) = (c.sample(t - 0.01), c.sample(t));
+                        let (e, f) = (c.from, c.to);
+                        assert!((b.x - a.x) * (f.x - e.x) >= -1e-4);
+                        assert!((b.y - a.y) * (f.y - e.y) >= -1e-4);
+                    }
+                    cur = p;
+                }
+                PathOp::MoveTo(p) | PathOp::LineTo(p) => cur = p,
+                PathOp::Close => {}
+            }
+        }
+
+        // the pieces end exactly where the original curves did
+        assert!(split.ops.contains(&PathOp::QuadTo(Point::new(7.5, 5.), Point::new(10., 0.))));
+        assert!(matches!(split.ops[split.ops.len() - 2], PathOp::CubicTo(_, _, p) if p == Point::new(10., 10.)));
+    }
+
+    #[test]
+    fn svg_points() {
+        let path = PathBuilder::from_svg_points(" 0,0 10 , 0\n10-1.5e1 .5.5 ", true).unwrap();
+        assert_eq!(path.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(10., 0.)),
+            PathOp::LineTo(Point::new(10., -15.)),
+            PathOp::LineTo(Point::new(0.5, 0.5)),
+            PathOp::Close,
+        ]);
+
+        let path = PathBuilder::from_svg_points("1,2,3,4", false).unwrap();
+        assert_eq!(path.ops.len(), 2);
+        assert!(PathBuilder::from_svg_points("", true).unwrap().ops.is_empty());
+
+        assert_eq!(PathBuilder::from_svg_points("1,2 3", false).unwrap_err(), PathError::OddNumberOfCoordinates);
+        assert_eq!(PathBuilder::from_svg_points("1,2 3,x", false).unwrap_err(), PathError::InvalidNumber(6));
+        assert_eq!(PathBuilder::from_svg_points("1,,2", false).unwrap_err(), PathError::InvalidNumber(2));
+        assert_eq!(PathBuilder::from_svg_points("1,2,", false).unwrap_err(), PathError::InvalidNumber(4));
+    }
+
+
+    #[test]
+    fn stroke_bleed() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 2.);
+        pb.line_to(8., 2.);
+        pb.line_to(8., 8.);
+        let path = pb.finish();
+        for &join in [LineJoin::Round, LineJoin::Miter, LineJoin::Bevel].iter() {
+            let style = StrokeStyle {
+                width: 2.,
+                cap: LineCap::Square,
+                join,
+                ..Default::default()
+            };
+            let parts = stroke_to_parts(&path, &style, &StrokeOptions::default());
+            assert!(!parts.caps.contains_point(0.01, 2.3, 2.2));
+            assert!(!parts.joins.contains_point(0.01, 7.7, 1.5));
+            assert!(!parts.joins.contains_point(0.01, 8.5, 2.3));
+
+            let options = StrokeOptions {
+                bleed: 0.5,
+                ..Default::default()
+            };
+            let parts = stroke_to_parts(&path, &style, &options);
+            assert!(parts.caps.contains_point(0.01, 2.3, 2.2));
+            assert!(parts.joins.contains_point(0.01, 7.7, 1.5));
+            assert!(parts.joins.contains_point(0.01, 8.5, 2.3));
+
+            // the overlap doesn't change the outline
+            let stroked = stroke_to_path_with_options(&path, &style, &options);
+            assert!(!stroked.contains_point(0.01, 2.3, 3.2));
+            assert!(!stroked.contains_point(0.01, 6.8, 3.2));
+        }
+    }
+
+
+    #[test]
+    fn stroke_outlined_paths() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 10.);
+        pb.quad_to(10., 0., 18., 10.);
+        let path = pb.finish();
+        let inner = StrokeStyle {
+            width: 2.,
+            ..Default::default()
+        };
+        let outer = StrokeStyle {
+            width: 6.,
+            join: LineJoin::Round,
+            ..Default::default()
+        };
+        let options = StrokeOptions::new();
+        let (inner_path, outer_path) = stroke_outlined(&path, &inner, &outer, &options);
+        assert_eq!(inner_path, stroke_to_path_with_options(&path, &inner, &options));
+        assert_eq!(outer_path, stroke_to_path_with_options(&path, &outer, &options));
+        assert!(inner_path.contains_point(0.01, 10., 5.5));
+        assert!(!inner_path.contains_point(0.01, 10., 7.5));
+        assert!(outer_path.contains_point(0.01, 10., 7.5));
+    }
+
+
+    #[test]
+    fn path_reverse_subpath() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.line_to(0., 10.);
+        pb.close();
+        pb.move_to(2., 2.);
+        pb.line_to(8., 2.);
+        pb.line_to(8., 8.);
+        pb.line_to(2., 8.);
+        pb.close();
+        pb.move_to(20., 0.);
+        pb.quad_to(25., 5., 30., 0.);
+        pb.cubic_to(31., 1., 32., 2., 33., 3.);
+        let mut path = pb.finish();
+        path.winding = Winding::NonZero;
+
+        // both squares wind the same way so the inner one doesn't make a hole
+        assert!(path.contains_point(0.1, 5., 5.));
+        let fixed = path.reverse_subpath(1);
+        assert!(!fixed.contains_point(0.1, 5., 5.));
+        assert!(fixed.contains_point(0.1, 1., 5.));
+        assert_eq!(fixed.ops[..5], path.ops[..5]);
+        assert_eq!(fixed.ops[10..], path.ops[10..]);
+        assert_eq!(
+            fixed.ops[5..10],
+            [
+                PathOp::MoveTo(Point::new(2., 2.)),
+                PathOp::LineTo(Point::new(2., 8.)),
+                PathOp::LineTo(Point::new(8., 8.)),
+                PathOp::LineTo(Point::new(8., 2.)),
+                PathOp::Close,
+            ]
+        );
+
+        let open = path.reverse_subpath(2);
+        assert_eq!(
+            open.ops[10..],
+            [
+                PathOp::MoveTo(Point::new(33., 3.)),
+                PathOp::CubicTo(Point::new(32., 2.), Point::new(31., 1.), Point::new(30., 0.)),
+                PathOp::QuadTo(Point::new(25., 5.), Point::new(20., 0.)),
+            ]
+        );
+        assert_eq!(open.reverse_subpath(2), path);
+
+        assert_eq!(path.reverse_subpath(3), path);
+    }
+
+
+    #[test]
+    fn path_bounds() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(0., 100., 100., 100., 100., 0.);
+        pb.quad_to(150., -100., 200., 0.);
+        pb.line_to(200., 10.);
+        let bounds = pb.finish().bounds();
+        // the control points reach 100 and -100 but the curves only reach 75 and -50
+        assert!((bounds.min.x - 0.).abs() < 1e-4);
+        assert!((bounds.max.x - 200.).abs() < 1e-4);
+        assert!((bounds.min.y - -50.).abs() < 1e-4);
+        assert!((bounds.max.y - 75.).abs() < 1e-4);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(1., 2.);
+        pb.line_to(3., 2.);
+        let bounds = pb.finish().bounds();
+        assert_eq!((bounds.min, bounds.max), (Point::new(1., 2.), Point::new(3., 2.)));
+
+        assert!(PathBuilder::new().finish().bounds().is_empty());
+    }
+
+
+    #[test]
+    fn incremental_stroker() {
+        let style = StrokeStyle {
+            width: 4.,
+            cap: LineCap::Round,
+            join: LineJoin::Miter,
+            ..Default::default()
+        };
+        let points = [(10., 10.), (30., 10.), (30., 10.), (30., 30.), (50., 20.)];
+
+        let mut stroker = IncrementalStroker::new(&style, &StrokeOptions::default());
+        let mut pb = PathBuilder::new();
+        let mut added = Vec::new();
+        let mut end_cap = Path { ops: Vec::new(), winding: Winding::NonZero };
+        for (i, &(x, y)) in points.iter().enumerate() {
+            let update = if i == 0 {
+                pb.move_to(x, y);
+                stroker.move_to(x, y)
+            } else {
+                pb.line_to(x, y);
+                stroker.line_to(x, y)
+            };
+            added.extend(update.added.ops);
+            end_cap = update.end_cap;
+        }
+        // only the cap from the last update is kept
+        let mut partial = Path { ops: added.clone(), winding: Winding::NonZero };
+        assert!(!partial.contains_point(0.01, 51.5, 19.));
+        partial.ops.extend(end_cap.ops);
+        assert!(partial.contains_point(0.01, 51.5, 19.));
+
+        added.extend(stroker.finish().added.ops);
+        let incremental = Path { ops: added, winding: Winding::NonZero };
+        let whole = stroke_to_path(&pb.finish(), &style);
+        for y in 0..40 {
+            for x in 0..60 {
+                let (x, y) = (x as f32 + 0.3, y as f32 + 0.3);
+                assert_eq!(incremental.contains_point(0.01, x, y), whole.contains_point(0.01, x, y), "{} {}", x, y);
+            }
+        }
+    }
+
+
+    #[test]
+    fn stroke_join_shares_corners() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.3, 0.1);
+        pb.line_to(10.7, 3.3);
+        pb.line_to(13.1, 17.9);
+        pb.line_to(2.9, 11.3);
+        pb.close();
+        let path = pb.finish();
+        for &join in [LineJoin::Round, LineJoin::Miter, LineJoin::Bevel].iter() {
+            let style = StrokeStyle {
+                width: 3.7,
+                join,
+                ..Default::default()
+            };
+            let parts = stroke_to_parts(&path, &style, &StrokeOptions::default());
+            let body: Vec<Point> = parts.body.ops.iter().filter_map(|op| match *op {
+                PathOp::MoveTo(p) | PathOp::LineTo(p) => Some(p),
+                _ => None,
+            }).collect();
+            // each join starts and ends on corners of the segment quads
+            let ops = &parts.joins.ops;
+            let mut joins = 0;
+            for (i, op) in ops.iter().enumerate() {
+                if let PathOp::MoveTo(start) = *op {
+                    let end = match ops[i..].iter().position(|op| *op == PathOp::Close) {
+                        Some(close) => match ops[i + close - 2] {
+                            PathOp::LineTo(end) => end,
+                            _ => panic!(),
+                        },
+                        None => panic!(),
+                    };
+                    assert!(body.contains(&start));
+                    assert!(body.contains(&end));
+                    joins += 1;
+                }
+            }
+            assert_eq!(joins, 4);
+        }
+    }
+
+
+    #[test]
+    fn stroke_bounds_tight() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 10.);
+        pb.line_to(20., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            cap: LineCap::Butt,
+            join: LineJoin::Miter,
+            miter_limit: 10.,
+            ..Default::default()
+        };
+        let approx = stroke_bounds(&path, &style);
+        let tight = stroke_outline_bounds(&path, &style);
+        assert!(approx.contains_box(&tight));
+        // the miter at the right angle only reaches sqrt(2) past the corner
+        assert!((tight.max.y - (10. + std::f32::consts::SQRT_2)).abs() < 1e-4);
+        assert!(approx.max.y >= 20.);
+        // the butt ends only reach out sideways
+        let h = 1. / std::f32::consts::SQRT_2;
+        assert!((tight.min.x - -h).abs() < 1e-4);
+        assert!((tight.min.y - -h).abs() < 1e-4);
+
+        let empty = PathBuilder::new().finish();
+        assert!(stroke_outline_bounds(&empty, &style).is_empty());
+        assert!(stroke_bounds(&empty, &style).is_empty());
+    }
+
+
+    #[test]
+    fn stroke_join_per_vertex() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.line_to(0., 10.);
+        pb.close();
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            join: LineJoin::Miter,
+            ..Default::default()
+        };
+        let stroked = stroke_to_path_with_joins(&path, &style, &StrokeOptions::default(), |i| {
+            if i == 2 { LineJoin::Bevel } else { LineJoin::Round }
+        });
+        // the corner at (10, 10) is bevelled
+        assert!(stroked.contains_point(0.01, 10.4, 10.4));
+        assert!(!stroked.contains_point(0.01, 10.8, 10.8));
+        // the others, including the one at the start, are round
+        assert!(stroked.contains_point(0.01, 10.6, -0.6));
+        assert!(!stroked.contains_point(0.01, 10.9, -0.9));
+        assert!(stroked.contains_point(0.01, -0.6, -0.6));
+        assert!(!stroked.contains_point(0.01, -0.9, -0.9));
+
+        let same = stroke_to_path_with_joins(&path, &style, &StrokeOptions::default(), |_| LineJoin::Miter);
+        assert!(same.contains_point(0.01, -0.9, -0.9));
+    }
+
+
+    #[test]
+    fn path_area_and_length() {
+        let mut pb = PathBuilder::new();
+        pb.rect(1., 1., 4., 3.);
+        let rect = pb.finish();
+        assert_eq!(rect.signed_area(), 12.);
+        assert_eq!(rect.length(0.1), 14.);
+        assert_eq!(rect.reverse_subpath(0).signed_area(), -12.);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(1., 2., 2., 0.);
+        // the area under a parabola is 2/3 of the area of its bounding box
+        assert!((pb.finish().signed_area() - -4. / 3.).abs() < 1e-5);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(0., 1., 1., 1., 1., 0.);
+        // integrating y dx for x = 3t² - 2t³, y = 3t(1 - t) gives 3/5
+        assert!((pb.finish().signed_area() - -0.6).abs() < 1e-6);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(1., 0., 2., 0., 3., 0.);
+        pb.quad_to(3., 2., 3., 4.);
+        assert!((pb.finish().length(0.01) - 7.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn path_remove_tiny_subpaths() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.rect(20., 20., 0.5, 0.5);
+        pb.move_to(30., 30.);
+        pb.line_to(30.5, 30.);
+        pb.move_to(40., 40.);
+        pb.line_to(45., 40.);
+        // a sliver that's long but has almost no area
+        pb.move_to(50., 50.);
+        pb.line_to(60., 50.);
+        pb.line_to(60., 50.01);
+        pb.close();
+        let path = pb.finish();
+
+        let cleaned = path.remove_tiny_subpaths(1.);
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.move_to(40., 40.);
+        pb.line_to(45., 40.);
+        assert_eq!(cleaned, pb.finish());
+        assert_eq!(path.remove_tiny_subpaths(0.), path);
+
+        // removing nothing doesn't need to measure curves
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.cubic_to(1., 2., 3., 2., 4., 0.);
+        let curve = pb.finish();
+        assert_eq!(curve.remove_tiny_subpaths(0.), curve);
+        assert_eq!(curve.remove_tiny_subpaths(-1.), curve);
+        assert_eq!(curve.remove_tiny_subpaths(1e-12), curve);
+        assert!(curve.remove_tiny_subpaths(100.).ops.is_empty());
+    }
+
+
+    #[test]
+    fn path_intersections() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        let rect = pb.finish();
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(-5., 5.);
+        pb.line_to(15., 5.);
+        let line = pb.finish();
+        let mut points = rect.intersections(&line, 0.1);
+        points.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+        assert_eq!(points, vec![Point::new(0., 5.), Point::new(10., 5.)]);
+
+        // an overlap along the top edge reports where it starts and ends
+        let mut pb = PathBuilder::new();
+        pb.move_to(5., 0.);
+        pb.line_to(20., 0.);
+        let overlap = rect.intersections(&pb.finish(), 0.1);
+        assert_eq!(overlap.len(), 2);
+        assert!(overlap.contains(&Point::new(5., 0.)));
+        assert!(overlap.contains(&Point::new(10., 0.)));
+
+        // open subpaths aren't closed
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let open = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 10.);
+        pb.line_to(10., 0.);
+        assert_eq!(open.intersections(&pb.finish(), 0.1), vec![Point::new(10., 0.)]);
+
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 10., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let circle = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.move_to(-15., 5.);
+        pb.line_to(15., 5.);
+        let hits = circle.intersections(&pb.finish(), 0.01);
+        assert_eq!(hits.len(), 2);
+        for hit in hits {
+            assert!((hit.y - 5.).abs() < 1e-4);
+            assert!((hit.x.abs() - 75f32.sqrt()).abs() < 0.05);
+        }
+    }
+
+
+    #[test]
+    fn stroke_coalesces_collinear_lines() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        for i in 1..=100 {
+            // wobble by less than the collinear tolerance
+            let y = if i % 2 == 0 { 0. } else { 0.00002 };
+            pb.line_to(i as f32 * 0.1, y);
+        }
+        pb.line_to(10., 10.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2.,
+            join: LineJoin::Bevel,
+            cap: LineCap::Butt,
+            ..Default::default()
+        };
+        let parts = stroke_to_parts(&path, &style, &StrokeOptions::default());
+        let quads = parts.body.ops.iter().filter(|op| **op == PathOp::Close).count();
+        let joins = parts.joins.ops.iter().filter(|op| **op == PathOp::Close).count();
+        assert_eq!((quads, joins), (2, 1));
+
+        // turning back isn't continuing in the same direction
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(5., 0.);
+        let parts = stroke_to_parts(&pb.finish(), &style, &StrokeOptions::default());
+        let quads = parts.body.ops.iter().filter(|op| **op == PathOp::Close).count();
+        assert_eq!(quads, 2);
+
+        // lines are compared with the merged line, by distance, so a long run that
+        // bends by half a pixel stays bent even though it only turns by 1/1000
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        for i in 1..=1000 {
+            let x = i as f32;
+            pb.line_to(x, if x <= 500. { 0. } else { (x - 500.) / 1000. });
+        }
+        let path = pb.finish();
+        let quads = |tolerance: f32| {
+            let options = StrokeOptions { tolerance, ..Default::default() };
+            let parts = stroke_to_parts(&path, &style, &options);
+            parts.body.ops.iter().filter(|op| **op == PathOp::Close).count()
+        };
+        assert_eq!(quads(0.1), 2);
+        assert!(!stroke_to_path(&path, &style).contains_point(0.01, 500., 1.1));
+        // but not when it's within the tolerance
+        assert_eq!(quads(100.), 1);
+    }
+
+
+    #[test]
+    fn path_round_corners() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        let rect = pb.finish();
+        let rounded = rect.round_corners(2.);
+        assert!(rounded.contains_point(0.01, 1., 1.));
+        assert!(!rounded.contains_point(0.01, 0.3, 0.3));
+        assert!(!rounded.contains_point(0.01, 9.7, 9.7));
+        assert!(rounded.contains_point(0.01, 5., 0.1));
+        assert!((rounded.signed_area() - (100. - (4. - std::f32::consts::PI) * 4.)).abs() < 0.1);
+        let bounds = rounded.bounds();
+        assert!((bounds.min - Point::new(0., 0.)).length() < 1e-4);
+        assert!((bounds.max - Point::new(10., 10.)).length() < 1e-4);
+
+        // a radius that's too large is reduced to fit
+        let circle = rect.round_corners(100.);
+        assert!(!circle.contains_point(0.01, 0.8, 0.8));
+        assert!((circle.signed_area() - std::f32::consts::PI * 25.).abs() < 0.5);
+
+        // the ends of open subpaths stay where they are
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let open = pb.finish().round_corners(3.);
+        assert_eq!(open.ops.first(), Some(&PathOp::MoveTo(Point::new(0., 0.))));
+        assert!(matches!(open.ops.last(), Some(PathOp::LineTo(p)) if (*p - Point::new(10., 10.)).length() < 1e-5));
+        assert_eq!(open.ops[1], PathOp::LineTo(Point::new(7., 0.)));
+    }
+
+
+    #[test]
+    fn path_distance_to() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let path = pb.finish();
+        assert_eq!(path.distance_to(Point::new(5., 3.), 0.1), 3.);
+        assert_eq!(path.distance_to(Point::new(13., 5.), 0.1), 3.);
+        assert_eq!(path.distance_to(Point::new(-3., -4.), 0.1), 5.);
+        // open subpaths aren't closed
+        assert_eq!(path.distance_to(Point::new(0., 10.), 0.1), 10.);
+
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 10., 0., 2. * std::f32::consts::PI);
+        let circle = pb.finish();
+        assert!((circle.distance_to(Point::new(0., 0.), 0.001) - 10.).abs() < 0.01);
+        assert!((circle.distance_to(Point::new(3., 4.), 0.001) - 5.).abs() < 0.01);
+
+        assert_eq!(PathBuilder::new().finish().distance_to(Point::new(0., 0.), 0.1), f32::INFINITY);
+    }
+
+
+    #[test]
+    fn path_builder_move_by() {
+        let mut pb = PathBuilder::new();
+        pb.move_by(1., 2.);
+        pb.line_to(5., 2.);
+        pb.move_by(1., 0.);
+        pb.rect(10., 10., 5., 5.);
+        pb.move_by(-2., -2.);
+        let path = pb.finish();
+        assert_eq!(path.ops[0], PathOp::MoveTo(Point::new(1., 2.)));
+        assert_eq!(path.ops[2], PathOp::MoveTo(Point::new(6., 2.)));
+        // after closing, the current point is back at the start of the subpath
+        assert_eq!(path.ops.last(), Some(&PathOp::MoveTo(Point::new(8., 8.))));
+    }
+
+
+    #[test]
+    fn round_cap_tolerance() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(1000., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            width: 2000.,
+            cap: LineCap::Round,
+            ..Default::default()
+        };
+        let max_deviation = |tolerance: f32| {
+            let options = StrokeOptions { round_tolerance: tolerance, ..Default::default() };
+            let caps = stroke_to_parts(&path, &style, &options).caps.flatten(0.001);
+            let mut max: f32 = 0.;
+            for op in &caps.ops {
+                if let PathOp::LineTo(p) | PathOp::MoveTo(p) = *op {
+                    let center = if p.x > 500. { Point::new(1000., 0.) } else { Point::new(0., 0.) };
+                    let d = (p - center).length();
+                    // skip the center of the cap
+                    if d > 500. {
+                        max = max.max((d - 1000.).abs());
+                    }
+                }
+            }
+            max
+        };
+        // two curves per cap, like before caps were adaptive
+        assert!(max_deviation(1.) > 0.2);
+        assert!(max_deviation(0.1) < 0.1);
+        assert!(max_deviation(0.01) < 0.01);
+    }
+
+
+    #[test]
+    fn path_even_odd_to_nonzero() {
+        // three nested squares all wound the same way, and one off to the side wound the other way
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 30.);
+        pb.rect(5., 5., 20., 20.);
+        pb.rect(10., 10., 10., 10.);
+        let mut path = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.rect(40., 0., 10., 10.);
+        path.ops.extend(pb.finish().reverse_subpath(0).ops);
+        path.winding = Winding::EvenOdd;
+
+        let converted = path.even_odd_to_nonzero(0.1);
+        assert_eq!(converted.winding, Winding::NonZero);
+        for &(x, y) in [(2., 15.), (7., 15.), (15., 15.), (45., 5.), (35., 5.)].iter() {
+            assert_eq!(converted.contains_point(0.01, x, y), path.contains_point(0.01, x, y));
+        }
+        assert!(!converted.contains_point(0.01, 7., 15.));
+        assert!(converted.contains_point(0.01, 15., 15.));
+        assert!(converted.contains_point(0.01, 45., 5.));
+
+        // a hole close to a curve in a glyph in em units is only found with
+        // a tolerance that's small enough for the scale of the path
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(0.5, 1., 1., 0.);
+        pb.close();
+        pb.move_to(0.2, 0.3);
+        pb.line_to(0.3, 0.2);
+        pb.line_to(0.2, 0.2);
+        pb.close();
+        let glyph = pb.finish();
+        let hole_area = |path: Path| path.subpath_ranges().into_iter().skip(1)
+            .map(|range| Path { ops: path.ops[range].to_vec(), winding: Winding::NonZero }.signed_area())
+            .sum::<f32>();
+        assert!(hole_area(glyph.even_odd_to_nonzero(0.001)) < 0.);
+        assert!(hole_area(glyph.even_odd_to_nonzero(0.1)) > 0.);
+    }
+
+
+    #[test]
+    fn miter_or_round_join() {
+        let stroke = |join, x2: f32, y2: f32| {
+            let mut pb = PathBuilder::new();
+            pb.move_to(0., 0.);
+            pb.line_to(10., 0.);
+            pb.line_to(x2, y2);
+            let style = StrokeStyle {
+                width: 2.,
+                join,
+                ..Default::default()
+            };
+            stroke_to_path(&pb.finish(), &style)
+        };
+        // a sharp turn is over the miter limit and becomes round instead of bevelled
+        let sharp = stroke(LineJoin::MiterOrRound, 0., 1.);
+        assert!(sharp.contains_point(0.01, 10.8, 0.));
+        assert!(!sharp.contains_point(0.01, 11.2, 0.));
+        assert!(!stroke(LineJoin::Miter, 0., 1.).contains_point(0.01, 10.8, 0.));
+
+        // otherwise it's a miter
+        let square = stroke(LineJoin::MiterOrRound, 10., 10.);
+        assert!(square.contains_point(0.01, 10.9, -0.9));
+    }
+
+
+    #[test]
+    fn path_turn_angles() {
+        use std::f32::consts::FRAC_PI_2;
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        // the rect turns right on screen at each corner
+        let angles = pb.finish().turn_angles();
+        assert_eq!(angles.len(), 4);
+        for a in angles {
+            assert!((a - -FRAC_PI_2).abs() < 1e-6);
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 0.);
+        // up the screen is a left turn
+        pb.line_to(10., -10.);
+        // leaves heading right and arrives heading down
+        pb.cubic_to(15., -10., 20., -10., 20., -5.);
+        pb.line_to(30., -5.);
+        let angles = pb.finish().turn_angles();
+        assert_eq!(angles.len(), 3);
+        assert!((angles[0] - FRAC_PI_2).abs() < 1e-6);
+        assert!((angles[1] - -FRAC_PI_2).abs() < 1e-6);
+        assert!((angles[2] - FRAC_PI_2).abs() < 1e-6);
+    }
+
+
+    #[test]
+    fn stroke_concentric_widths() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 10., 20., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle {
+            cap: LineCap::Round,
+            ..Default::default()
+        };
+        let options = StrokeOptions::default();
+        let outlines = stroke_concentric(&path, &style, &[2., 8., 4.], &options);
+        assert_eq!(outlines.len(), 3);
+        for (outline, &width) in outlines.iter().zip([8., 4., 2.].iter()) {
+            let style = StrokeStyle { width, ..style.clone() };
+            assert_eq!(*outline, stroke_to_path_with_options(&path, &style, &options));
+        }
+    }
+
+
+    #[test]
+    fn path_elevate_quads() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(3., 6., 6., 0.);
+        pb.line_to(6., 3.);
+        pb.close();
+        pb.quad_to(3., 3., 0., 6.);
+        let path = pb.finish();
+        let elevated = path.elevate_quads();
+        assert_eq!(
+            elevated.ops,
+            vec![
+                PathOp::MoveTo(Point::new(0., 0.)),
+                PathOp::CubicTo(Point::new(2., 4.), Point::new(4., 4.), Point::new(6., 0.)),
+                PathOp::LineTo(Point::new(6., 3.)),
+                PathOp::Close,
+                // continues from the start of the closed subpath
+                PathOp::CubicTo(Point::new(2., 2.), Point::new(2., 4.), Point::new(0., 6.)),
+            ]
+        );
+        // the curves are the same
+        assert!((elevated.bounds().max.y - path.bounds().max.y).abs() < 1e-5);
+        assert!((elevated.length(0.001) - path.length(0.001)).abs() < 1e-3);
+    }
+
+
+    #[test]
+    fn path_sample_uniform() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.move_to(20., 0.);
+        pb.line_to(20., 10.);
+        let samples = pb.finish().sample_uniform(7, 0.1);
+        let expected = [
+            ((0., 0.), (1., 0.)),
+            ((5., 0.), (1., 0.)),
+            ((10., 0.), (1., 0.)),
+            ((10., 5.), (0., 1.)),
+            ((10., 10.), (0., 1.)),
+            ((20., 5.), (0., 1.)),
+            ((20., 10.), (0., 1.)),
+        ];
+        assert_eq!(samples.len(), expected.len());
+        for (&(p, v), &((x, y), (vx, vy))) in samples.iter().zip(expected.iter()) {
+            assert!((p - Point::new(x, y)).length() < 1e-5, "{:?}", p);
+            assert_eq!(v.get(), Vector::new(vx, vy));
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 10., 0., std::f32::consts::PI);
+        let samples = pb.finish().sample_uniform(5, 0.001);
+        // a quarter of the way around a half circle is at 45 degrees
+        let h = 10. * std::f32::consts::FRAC_1_SQRT_2;
+        assert!((samples[1].0 - Point::new(h, h)).length() < 0.05);
+        assert!((samples[2].0 - Point::new(0., 10.)).length() < 0.05);
+        assert!((samples[2].1.get() - Vector::new(-1., 0.)).length() < 0.05);
+
+        assert!(PathBuilder::new().finish().sample_uniform(3, 0.1).is_empty());
+    }
+
+
+    #[test]
+    fn calligraphic_stroke() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(20., 0.);
+        pb.line_to(20., 20.);
+        pb.line_to(0., 0.);
+        let path = pb.finish();
+        // a nib at 45 degrees
+        let nib = std::f32::consts::FRAC_PI_4;
+        let stroked = stroke_calligraphic(&path, 4., nib, &StrokeOptions::default());
+        let h = 2. * std::f32::consts::FRAC_1_SQRT_2;
+        // the horizontal and vertical lines are as thick as the nib reaches across them
+        assert!(stroked.contains_point(0.01, 10., h - 0.1));
+        assert!(!stroked.contains_point(0.01, 10., h + 0.1));
+        assert!(stroked.contains_point(0.01, 20. - h + 0.1, 10.));
+        assert!(!stroked.contains_point(0.01, 20. - h - 0.1, 10.));
+        // the diagonal runs along the nib so it has no thickness
+        assert!(!stroked.contains_point(0.01, 10.2, 9.8));
+        assert!(!stroked.contains_point(0.01, 9.8, 10.2));
+        // the segments overlap without cancelling each other out
+        assert!(stroked.contains_point(0.01, 20., 0.));
+        assert!(stroked.contains_point(0.01, 0.5, 0.3));
+    }
+
+
+    #[test]
+    fn path_builder_capacity() {
+        let pb = PathBuilder::with_capacity(10);
+        assert!(pb.finish().ops.capacity() >= 10);
+
+        let mut pb = PathBuilder::with_capacity(5);
+        pb.rect(0., 0., 1., 1.);
+        pb.reserve(100);
+        let path = pb.finish();
+        assert_eq!(path.ops.len(), 5);
+        assert!(path.ops.capacity() >= 105);
+    }
+
+
+    #[test]
+    fn path_normalize() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.move_to(1., 1.);
+        pb.move_to(2., 2.);
+        pb.line_to(3., 3.);
+        pb.move_to(4., 4.);
+        pb.close();
+        pb.move_to(5., 5.);
+        pb.move_to(6., 6.);
+        let path = pb.finish();
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 2.);
+        pb.line_to(3., 3.);
+        pb.move_to(4., 4.);
+        pb.close();
+        let normalized = path.normalize();
+        assert_eq!(normalized, pb.finish());
+        assert_eq!(normalized.normalize(), normalized);
+
+        let style = StrokeStyle::default();
+        assert_eq!(stroke_to_path(&path, &style), stroke_to_path(&normalized, &style));
+    }
+
+    #[test]
+    fn stroke_with_curves() {
+        let mut pb = PathBuilder::new();
+        pb.arc(50., 50., 40., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let path = pb.finish();
+        let style = StrokeStyle { width: 10., ..Default::default() };
+        let options = StrokeOptions::default();
+
+        let stroked = stroke_to_path_with_curves(&path, &style, &options);
+        assert!(stroked.ops.iter().any(|op| matches!(op, PathOp::CubicTo(..))));
+        assert!(stroked.ops.len() * 4 < stroke_to_path_with_options(&path, &style, &options).ops.len());
+
+        // the band covers the circle out to half the width on either side
+        for &(r, inside) in &[(32., false), (36., true), (40., true), (44., true), (48., false)] {
+            for i in 0..16 {
+                let a = i as f32 * std::f32::consts::PI / 8. + 0.1;
+                let (x, y) = (50. + r * a.cos(), 50. + r * a.sin());
+                assert_eq!(stroked.contains_point(0.01, x, y), inside, "radius {} angle {}", r, a);
+            }
+        }
+
+        // paths without curves are stroked as before
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let path = pb.finish();
+        assert_eq!(
+            stroke_to_path_with_curves(&path, &style, &options).ops,
+            stroke_to_path_with_options(&path, &style, &options).ops
+        );
+    }
+
+    #[test]
+    fn path_point_on_stroke() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(50., 10.);
+        pb.line_to(50., 50.);
+        let path = pb.finish();
+        let mut style = StrokeStyle { width: 4., cap: LineCap::Butt, ..Default::default() };
+        let tol = 0.1;
+
+        assert!(path.point_on_stroke(Point::new(30., 11.9), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(30., 12.1), &style, tol));
+        assert!(path.point_on_stroke(Point::new(51., 30.), &style, tol));
+        // past the start of the path
+        assert!(!path.point_on_stroke(Point::new(9., 10.), &style, tol));
+        style.cap = LineCap::Square;
+        assert!(path.point_on_stroke(Point::new(8.5, 11.5), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(7.5, 10.), &style, tol));
+        style.cap = LineCap::Round;
+        assert!(path.point_on_stroke(Point::new(9., 11.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(8.5, 11.5), &style, tol));
+
+        // the gaps between dashes aren't part of the stroke
+        style.cap = LineCap::Butt;
+        style.dash_array = vec![10., 10.];
+        assert!(path.point_on_stroke(Point::new(15., 10.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(25., 10.), &style, tol));
+        // within half of the width of the centerline but in a gap
+        assert!(!path.point_on_stroke(Point::new(25., 11.5), &style, tol));
+        // the dash around the corner goes from 40 to 50 along the path
+        assert!(path.point_on_stroke(Point::new(50., 15.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(50., 25.), &style, tol));
+        // the offset starts the path partway into the pattern
+        style.dash_offset = 5.;
+        assert!(path.point_on_stroke(Point::new(12., 10.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(22., 10.), &style, tol));
+        assert!(path.point_on_stroke(Point::new(27., 10.), &style, tol));
+        // the caps of each dash reach into the gaps
+        style.dash_offset = 0.;
+        style.cap = LineCap::Square;
+        assert!(path.point_on_stroke(Point::new(21.5, 10.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(22.5, 10.), &style, tol));
+
+        // closed subpaths have no caps
+        let mut pb = PathBuilder::new();
+        pb.rect(10., 10., 20., 20.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 4., cap: LineCap::Square, ..Default::default() };
+        assert!(path.point_on_stroke(Point::new(9., 9.), &style, tol));
+        assert!(!path.point_on_stroke(Point::new(20., 20.), &style, tol));
+    }
+
+    #[test]
+    fn stroke_closed_rect_returning_to_start() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(2., 2.);
+        pb.line_to(12., 2.);
+        pb.line_to(12., 8.);
+        pb.line_to(2., 8.);
+        pb.line_to(2., 2.);
+        pb.close();
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2., ..Default::default() };
+
+        // `stroke_to_parts` doesn't take the fast path for rectangles
+        let parts = stroke_to_parts(&path, &style, &StrokeOptions::default());
+        assert!(parts.caps.ops.is_empty());
+        let joins = parts.joins.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(..))).count();
+        assert_eq!(joins, 4);
+        let body = parts.body.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(..))).count();
+        assert_eq!(body, 4);
+
+        // all four corners are mitered
+        let stroked = stroke_to_path_with_joins(&path, &style, &StrokeOptions::default(), |_| LineJoin::Miter);
+        for &(x, y) in &[(1.1, 1.1), (12.9, 1.1), (12.9, 8.9), (1.1, 8.9)] {
+            assert!(stroked.contains_point(0.01, x, y), "{} {}", x, y);
+        }
+        assert!(!stroked.contains_point(0.01, 7., 5.));
+    }
+
+    #[test]
+    fn path_flatten_into() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        pb.close();
+        let a = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.move_to(20., 0.);
+        pb.cubic_to(30., 0., 30., 10., 20., 10.);
+        let b = pb.finish();
+
+        let mut out = PathBuilder::new();
+        a.flatten_into(0.1, &mut out);
+        b.flatten_into(0.1, &mut out);
+        let out = out.finish();
+        let mut expected = a.flatten(0.1).ops;
+        expected.extend(b.flatten(0.1).ops);
+        assert_eq!(out.ops, expected);
+
+        // a curve at the start continues from the builder's current point
+        let mut pb = PathBuilder::new();
+        pb.quad_to(10., 0., 10., 10.);
+        let c = pb.finish();
+        let mut out = PathBuilder::new();
+        out.move_to(0., 0.);
+        c.flatten_into(0.1, &mut out);
+        let out = out.finish();
+        assert_eq!(&out.ops[1..], &a.flatten(0.1).ops[1..out.ops.len()]);
+    }
+
+    #[test]
+    fn path_dash_evenly() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 10.);
+        let path = pb.finish();
+
+        // 80 units around split into 8 dashes of 5
+        let dashed = path.dash_evenly(8, 0.5, 0.1);
+        let starts: Vec<_> = dashed.ops.iter().filter_map(|op| match *op {
+            PathOp::MoveTo(pt) => Some(pt),
+            _ => None,
+        }).collect();
+        let expected = [(0., 0.), (10., 0.), (20., 0.), (30., 0.), (30., 10.), (20., 10.), (10., 10.), (0., 10.)];
+        assert_eq!(starts.len(), expected.len());
+        for (pt, &(x, y)) in starts.iter().zip(&expected) {
+            assert!((*pt - Point::new(x, y)).length() < 1e-4, "{:?}", pt);
+        }
+        assert!((dashed.length(0.1) - 40.).abs() < 1e-3);
+        // the dash starting at (30, 0) runs down the right side
+        assert!(dashed.ops.contains(&PathOp::LineTo(Point::new(30., 5.))));
+
+        // a dash can wrap around a corner
+        let dashed = path.dash_evenly(2, 0.875, 0.1);
+        assert_eq!(dashed.ops[..4], [
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(30., 0.)),
+            PathOp::LineTo(Point::new(30., 5.)),
+            PathOp::MoveTo(Point::new(30., 10.)),
+        ]);
+
+        // every circle gets whole dashes
+        let mut pb = PathBuilder::new();
+        pb.arc(0., 0., 20., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let circle = pb.finish();
+        let dashed = circle.dash_evenly(12, 0.5, 0.01);
+        assert_eq!(dashed.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(..))).count(), 12);
+        assert!((dashed.length(0.01) - circle.length(0.01) / 2.).abs() < 0.1);
+    }
+
+    #[test]
+    fn path_closest_point() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.move_to(20., 0.);
+        pb.line_to(30., 0.);
+        let path = pb.finish();
+
+        assert_eq!(path.closest_point(Point::new(4., 3.), 0.1), Some((Point::new(4., 0.), 4.)));
+        assert_eq!(path.closest_point(Point::new(12., 6.), 0.1), Some((Point::new(10., 6.), 16.)));
+        // the gap between the subpaths isn't counted
+        assert_eq!(path.closest_point(Point::new(25., -1.), 0.1), Some((Point::new(25., 0.), 25.)));
+        assert_eq!(path.closest_point(Point::new(-5., 0.), 0.1), Some((Point::new(0., 0.), 0.)));
+        assert_eq!(PathBuilder::new().finish().closest_point(Point::new(0., 0.), 0.1), None);
+    }
+
+    #[test]
+    fn stroke_centerline() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2., ..Default::default() };
+        let options = StrokeOptions::default();
+
+        let (stroke, centerline) = stroke_with_centerline(&path, &style, &options);
+        assert_eq!(stroke.ops, stroke_to_path_with_options(&path, &style, &options).ops);
+        assert_eq!(centerline.ops, path.flatten(options.tolerance).ops);
+    }
+
+    #[test]
+    fn path_split_y_monotone() {
+        // a diamond starting at its left corner
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 5.);
+        pb.line_to(5., 0.);
+        pb.line_to(10., 5.);
+        pb.line_to(5., 10.);
+        pb.close();
+        let chains = pb.finish().split_y_monotone(0.1);
+        assert_eq!(chains, vec![
+            vec![Point::new(5., 10.), Point::new(0., 5.), Point::new(5., 0.)],
+            vec![Point::new(5., 0.), Point::new(10., 5.), Point::new(5., 10.)],
+        ]);
+
+        // horizontal edges stay with the chain before them
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        let chains = pb.finish().split_y_monotone(0.1);
+        assert_eq!(chains, vec![
+            vec![Point::new(0., 0.), Point::new(10., 0.), Point::new(10., 10.), Point::new(0., 10.)],
+            vec![Point::new(0., 10.), Point::new(0., 0.)],
+        ]);
+
+        // curves are flattened and every chain is monotone
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 0.);
+        pb.quad_to(10., 10., 0., 10.);
+        pb.quad_to(-10., 10., -10., 0.);
+        pb.quad_to(-10., -10., 0., -10.);
+        pb.quad_to(10., -10., 10., 0.);
+        pb.close();
+        let chains = pb.finish().split_y_monotone(0.1);
+        assert_eq!(chains.len(), 2);
+        for chain in &chains {
+            let up = chain.windows(2).all(|w| w[1].y <= w[0].y);
+            let down = chain.windows(2).all(|w| w[1].y >= w[0].y);
+            assert!(up || down);
+        }
+    }
+
+    #[test]
+    fn flatten_cubic_cusps_and_loops() {
+        let has_vertex = |path: &Path, pt: Point| {
+            path.ops.iter().filter(|op| match **op {
+                PathOp::LineTo(p) => (p - pt).length() < 1e-3,
+                _ => false,
+            }).count()
+        };
+
+        // a cusp at t = 0.4
+        let c = lyon_geom::CubicBezierSegment {
+            from: Point::new(0., 0.),
+            ctrl1: Point::new(40., 60.),
+            ctrl2: Point::new(-20., 60.),
+            to: Point::new(70., -75.),
+        };
+        assert!(c.derivative(0.4).length() < 1e-3);
+        let mut pb = PathBuilder::new();
+        pb.move_to(c.from.x, c.from.y);
+        pb.cubic_to(c.ctrl1.x, c.ctrl1.y, c.ctrl2.x, c.ctrl2.y, c.to.x, c.to.y);
+        assert_eq!(has_vertex(&pb.finish().flatten(0.1), c.sample(0.4)), 1);
+
+        // the point where a loop crosses itself is visited twice
+        let mut pb = PathBuilder::new();
+        pb.move_to(-10., 0.);
+        pb.cubic_to(100., 100., -100., 100., 10., 0.);
+        let flattened = pb.finish().flatten(0.1);
+        let crossing = flattened.ops.iter().filter_map(|op| match *op {
+            PathOp::LineTo(p) if p.x.abs() < 1e-3 => Some(p),
+            _ => None,
+        }).next().unwrap();
+        assert_eq!(has_vertex(&flattened, crossing), 2);
+    }
+
+    #[test]
+    fn path_with_consistent_winding() {
+        // an outer square with a hole, both wound like `rect`, and a square off to the side
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 30.);
+        pb.rect(10., 10., 10., 10.);
+        pb.rect(40., 0., 10., 10.);
+        let path = pb.finish();
+        assert!(path.contains_point(0.01, 15., 15.));
+
+        let fixed = path.with_consistent_winding(0.1);
+        let areas: Vec<f32> = fixed.subpath_ranges().into_iter()
+            .map(|range| Path { ops: fixed.ops[range].to_vec(), winding: Winding::NonZero }.signed_area())
+            .collect();
+        assert_eq!(areas, vec![900., -100., 100.]);
+        assert!(fixed.contains_point(0.01, 5., 15.));
+        assert!(!fixed.contains_point(0.01, 15., 15.));
+        assert!(fixed.contains_point(0.01, 45., 5.));
+
+        // the same orientation as `with_outer_ccw` for one outline with a hole
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 30.);
+        pb.rect(10., 10., 10., 10.);
+        let path = pb.finish();
+        let fixed = path.with_consistent_winding(0.1);
+        assert_eq!(fixed.ops, path.with_outer_ccw().ops);
+        assert_eq!(fixed.signed_area(), 800.);
+    }
+
+    #[test]
+    fn stroke_sink() {
+        struct Counter {
+            triangles: usize,
+            arcs: usize,
+        }
+        impl StrokeSink for Counter {
+            fn triangle(&mut self, _: Point, _: Point, _: Point) {
+                self.triangles += 1;
+            }
+            fn arc(&mut self, _: Point, _: f32, _: UnitVector, _: UnitVector) {
+                self.arcs += 1;
+            }
+        }
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(40., 10.);
+        pb.line_to(40., 40.);
+        pb.line_to(10., 30.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 6., cap: LineCap::Round, join: LineJoin::Miter, ..Default::default() };
+
+        let mut counter = Counter { triangles: 0, arcs: 0 };
+        stroke_to_sink(&path, &style, &StrokeOptions::default(), &mut counter);
+        assert!(counter.triangles > 0);
+        // two round caps of at least two arcs each
+        assert!(counter.arcs >= 4);
+
+        // a path builder gets the same pieces as `stroke_to_path`
+        let mut pb = PathBuilder::new();
+        stroke_to_sink(&path, &style, &StrokeOptions::default(), &mut pb);
+        assert_eq!(pb.finish(), stroke_to_path(&path, &style));
+
+        // and the triangles and sectors cover the same area as the stroke
+        struct Pieces(PathBuilder);
+        impl StrokeSink for Pieces {
+            fn triangle(&mut self, a: Point, b: Point, c: Point) {
+                self.0.triangle(a, b, c);
+            }
+            fn arc(&mut self, center: Point, radius: f32, from: UnitVector, to: UnitVector) {
+                StrokeSink::arc(&mut self.0, center, radius, from, to);
+            }
+        }
+        let mut pieces = Pieces(PathBuilder::new());
+        stroke_to_sink(&path, &style, &StrokeOptions::default(), &mut pieces);
+        let pieces = pieces.0.finish();
+        let stroked = stroke_to_path(&path, &style);
+        for y in 0..50 {
+            for x in 0..50 {
+                let (x, y) = (x as f32 + 0.3, y as f32 + 0.6);
+                assert_eq!(pieces.contains_point(0.01, x, y), stroked.contains_point(0.01, x, y), "{} {}", x, y);
+            }
+        }
+    }
+
+    #[test]
+    fn path_map_subpaths() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.rect(20., 0., 10., 10.);
+        pb.move_to(40., 0.);
+        pb.line_to(50., 0.);
+        let mut path = pb.finish();
+        path.winding = Winding::EvenOdd;
+
+        // move each subpath down by ten times its index
+        let moved = path.map_subpaths(|i, subpath| {
+            subpath.clone().transform(&Transform::translation(0., 10. * i as f32))
+        });
+        assert_eq!(moved.winding, Winding::EvenOdd);
+        assert_eq!(moved.ops.len(), path.ops.len());
+        assert_eq!(moved.ops[0], PathOp::MoveTo(Point::new(0., 0.)));
+        assert_eq!(moved.ops[5], PathOp::MoveTo(Point::new(20., 10.)));
+        assert_eq!(moved.ops[11], PathOp::LineTo(Point::new(50., 20.)));
+
+        // subpaths can be dropped
+        let first = path.map_subpaths(|i, subpath| if i == 0 { subpath.clone() } else { PathBuilder::new().finish() });
+        assert_eq!(first.ops, path.ops[..5].to_vec());
+    }
+
+    #[test]
+    fn stroke_huge_round_caps() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(1e18, 1e18);
+        pb.line_to(3e18, 1e18);
+        pb.line_to(3e18, 3e18);
+        // round joins turning every which way, whose control points can reach
+        // past the range of f32
+        for i in 0..32 {
+            let angle = i as f32 * 0.7;
+            pb.move_to(2.53e38, 2.53e38);
+            pb.line_to(2.53e38 + 1e36 * angle.cos(), 2.53e38 + 1e36 * angle.sin());
+            pb.line_to(2.53e38 + 1e36 * (angle * 2.).cos(), 2.53e38 + 1e36 * (angle * 2.).sin());
+        }
+        let path = pb.finish();
+        for &width in &[1e18, 1e30, 1.7e38] {
+            let style = StrokeStyle { width, cap: LineCap::Round, join: LineJoin::Round, ..Default::default() };
+            let stroked = stroke_to_path(&path, &style);
+            let finite = |p: Point| p.x.is_finite() && p.y.is_finite();
+            assert!(stroked.ops.iter().all(|op| match *op {
+                PathOp::MoveTo(p) | PathOp::LineTo(p) => finite(p),
+                PathOp::QuadTo(a, b) => finite(a) && finite(b),
+                PathOp::CubicTo(a, b, c) => finite(a) && finite(b) && finite(c),
+                PathOp::Close => true,
+            }), "width {}", width);
+        }
+    }
+
+    #[test]
+    fn path_to_polygons_and_polylines() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.move_to(20., 0.);
+        pb.line_to(30., 0.);
+        pb.line_to(30., 0.);
+        pb.line_to(30., 10.);
+        // a triangle that returns to its start before closing
+        pb.move_to(40., 0.);
+        pb.line_to(50., 0.);
+        pb.line_to(50., 10.);
+        pb.line_to(40., 0.);
+        pb.close();
+        // continues from the start of the triangle
+        pb.line_to(40., 20.);
+        let path = pb.finish();
+
+        assert_eq!(path.to_polygons(0.1), vec![
+            vec![Point::new(0., 0.), Point::new(10., 0.), Point::new(10., 10.), Point::new(0., 10.)],
+            vec![Point::new(40., 0.), Point::new(50., 0.), Point::new(50., 10.)],
+        ]);
+        assert_eq!(path.to_polylines(0.1), vec![
+            vec![Point::new(20., 0.), Point::new(30., 0.), Point::new(30., 10.)],
+            vec![Point::new(40., 0.), Point::new(40., 20.)],
+        ]);
+
+        // curves are flattened
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        pb.close();
+        let polygons = pb.finish().to_polygons(0.1);
+        assert_eq!(polygons.len(), 1);
+        assert!(polygons[0].len() > 3);
+    }
+
+    #[test]
+    fn stroke_with_variable_width() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(100., 0.);
+        pb.line_to(100., 100.);
+        let path = pb.finish();
+        let style = StrokeStyle { cap: LineCap::Butt, join: LineJoin::Round, ..Default::default() };
+
+        // the width grows from 2 to 10 and then shrinks back to 2
+        let stroked = stroke_variable_width(&path, &[1., 5., 1.], &style);
+        assert!(stroked.contains_point(0.01, 10., 1.3));
+        assert!(!stroked.contains_point(0.01, 10., 1.5));
+        assert!(stroked.contains_point(0.01, 90., 4.5));
+        assert!(!stroked.contains_point(0.01, 90., 4.8));
+        assert!(stroked.contains_point(0.01, 103., 50.));
+        // the round join uses the width at the corner
+        assert!(stroked.contains_point(0.01, 103., -3.));
+        assert!(!stroked.contains_point(0.01, 104., -4.));
+        // butt caps
+        assert!(!stroked.contains_point(0.01, -0.5, 0.));
+
+        // missing widths repeat the last one
+        let uniform = stroke_variable_width(&path, &[3.], &style);
+        assert!(uniform.contains_point(0.01, 50., 2.9));
+        assert!(!uniform.contains_point(0.01, 50., 3.1));
+        assert!(uniform.contains_point(0.01, 102.9, 90.));
+    }
+
+    #[test]
+    fn path_as_lines() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.close();
+        let path = pb.finish();
+        assert_eq!(path.as_lines(), Some(vec![
+            (Point::new(0., 0.), Point::new(10., 0.)),
+            (Point::new(10., 0.), Point::new(10., 10.)),
+            (Point::new(10., 10.), Point::new(0., 0.)),
+        ]));
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        let path = pb.finish();
+        assert_eq!(path.as_lines(), None);
+        assert!(path.flatten(0.1).as_lines().is_some());
+    }
+
+    #[test]
+    fn path_simplify_preserving_corners() {
+        // a square with extra points along its sides and a small wobble
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(5., 0.);
+        pb.line_to(10., 0.1);
+        pb.line_to(15., 0.);
+        pb.line_to(20., 0.);
+        pb.line_to(20., 10.);
+        pb.line_to(20., 20.);
+        pb.line_to(0., 20.);
+        pb.close();
+        let path = pb.finish();
+
+        let simplified = path.simplify_preserving_corners(0.5, 0.5);
+        assert_eq!(simplified.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(20., 0.)),
+            PathOp::LineTo(Point::new(20., 20.)),
+            PathOp::LineTo(Point::new(0., 20.)),
+            PathOp::Close,
+        ]);
+
+        // a corner within the tolerance is kept, unlike with plain simplification
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(1., 1.);
+        pb.line_to(2., 0.);
+        let path = pb.finish();
+        assert_eq!(path.simplify_preserving_corners(2., 1.).ops, path.ops);
+        assert_eq!(path.simplify_preserving_corners(2., 2.).ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(2., 0.)),
+        ]);
+
+        // curves are kept
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(5., 0.);
+        pb.quad_to(10., 0., 10., 10.);
+        pb.line_to(10., 15.);
+        pb.line_to(10., 20.);
+        let simplified = pb.finish().simplify_preserving_corners(0.1, 0.5);
+        assert_eq!(simplified.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(5., 0.)),
+            PathOp::QuadTo(Point::new(10., 0.), Point::new(10., 10.)),
+            PathOp::LineTo(Point::new(10., 20.)),
+        ]);
+    }
+
+    #[test]
+    fn stroke_indexed() {
+        let area = |vertices: &[Point], indices: &[u32]| -> f32 {
+            indices.chunks(3).map(|t| {
+                let (a, b, c) = (vertices[t[0] as usize], vertices[t[1] as usize], vertices[t[2] as usize]);
+                (b - a).cross(c - a).abs() / 2.
+            }).sum()
+        };
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        let line = pb.finish();
+        let style = StrokeStyle { width: 2., cap: LineCap::Butt, ..Default::default() };
+        let (vertices, indices) = stroke_to_indexed(&line, &style, 0.);
+        assert_eq!(indices.len() % 3, 0);
+        assert!((area(&vertices, &indices) - 20.).abs() < 1e-4);
+        // the corners are shared between the triangles
+        assert!(vertices.len() < indices.len());
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len()));
+
+        // round caps are split into triangles close to the circle
+        let style = StrokeStyle { width: 20., cap: LineCap::Round, ..Default::default() };
+        let (vertices, indices) = stroke_to_indexed(&line, &style, 0.);
+        let mut pb = PathBuilder::new();
+        for t in indices.chunks(3) {
+            let (a, b, c) = (vertices[t[0] as usize], vertices[t[1] as usize], vertices[t[2] as usize]);
+            pb.move_to(a.x, a.y);
+            pb.line_to(b.x, b.y);
+            pb.line_to(c.x, c.y);
+            pb.close();
+        }
+        let triangles = pb.finish();
+        for i in 0..32 {
+            // off the vertices so that the ray cast doesn't graze them
+            let angle = (i as f32 + 0.5) * std::f32::consts::PI / 16.;
+            let (x, y) = if angle.cos() < 0. { (0., 0.) } else { (10., 0.) };
+            assert!(triangles.contains_point(0., x + 9.8 * angle.cos(), y + 9.8 * angle.sin()));
+            assert!(!triangles.contains_point(0., x + 10.1 * angle.cos(), y + 10.1 * angle.sin()));
+        }
+
+        // merging nearby vertices gives fewer of them
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10.0001, 10.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2., ..Default::default() };
+        let exact = stroke_to_indexed(&path, &style, 0.).0.len();
+        let merged = stroke_to_indexed(&path, &style, 0.01).0.len();
+        assert!(merged <= exact);
+    }
+
+    #[test]
+    fn path_dedup_points() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10.05, 0.);
+        pb.line_to(10., 10.);
+        pb.line_to(0., 0.);
+        pb.close();
+        pb.move_to(20., 20.);
+        pb.line_to(20., 20.);
+        let path = pb.finish();
+
+        assert_eq!(path.dedup_points(0.1).ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(10., 0.)),
+            PathOp::LineTo(Point::new(10., 10.)),
+            PathOp::LineTo(Point::new(0., 0.)),
+            PathOp::Close,
+            // a subpath that doesn't go anywhere keeps a line to mark its dot
+            PathOp::MoveTo(Point::new(20., 20.)),
+            PathOp::LineTo(Point::new(20., 20.)),
+        ]);
+        // only exact duplicates with no epsilon
+        assert_eq!(path.dedup_points(0.).ops.len(), path.ops.len() - 1);
+
+        // so it's still stroked the same, even after quantizing scattered points
+        let mut pb = PathBuilder::new();
+        for &(x, y) in &[(10.2, 10.1), (30., 10.)] {
+            pb.move_to(x, y);
+            pb.line_to(x + 0.1, y);
+            pb.line_to(x, y + 0.1);
+        }
+        let dots = pb.finish().quantize(1.);
+        let style = StrokeStyle { width: 4., cap: LineCap::Round, ..Default::default() };
+        let stroked = stroke_to_path(&dots.dedup_points(0.), &style);
+        assert_eq!(stroked, stroke_to_path(&dots, &style));
+        assert!(stroked.contains_point(0.1, 11., 10.));
+        assert!(stroked.contains_point(0.1, 30., 11.));
+    }
+
+
+    #[test]
+    fn stroke_non_overlapping() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(40., 10.);
+        pb.line_to(40., 40.);
+        let path = pb.finish();
+        for &(join, cap) in &[(LineJoin::Round, LineCap::Round), (LineJoin::Miter, LineCap::Square), (LineJoin::Bevel, LineCap::Butt)] {
+            let style = StrokeStyle { width: 10., join, cap, ..Default::default() };
+            let stroked = stroke_to_path_non_overlapping(&path, &style, &StrokeOptions::default());
+
+            // fill every piece on its own at half alpha; any overlap would be drawn darker
+            let mut dt = DrawTarget::new(60, 60);
+            let src = Source::Solid(SolidSource { r: 0, g: 0, b: 0, a: 0x80 });
+            let options = DrawOptions { antialias: AntialiasMode::None, ..Default::default() };
+            let mut pieces = 0;
+            for piece in stroked.subpath_ranges() {
+                let piece = Path { ops: stroked.ops[piece].to_vec(), winding: stroked.winding };
+                dt.fill(&piece, &src, &options);
+                pieces += 1;
+            }
+            assert!(pieces > 1);
+            let body = dt.get_data()[12 * 60 + 25] >> 24;
+            assert_eq!(body, 0x80);
+            // the inside and outside of the join
+            assert_eq!(dt.get_data()[13 * 60 + 37] >> 24, body);
+            assert_eq!(dt.get_data()[8 * 60 + 42] >> 24, body);
+            // and the two halves of it, which the segments both cover
+            assert_eq!(dt.get_data()[7 * 60 + 37] >> 24, body);
+            assert_eq!(dt.get_data()[13 * 60 + 42] >> 24, body);
+        }
+    }
+
+
+    #[test]
+    fn subpath_bounds() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.move_to(20., 20.);
+        pb.quad_to(30., 40., 40., 20.);
+        pb.close();
+        let path = pb.finish();
+        let bounds = path.subpath_bounds(0).unwrap();
+        assert_eq!((bounds.min, bounds.max), (Point::new(0., 0.), Point::new(10., 10.)));
+        // tight around the curve, not its control point
+        let bounds = path.subpath_bounds(1).unwrap();
+        assert_eq!((bounds.min, bounds.max), (Point::new(20., 20.), Point::new(40., 30.)));
+        assert_eq!(path.subpath_bounds(2), None);
+        assert_eq!(Path { ops: Vec::new(), winding: Winding::NonZero }.subpath_bounds(0), None);
+    }
+
+
+    #[test]
+    fn dash_with_caps() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(100., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 10., cap: LineCap::Round, dash_array: vec![20., 20.], ..Default::default() };
+        // the first dash of an open subpath is emitted last
+        let dash_xs = |dashed: Path| -> Vec<(f32, f32)> {
+            let mut xs: Vec<_> = dashed.as_lines().unwrap().iter().map(|(p0, p1)| (p0.x, p1.x)).collect();
+            xs.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            xs
+        };
+
+        // the caps now end where the dashes used to
+        let flush = path.dash_with_caps(&style, DashCapFit::Flush, 0.1);
+        assert_eq!(dash_xs(flush), vec![(5., 15.), (45., 55.), (85., 95.)]);
+
+        let overlap = path.dash_with_caps(&style, DashCapFit::Overlap, 0.1);
+        assert_eq!(dash_xs(overlap), vec![(0., 25.), (35., 65.), (75., 100.)]);
+
+        // short dashes shrink to nothing and are left out instead of becoming dots
+        let short = StrokeStyle { dash_array: vec![5., 15.], ..style.clone() };
+        let flush = path.dash_with_caps(&short, DashCapFit::Flush, 0.1);
+        assert!(flush.ops.is_empty());
+        assert!(stroke_to_path(&flush, &short).ops.is_empty());
+
+        // the period stays the same when only some of them do
+        let mixed = StrokeStyle { dash_array: vec![6., 4., 20., 10.], ..style.clone() };
+        let flush = path.dash_with_caps(&mixed, DashCapFit::Flush, 0.1);
+        assert_eq!(dash_xs(flush), vec![(15., 25.), (55., 65.), (95., 100.)]);
+
+        let style = StrokeStyle { dash_array: vec![6., 14.], dash_offset: 5., ..style };
+
+        // butt caps don't change anything
+        let style = StrokeStyle { cap: LineCap::Butt, ..style };
+        let dashed = path.dash_with_caps(&style, DashCapFit::Overlap, 0.1);
+        assert_eq!(dash_xs(dashed), vec![(0., 1.), (15., 21.), (35., 41.), (55., 61.), (75., 81.), (95., 100.)]);
+    }
+
+
+    #[test]
+    fn join_collinear() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 0.);
+        pb.line_to(20., 0.);
+        // reversed and slightly off
+        pb.move_to(30., 0.);
+        pb.line_to(20., 0.01);
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.move_to(30., 0.);
+        pb.line_to(30., 10.);
+        pb.line_to(30., 20.);
+        // closed subpaths are left alone
+        pb.move_to(0., 0.);
+        pb.line_to(5., 5.);
+        pb.close();
+        let joined = pb.finish().join_collinear(0.1);
+        assert_eq!(joined.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(30., 0.)),
+            PathOp::LineTo(Point::new(30., 20.)),
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(5., 5.)),
+            PathOp::Close,
+        ]);
+
+        // lines that double back aren't merged
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(5., 0.);
+        let path = pb.finish();
+        assert_eq!(path.join_collinear(0.1), path);
+    }
+
+
+    #[test]
+    fn stroke_round_tolerance() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(50., 100., 100., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2000., cap: LineCap::Round, ..Default::default() };
+        let count = |tolerance, round_tolerance| {
+            let options = StrokeOptions { tolerance, round_tolerance, ..Default::default() };
+            let stroked = stroke_to_path_with_options(&path, &style, &options);
+            let lines = stroked.ops.iter().filter(|op| matches!(op, PathOp::LineTo(..))).count();
+            let curves = stroked.ops.iter().filter(|op| matches!(op, PathOp::CubicTo(..))).count();
+            (lines, curves)
+        };
+        // the flattening of the path and the caps are controlled separately
+        let (fine_lines, fine_caps) = count(0.01, 0.001);
+        let (lines, coarse_caps) = count(0.01, 10.);
+        assert_eq!(lines, fine_lines);
+        assert!(coarse_caps < fine_caps);
+        let (coarse_lines, caps) = count(1., 0.001);
+        assert!(coarse_lines < fine_lines);
+        assert_eq!(caps, fine_caps);
+    }
+
+
+    #[test]
+    fn with_outer_ccw() {
+        let mut pb = PathBuilder::new();
+        // a small hole wound like the outside, before a backwards outside
+        pb.rect(40., 40., 20., 20.);
+        pb.move_to(0., 0.);
+        pb.line_to(0., 100.);
+        pb.line_to(100., 100.);
+        pb.line_to(100., 0.);
+        pb.close();
+        let path = pb.finish();
+        assert_eq!(path.signed_area(), 400. - 10000.);
+
+        let fixed = path.with_outer_ccw();
+        assert_eq!(fixed.signed_area(), 10000. - 400.);
+        assert_eq!(fixed.subpath_bounds(1), path.subpath_bounds(1));
+        // already consistent paths don't change
+        assert_eq!(fixed.with_outer_ccw(), fixed);
+    }
+
+
+    #[test]
+    fn try_stroke_errors() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.quad_to(10., f32::NAN, 20., 0.);
+        let bad = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        let path = pb.finish();
+        let options = StrokeOptions::default();
+        let style = StrokeStyle::default();
+
+        assert_eq!(try_stroke_to_path(&path, &style, &options), Ok(stroke_to_path(&path, &style)));
+        assert_eq!(try_stroke_to_path(&bad, &style, &options), Err(StrokeError::NonFiniteCoordinate { op_index: 2 }));
+        let check = |style: StrokeStyle| try_stroke_to_path(&path, &style, &options).err();
+        assert_eq!(check(StrokeStyle { width: -1., ..Default::default() }), Some(StrokeError::InvalidWidth));
+        assert_eq!(check(StrokeStyle { width: f32::INFINITY, ..Default::default() }), Some(StrokeError::InvalidWidth));
+        assert_eq!(check(StrokeStyle { miter_limit: f32::NAN, ..Default::default() }), Some(StrokeError::InvalidMiterLimit));
+        assert_eq!(check(StrokeStyle { miter_limit: f32::INFINITY, ..Default::default() }), None);
+        assert_eq!(check(StrokeStyle { dash_array: vec![1., -1.], ..Default::default() }), Some(StrokeError::InvalidDash));
+        assert_eq!(check(StrokeStyle { dash_offset: -5., ..Default::default() }), None);
+        assert_eq!(StrokeError::NonFiniteCoordinate { op_index: 2 }.to_string(), "non-finite coordinate in op 2");
+    }
+
+
+    #[test]
+    fn extend_from_slice() {
+        let chunk = [PathOp::LineTo(Point::new(10., 0.)), PathOp::LineTo(Point::new(10., 10.))];
+        let mut pb = PathBuilder::new();
+        assert_eq!(pb.try_extend_from_slice(&chunk), Err(PathError::MissingMoveTo));
+        pb.try_extend_from_slice(&[PathOp::MoveTo(Point::new(0., 0.))]).unwrap();
+        pb.try_extend_from_slice(&chunk).unwrap();
+        assert_eq!(pb.try_extend_from_slice(&[PathOp::Close, PathOp::LineTo(Point::new(0., f32::NAN))]),
+                   Err(PathError::NonFiniteCoordinate { op_index: 1 }));
+        pb.extend_from_slice(&[PathOp::Close]);
+        // after a close the current point is the start of the subpath
+        pb.try_extend_from_slice(&chunk).unwrap();
+
+        let mut expected = PathBuilder::new();
+        expected.move_to(0., 0.);
+        expected.line_to(10., 0.);
+        expected.line_to(10., 10.);
+        expected.close();
+        expected.line_to(10., 0.);
+        expected.line_to(10., 10.);
+        assert_eq!(pb.finish(), expected.finish());
+    }
+
+
+    #[test]
+    fn smooth() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(12., 12.);
+        pb.line_to(24., 0.);
+        let path = pb.finish();
+        assert_eq!(path.smooth(0., 0.1).ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::CubicTo(Point::new(2., 2.), Point::new(8., 12.), Point::new(12., 12.)),
+            PathOp::CubicTo(Point::new(16., 12.), Point::new(22., 2.), Point::new(24., 0.)),
+        ]);
+        // full tension gives straight lines
+        let straight = path.smooth(1., 0.1);
+        assert_eq!(straight.flatten(0.1).simplify_preserving_corners(0.01, 0.1), path);
+
+        // closed subpaths wrap around, heading at each corner from its
+        // neighbours, here the other corners of the square
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 12., 12.);
+        let smoothed = pb.finish().smooth(0., 0.1);
+        assert_eq!(smoothed.ops.len(), 6);
+        assert_eq!(smoothed.ops[1], PathOp::CubicTo(Point::new(2., -2.), Point::new(10., -2.), Point::new(12., 0.)));
+        assert_eq!(smoothed.ops[4], PathOp::CubicTo(Point::new(-2., 10.), Point::new(-2., 2.), Point::new(0., 0.)));
+        assert_eq!(smoothed.ops[5], PathOp::Close);
+
+        // curves are flattened with the tolerance first
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(50., 100., 100., 0.);
+        let curve = pb.finish();
+        assert!(curve.smooth(0., 0.01).ops.len() > curve.smooth(0., 10.).ops.len());
+    }
+
+
+    #[test]
+    fn stroke_length_range() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.rect(20., 0., 10., 10.);
+        let path = pb.finish();
+
+        let mut expected = PathBuilder::new();
+        expected.move_to(5., 0.);
+        expected.line_to(10., 0.);
+        expected.line_to(10., 10.);
+        expected.rect(20., 0., 10., 10.);
+        assert_eq!(path.slice_by_length(5., 60., 0.1), expected.finish());
+
+        // the gap between subpaths doesn't count and the cut rect is open
+        let mut expected = PathBuilder::new();
+        expected.move_to(10., 5.);
+        expected.line_to(10., 10.);
+        expected.move_to(20., 0.);
+        expected.line_to(30., 0.);
+        expected.line_to(30., 5.);
+        assert_eq!(path.slice_by_length(15., 35., 0.1), expected.finish());
+
+        let style = StrokeStyle { width: 2., cap: LineCap::Square, ..Default::default() };
+        let stroked = stroke_range(&path, &style, 2., 8.);
+        let bounds = stroked.bounds();
+        assert_eq!((bounds.min, bounds.max), (Point::new(1., -1.), Point::new(9., 1.)));
+    }
+
+
+    #[test]
+    fn unit_vector() {
+        assert_eq!(UnitVector::new(Vector::zero()), None);
+        assert_eq!(UnitVector::new(Vector::new(f32::INFINITY, 0.)), None);
+        let v = UnitVector::new(Vector::new(3., 4.)).unwrap();
+        assert_eq!(v.get(), Vector::new(0.6, 0.8));
+        assert_eq!(v.perp().get(), Vector::new(-0.8, 0.6));
+        assert_eq!(v.perp().unperp(), v);
+        assert_eq!(v.flip().get(), Vector::new(-0.6, -0.8));
+        assert_eq!(v * 5., Vector::new(3., 4.));
+        assert_eq!(v.x, 0.6);
+    }
+
+
+    #[test]
+    fn contains_path() {
+        let rect = |x, y, w, h| {
+            let mut pb = PathBuilder::new();
+            pb.rect(x, y, w, h);
+            pb.finish()
+        };
+        let outer = rect(0., 0., 100., 100.);
+        assert!(outer.contains_path(&rect(10., 10., 20., 20.), 0.1));
+        assert!(!outer.contains_path(&rect(90., 10., 20., 20.), 0.1));
+        assert!(!rect(10., 10., 20., 20.).contains_path(&outer, 0.1));
+
+        // every vertex is inside but an edge pokes out
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(100., 0.);
+        pb.line_to(100., 100.);
+        pb.line_to(50., 50.);
+        pb.line_to(0., 100.);
+        pb.close();
+        let notched = pb.finish();
+        assert!(notched.contains_point(0.1, 45., 52.));
+        assert!(!notched.contains_path(&rect(10., 60., 80., 2.), 0.1));
+        assert!(notched.contains_path(&rect(45., 20., 10., 10.), 0.1));
+
+        // a hole inside `other`
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 100., 100.);
+        pb.rect(40., 40., 10., 10.);
+        let holed = Path { winding: Winding::EvenOdd, ..pb.finish() };
+        assert!(!holed.contains_path(&rect(30., 30., 30., 30.), 0.1));
+        assert!(holed.contains_path(&rect(5., 5., 30., 20.), 0.1));
+        // but nested subpaths that both fill don't make a hole
+        let nested = Path { winding: Winding::NonZero, ..holed };
+        assert!(nested.contains_path(&rect(30., 30., 30., 30.), 0.1));
+    }
+
+    #[test]
+    fn stroke_indexed_lengths() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 20.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2., cap: LineCap::Square, ..Default::default() };
+        let (vertices, lengths, indices) = stroke_to_indexed_with_lengths(&path, &style);
+        assert_eq!(vertices.len(), lengths.len());
+        assert_eq!(indices.len() % 3, 0);
+        let length_at = |pt: Point| -> Vec<f32> {
+            let mut found: Vec<f32> = vertices.iter().zip(&lengths).filter(|(v, _)| **v == pt).map(|(_, l)| *l).collect();
+            found.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            found
+        };
+        // the start cap, the quads and the end cap
+        assert_eq!(length_at(Point::new(-1., 1.)), vec![0.]);
+        assert_eq!(length_at(Point::new(0., 1.)), vec![0.]);
+        assert_eq!(length_at(Point::new(10., 1.)), vec![10.]);
+        assert_eq!(length_at(Point::new(9., 0.)), vec![10.]);
+        assert_eq!(length_at(Point::new(9., 20.)), vec![30.]);
+        assert_eq!(length_at(Point::new(11., 21.)), vec![30.]);
+        // the tip of the miter join
+        assert_eq!(length_at(Point::new(11., -1.)), vec![10.]);
+
+        // the same area as the plain indexed stroke
+        let area = |vertices: &[Point], indices: &[u32]| -> f32 {
+            indices.chunks(3).map(|t| {
+                let (a, b, c) = (vertices[t[0] as usize], vertices[t[1] as usize], vertices[t[2] as usize]);
+                (b - a).cross(c - a) / 2.
+            }).sum()
+        };
+        let (plain_vertices, plain_indices) = stroke_to_indexed(&path, &style, 0.);
+        assert!((area(&vertices, &indices) - area(&plain_vertices, &plain_indices)).abs() < 1e-3);
+
+        // subpaths that don't go anywhere are dots, a lone move isn't drawn
+        let mut pb = PathBuilder::new();
+        pb.move_to(30., 30.);
+        pb.line_to(30., 30.);
+        pb.move_to(50., 50.);
+        pb.close();
+        pb.move_to(70., 70.);
+        let path = pb.finish();
+        let (vertices, lengths, indices) = stroke_to_indexed_with_lengths(&path, &style);
+        assert!(lengths.iter().all(|l| *l == 0.));
+        assert!((area(&vertices, &indices) - 8.).abs() < 1e-3);
+        assert!(vertices.iter().all(|v| (*v - Point::new(70., 70.)).length() > 2.));
+    }
+
+    #[test]
+    fn path_fillet() {
+        let mut pb = PathBuilder::new();
+        pb.rect(20., 0., 5., 5.);
+        pb.rect(0., 0., 10., 10.);
+        let path = pb.finish();
+        let rounded = path.fillet(1, 0, 2.);
+        assert!(!rounded.contains_point(0.01, 0.3, 0.3));
+        assert!(rounded.contains_point(0.01, 9.7, 0.3));
+        assert!(rounded.contains_point(0.01, 9.7, 9.7));
+        assert!(rounded.contains_point(0.01, 0.3, 9.7));
+        assert!(rounded.contains_point(0.01, 20.1, 0.1));
+        assert!((rounded.signed_area() - (125. - (4. - std::f32::consts::PI))).abs() < 0.1);
+
+        // the radius is reduced to fit the whole of the adjacent lines
+        let quarter = path.fillet(1, 0, 100.);
+        assert!((quarter.signed_area() - (25. + std::f32::consts::PI * 25.)).abs() < 0.5);
+
+        // vertices that don't exist or aren't corners are left alone
+        assert_eq!(path.fillet(1, 4, 2.).ops, path.ops);
+        assert_eq!(path.fillet(2, 0, 2.).ops, path.ops);
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let open = pb.finish();
+        assert_eq!(open.fillet(0, 0, 2.).ops, open.ops);
+        assert_eq!(open.fillet(0, 1, 3.).ops[1], PathOp::LineTo(Point::new(7., 0.)));
+    }
+
+    #[test]
+    fn path_offset_subpaths() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        let square = pb.finish();
+        let grown = square.offset_subpaths_by(|_| 1., 0.01);
+        assert!((grown.signed_area() - (140. + std::f32::consts::PI)).abs() < 0.1);
+        let bounds = grown.bounds();
+        assert!((bounds.min - Point::new(-1., -1.)).length() < 1e-4);
+        assert!((bounds.max - Point::new(11., 11.)).length() < 1e-4);
+        let shrunk = square.offset_subpaths_by(|_| -2., 0.01);
+        assert!((shrunk.signed_area() - 36.).abs() < 1e-3);
+        // the orientation of the subpath doesn't matter
+        let reversed = square.reverse_subpath(0).offset_subpaths_by(|_| -2., 0.01);
+        assert!((reversed.signed_area() + 36.).abs() < 1e-3);
+        // shrinking past the middle leaves nothing
+        assert!(square.offset_subpaths_by(|_| -6., 0.01).ops.is_empty());
+
+        // the neck between two squares is trimmed off, splitting them apart
+        let mut pb = PathBuilder::new();
+        for &(x, y) in &[(0., 0.), (10., 0.), (10., 4.), (20., 4.), (20., 0.), (30., 0.),
+                         (30., 10.), (20., 10.), (20., 6.), (10., 6.), (10., 10.), (0., 10.)] {
+            pb.line_to(x, y);
+        }
+        pb.close();
+        let split = pb.finish().offset_subpaths_by(|_| -1.5, 0.01);
+        assert_eq!(split.subpath_ranges().len(), 2);
+        assert!(split.signed_area() > 98. && split.signed_area() < 100.);
+        assert!(split.contains_point(0.01, 5., 3.));
+        assert!(split.contains_point(0.01, 25., 3.));
+        assert!(!split.contains_point(0.01, 15., 4.7));
+
+        // outlines grow and holes shrink
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 30.);
+        pb.move_to(10., 10.);
+        pb.line_to(10., 20.);
+        pb.line_to(20., 20.);
+        pb.line_to(20., 10.);
+        pb.close();
+        pb.move_to(40., 0.);
+        pb.line_to(50., 0.);
+        let shell = pb.finish().offset_each_subpath(2., 0.01);
+        assert!(shell.contains_point(0.01, -1., 15.));
+        assert!(shell.contains_point(0.01, 11., 15.));
+        assert!(!shell.contains_point(0.01, 13., 15.));
+        assert!(shell.ops.ends_with(&[PathOp::MoveTo(Point::new(40., 0.)), PathOp::LineTo(Point::new(50., 0.))]));
+    }
+
+    #[test]
+    fn path_centerline_approx() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 100., 10.);
+        let bar = pb.finish().centerline_approx(1.);
+        match bar.ops[..] {
+            [PathOp::MoveTo(a), PathOp::LineTo(b)] => {
+                let (left, right) = if a.x < b.x { (a, b) } else { (b, a) };
+                assert!((left.x - 5.).abs() <= 1. && (right.x - 95.).abs() <= 1.);
+                assert!((left.y - 5.).abs() <= 1. && (right.y - 5.).abs() <= 1.);
+            }
+            _ => panic!("{:?}", bar.ops),
+        }
+
+        // the branches into the corners of an L are trimmed
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 60., 10.);
+        pb.rect(0., 0., 10., 60.);
+        let l = pb.finish().centerline_approx(1.);
+        assert_eq!(l.subpath_ranges().len(), 1);
+        assert!(l.distance_to(Point::new(5., 5.), 0.1) < 2.);
+        assert!(l.distance_to(Point::new(55., 5.), 0.1) < 2.);
+        assert!(l.distance_to(Point::new(5., 55.), 0.1) < 2.);
+
+        // a ring gives a loop around the middle of it
+        let mut pb = PathBuilder::new();
+        pb.arc(50., 50., 40., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        pb.arc(50., 50., 30., 0., -2. * std::f32::consts::PI);
+        pb.close();
+        let ring = pb.finish().centerline_approx(1.);
+        assert_eq!(ring.ops.last(), Some(&PathOp::Close));
+        assert_eq!(ring.subpath_ranges().len(), 1);
+        for op in &ring.ops {
+            if let PathOp::MoveTo(p) | PathOp::LineTo(p) = *op {
+                assert!(((p - Point::new(50., 50.)).length() - 35.).abs() < 2.);
+            }
+        }
+
+        assert!(Path { ops: Vec::new(), winding: Winding::NonZero }.centerline_approx(1.).ops.is_empty());
+    }
+
+    #[test]
+    fn path_clamp_to() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(-1e30, 5.);
+        pb.quad_to(f32::NAN, f32::INFINITY, 50., 50.);
+        pb.cubic_to(200., -3., 10., 10., 100., 120.);
+        pb.close();
+        let path = pb.finish();
+        let clamped = path.clamp_to(&euclid::default::Box2D::new(Point::new(0., 0.), Point::new(100., 100.)));
+        assert_eq!(clamped.ops, vec![
+            PathOp::MoveTo(Point::new(0., 5.)),
+            PathOp::QuadTo(Point::new(0., 100.), Point::new(50., 50.)),
+            PathOp::CubicTo(Point::new(100., 0.), Point::new(10., 10.), Point::new(100., 100.)),
+            PathOp::Close,
+        ]);
+        assert_eq!(clamped.winding, path.winding);
+    }
+
+    #[test]
+    fn path_segments() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(30., 0.);
+        pb.quad_to(30., 40., 0., 40.);
+        pb.close();
+        pb.move_to(50., 0.);
+        pb.line_to(60., 0.);
+        pb.close();
+        pb.line_to(50., 10.);
+        let path = pb.finish();
+        let segments: Vec<PathSegment> = path.segments().collect();
+        assert_eq!(segments, vec![
+            PathSegment::Line { from: Point::new(0., 0.), to: Point::new(30., 0.) },
+            PathSegment::Quad { from: Point::new(30., 0.), ctrl: Point::new(30., 40.), to: Point::new(0., 40.) },
+            PathSegment::Close { from: Point::new(0., 40.), to: Point::new(0., 0.) },
+            PathSegment::Line { from: Point::new(50., 0.), to: Point::new(60., 0.) },
+            PathSegment::Close { from: Point::new(60., 0.), to: Point::new(50., 0.) },
+            // continuing from where the last subpath was closed
+            PathSegment::Line { from: Point::new(50., 0.), to: Point::new(50., 10.) },
+        ]);
+        let length: f32 = segments.iter().map(|s| s.length(0.01)).sum();
+        assert!((length - path.length(0.01)).abs() < 1e-3);
+        assert_eq!(segments[2].length(0.01), 40.);
+    }
+
+    #[test]
+    fn path_quantize() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0.1, 0.3);
+        pb.line_to(0.2, 0.1);
+        pb.quad_to(1.26, -0.74, 2.4, 2.6);
+        let path = pb.finish();
+        let quantized = path.quantize(0.5);
+        assert_eq!(quantized.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.5)),
+            PathOp::LineTo(Point::new(0., 0.)),
+            PathOp::QuadTo(Point::new(1.5, -0.5), Point::new(2.5, 2.5)),
+        ]);
+        // the points that land in the same place can be removed afterwards
+        let quantized = path.quantize(1.).dedup_points(0.);
+        assert_eq!(quantized.ops, vec![
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::QuadTo(Point::new(1., -1.), Point::new(2., 3.)),
+        ]);
+        assert_eq!(path.quantize(0.).ops, path.ops);
+    }
+
+    #[test]
+    fn regular_polygon_and_star() {
+        let mut pb = PathBuilder::new();
+        pb.regular_polygon(10., 10., 5., 4, 0.);
+        let square = pb.finish();
+        assert_eq!(square.ops.len(), 5);
+        assert_eq!(square.ops[0], PathOp::MoveTo(Point::new(15., 10.)));
+        assert!(matches!(square.ops[1], PathOp::LineTo(p) if (p - Point::new(10., 15.)).length() < 1e-5));
+        assert_eq!(square.ops[4], PathOp::Close);
+        // wound like rect
+        assert!((square.signed_area() - 50.).abs() < 1e-3);
+
+        let mut pb = PathBuilder::new();
+        pb.regular_polygon(0., 0., 1., 64, 0.3);
+        assert!((pb.finish().signed_area() - std::f32::consts::PI).abs() < 0.01);
+
+        let mut pb = PathBuilder::new();
+        pb.star(0., 0., 10., 4., 5, -std::f32::consts::FRAC_PI_2);
+        let star = pb.finish();
+        assert_eq!(star.ops.len(), 11);
+        assert!(matches!(star.ops[0], PathOp::MoveTo(p) if (p - Point::new(0., -10.)).length() < 1e-5));
+        for (i, op) in star.ops[..10].iter().enumerate() {
+            if let PathOp::MoveTo(p) | PathOp::LineTo(p) = *op {
+                let expected = if i % 2 == 0 { 10. } else { 4. };
+                assert!((p.to_vector().length() - expected).abs() < 1e-4);
+            }
+        }
+        assert!(star.signed_area() > 0.);
+
+        let mut pb = PathBuilder::new();
+        pb.regular_polygon(0., 0., 1., 2, 0.);
+        pb.star(0., 0., 1., 2., 1, 0.);
+        assert!(pb.finish().ops.is_empty());
+    }
+
+    #[test]
+    fn stroke_offset_snap() {
+        let line = |angle: f32| {
+            let mut pb = PathBuilder::new();
+            pb.move_to(10., 10.);
+            pb.line_to(10. + 100. * angle.cos(), 10. + 100. * angle.sin());
+            pb.finish()
+        };
+        let style = StrokeStyle { width: 4., ..Default::default() };
+        let options = StrokeOptions { offset_snap: 0.25, ..Default::default() };
+        let first_vertex = |path: &Path| match path.ops[0] {
+            PathOp::MoveTo(p) => p,
+            _ => panic!(),
+        };
+
+        // a slight turn moves the outline without snapping but not with it
+        let (a, b) = (line(0.001), line(0.002));
+        let plain = (stroke_to_path(&a, &style), stroke_to_path(&b, &style));
+        assert_ne!(first_vertex(&plain.0), first_vertex(&plain.1));
+        let snapped = (stroke_to_path_with_options(&a, &style, &options), stroke_to_path_with_options(&b, &style, &options));
+        assert_eq!(first_vertex(&snapped.0), first_vertex(&snapped.1));
+        let offset = first_vertex(&snapped.0) - Point::new(10., 10.);
+        assert_eq!(((offset.x / 0.25).fract(), (offset.y / 0.25).fract()), (0., 0.));
+        assert_eq!(offset.length(), 2.);
+
+        // the path isn't snapped so moving it moves the stroke just as far
+        let moved = stroke_to_path_with_options(&a.transform(&Transform::translation(0.1, 0.)), &style, &options);
+        assert!((first_vertex(&moved) - first_vertex(&snapped.0) - Vector::new(0.1, 0.)).length() < 1e-5);
+
+        // axis aligned rectangles are snapped like everything else
+        let mut pb = PathBuilder::new();
+        pb.rect(10., 10., 50., 30.);
+        let rect = pb.finish();
+        let style = StrokeStyle { width: 4.2, ..Default::default() };
+        let stroked = stroke_to_path_with_options(&rect, &style, &options);
+        for op in &stroked.ops {
+            if let PathOp::MoveTo(p) | PathOp::LineTo(p) = *op {
+                assert_eq!(((p.x / 0.25).fract(), (p.y / 0.25).fract()), (0., 0.));
+            }
+        }
+        assert_ne!(stroked.bounds().min.y, 7.9);
+    }
+
+    #[test]
+    fn build_caps_and_joins() {
+        let style = StrokeStyle { width: 2., cap: LineCap::Square, join: LineJoin::Miter, ..Default::default() };
+        let along_x = UnitVector::new(Vector::new(1., 0.)).unwrap().perp();
+        let along_y = UnitVector::new(Vector::new(0., 1.)).unwrap().perp();
+
+        let mut pb = PathBuilder::new();
+        build_cap(&mut pb, &style, Point::new(10., 0.), along_x);
+        let end_cap = pb.finish();
+        assert!((end_cap.signed_area().abs() - 2.).abs() < 1e-5);
+        assert_eq!((end_cap.bounds().min, end_cap.bounds().max), (Point::new(10., -1.), Point::new(11., 1.)));
+        let mut pb = PathBuilder::new();
+        build_cap(&mut pb, &style, Point::new(0., 0.), along_x.flip());
+        let start_cap = pb.finish();
+        assert_eq!((start_cap.bounds().min, start_cap.bounds().max), (Point::new(-1., -1.), Point::new(0., 1.)));
+
+        // the miter fills the outside corner of the turn
+        let mut pb = PathBuilder::new();
+        build_join(&mut pb, &style, Point::new(10., 0.), along_x, along_y);
+        let join = pb.finish();
+        assert_eq!((join.bounds().min, join.bounds().max), (Point::new(10., -1.), Point::new(11., 0.)));
+        assert!((join.signed_area().abs() - 1.).abs() < 1e-5);
+
+        // together with the lines they make up the stroke
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        let stroked = stroke_to_path(&pb.finish(), &style);
+        assert!(stroked.contains_point(0.01, -0.5, 0.5) && start_cap.contains_point(0.01, -0.5, 0.5));
+        assert!(stroked.contains_point(0.01, 10.5, -0.5) && join.contains_point(0.01, 10.5, -0.5));
+
+        let mut pb = PathBuilder::new();
+        build_cap(&mut pb, &StrokeStyle { cap: LineCap::Butt, ..style.clone() }, Point::new(0., 0.), along_x);
+        build_join(&mut pb, &style, Point::new(10., 0.), along_x, along_x);
+        build_join(&mut pb, &StrokeStyle { join: LineJoin::Round, ..style.clone() }, Point::new(10., 0.), along_x, along_x);
+        assert!(pb.finish().ops.is_empty());
+    }
+
+    #[test]
+    fn path_reverse() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(10., -5., 20., 0.);
+        pb.cubic_to(25., 5., 25., 15., 20., 20.);
+        pb.line_to(0., 20.);
+        pb.close();
+        pb.move_to(40., 0.);
+        pb.cubic_to(41., 10., 49., -10., 50., 0.);
+        pb.line_to(55., 5.);
+        let path = pb.finish();
+        let reversed = path.reverse();
+        assert_eq!(reversed.ops, vec![
+            PathOp::MoveTo(Point::new(55., 5.)),
+            PathOp::LineTo(Point::new(50., 0.)),
+            PathOp::CubicTo(Point::new(49., -10.), Point::new(41., 10.), Point::new(40., 0.)),
+            PathOp::MoveTo(Point::new(0., 0.)),
+            PathOp::LineTo(Point::new(0., 20.)),
+            PathOp::LineTo(Point::new(20., 20.)),
+            PathOp::CubicTo(Point::new(25., 15.), Point::new(25., 5.), Point::new(20., 0.)),
+            PathOp::QuadTo(Point::new(10., -5.), Point::new(0., 0.)),
+            PathOp::Close,
+        ]);
+        assert!((reversed.signed_area() + path.signed_area()).abs() < 1e-3);
+        assert!((reversed.length(0.01) - path.length(0.01)).abs() < 1e-3);
+        assert!(reversed.reverse().approx_eq(&path, 1e-6));
+    }
+
+    #[test]
+    fn path_union() {
+        let rect = |x, y, w, h| {
+            let mut pb = PathBuilder::new();
+            pb.rect(x, y, w, h);
+            pb.finish()
+        };
+        let a = rect(0., 0., 10., 10.);
+
+        // overlapping squares give one outline around both
+        let u = a.union(&rect(5., 5., 10., 10.), 0.1);
+        assert_eq!(u.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(_))).count(), 1);
+        assert_eq!(u.ops.len(), 9);
+        assert!((u.signed_area() - 175.).abs() < 1e-3);
+        assert!(u.contains_point(0.1, 12., 12.));
+        assert!(!u.contains_point(0.1, 12., 2.));
+
+        // a shared edge disappears
+        let u = a.union(&rect(10., 0., 10., 10.), 0.1);
+        assert_eq!(u.ops.len(), 5);
+        assert!((u.signed_area() - 200.).abs() < 1e-3);
+
+        // touching at a corner keeps two outlines
+        let u = a.union(&rect(10., 10., 5., 5.), 0.1);
+        assert_eq!(u.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(_))).count(), 2);
+        assert!((u.signed_area() - 125.).abs() < 1e-3);
+
+        // crossing bars make a frame with a hole wound the other way
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 30., 10.);
+        pb.rect(0., 20., 30., 10.);
+        let bars = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 30.);
+        pb.rect(20., 0., 10., 30.);
+        let u = bars.union(&pb.finish(), 0.1);
+        assert!((u.signed_area() - 800.).abs() < 1e-3);
+        assert!(!u.contains_point(0.1, 15., 15.));
+        assert!(u.contains_point(0.1, 5., 15.));
+    }
+
+    #[test]
+    fn path_intersect_difference() {
+        let rect = |x, y, w, h| {
+            let mut pb = PathBuilder::new();
+            pb.rect(x, y, w, h);
+            pb.finish()
+        };
+        let a = rect(0., 0., 10., 10.);
+        let b = rect(5., 5., 10., 10.);
+
+        let i = a.bool_intersect(&b, 0.1);
+        assert_eq!(i.ops.len(), 5);
+        assert!((i.signed_area() - 25.).abs() < 1e-3);
+        assert!(i.contains_point(0.1, 7., 7.));
+        assert!(!i.contains_point(0.1, 2., 2.));
+
+        let d = a.bool_difference(&b, 0.1);
+        assert_eq!(d.ops.len(), 7);
+        assert!((d.signed_area() - 75.).abs() < 1e-3);
+        assert!(d.contains_point(0.1, 2., 7.));
+        assert!(!d.contains_point(0.1, 7., 7.));
+
+        // disjoint shapes
+        assert!(a.bool_intersect(&rect(20., 0., 5., 5.), 0.1).ops.is_empty());
+        assert!((a.bool_difference(&rect(20., 0., 5., 5.), 0.1).signed_area() - 100.).abs() < 1e-3);
+
+        // punching a circle out of a rect leaves a hole wound the other way
+        let mut pb = PathBuilder::new();
+        pb.arc(20., 20., 5., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let circle = pb.finish();
+        let big = rect(0., 0., 40., 40.);
+        let d = big.bool_difference(&circle, 0.1);
+        assert_eq!(d.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(_))).count(), 2);
+        assert!(!d.contains_point(0.1, 20., 20.3));
+        assert!(d.contains_point(0.1, 2., 20.3));
+        // the circle is flattened so its area comes out a little smaller
+        let circle_area = big.bool_intersect(&circle, 0.1).signed_area();
+        assert!((circle_area - circle.signed_area()).abs() < 0.05 * circle_area);
+        assert!((d.signed_area() + circle_area - 1600.).abs() < 1e-2);
+
+        // a circle straddling the edge of the rect gets cut along it
+        let mut pb = PathBuilder::new();
+        pb.arc(40., 20., 5., 0., 2. * std::f32::consts::PI);
+        pb.close();
+        let edge = pb.finish();
+        let d = big.bool_difference(&edge, 0.1);
+        assert_eq!(d.ops.iter().filter(|op| matches!(op, PathOp::MoveTo(_))).count(), 1);
+        assert!((d.signed_area() - (1600. - circle_area / 2.)).abs() < 1e-2);
+        assert!(!d.contains_point(0.1, 38., 20.3));
+    }
+
+    #[test]
+    fn path_clip_to_path() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        let clip = pb.finish();
+
+        // fills are intersected
+        let mut pb = PathBuilder::new();
+        pb.rect(5., 5., 10., 10.);
+        let clipped = pb.finish().clip_to_path(&clip, 0.1);
+        assert_eq!(clipped.ops.len(), 5);
+        assert!((clipped.signed_area() - 25.).abs() < 1e-3);
+
+        // open subpaths are cut where they cross the outline
+        let mut pb = PathBuilder::new();
+        pb.move_to(-5., 5.);
+        pb.line_to(15., 5.);
+        pb.line_to(15., 7.);
+        pb.line_to(5., 7.);
+        pb.move_to(20., 0.);
+        pb.line_to(30., 0.);
+        let clipped = pb.finish().clip_to_path(&clip, 0.1);
+        assert_eq!(clipped.ops, vec![
+            PathOp::MoveTo(Point::new(0., 5.)),
+            PathOp::LineTo(Point::new(10., 5.)),
+            PathOp::MoveTo(Point::new(10., 7.)),
+            PathOp::LineTo(Point::new(5., 7.)),
+        ]);
+
+        // a line that leaves and comes back in through a notch
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.line_to(10., 10.);
+        pb.line_to(6., 10.);
+        pb.line_to(6., 4.);
+        pb.line_to(4., 4.);
+        pb.line_to(4., 10.);
+        pb.line_to(0., 10.);
+        pb.close();
+        let notched = pb.finish();
+        let mut pb = PathBuilder::new();
+        pb.move_to(1., 7.);
+        pb.line_to(9., 7.);
+        pb.line_to(9., 2.);
+        let clipped = pb.finish().clip_to_path(&notched, 0.1);
+        assert_eq!(clipped.ops, vec![
+            PathOp::MoveTo(Point::new(1., 7.)),
+            PathOp::LineTo(Point::new(4., 7.)),
+            PathOp::MoveTo(Point::new(6., 7.)),
+            PathOp::LineTo(Point::new(9., 7.)),
+            PathOp::LineTo(Point::new(9., 2.)),
+        ]);
+
+        // both kinds together, with the fill first
+        let mut pb = PathBuilder::new();
+        pb.move_to(-5., 1.);
+        pb.line_to(5., 1.);
+        pb.rect(-5., -5., 10., 10.);
+        let clipped = pb.finish().clip_to_path(&clip, 0.1);
+        assert_eq!(clipped.ops.len(), 7);
+        assert_eq!(clipped.ops[5..], [PathOp::MoveTo(Point::new(0., 1.)), PathOp::LineTo(Point::new(5., 1.))]);
+    }
+
+    #[test]
+    fn stroke_lod() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(50., 100., 100., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 10., cap: LineCap::Round, ..Default::default() };
+
+        let zoomed_in = stroke_to_path_lod(&path, &style, 8.);
+        let zoomed_out = stroke_to_path_lod(&path, &style, 0.05);
+        assert!(zoomed_in.ops.len() > zoomed_out.ops.len());
+
+        let mut options = StrokeOptions::default();
+        options.tolerance = 0.25 / 8.;
+        options.round_tolerance = 0.25 / 8.;
+        assert_eq!(zoomed_in, stroke_to_path_with_options(&path, &style, &options));
+
+        // a scale that makes no sense falls back to the defaults
+        let default = stroke_to_path(&path, &style);
+        assert_eq!(stroke_to_path_lod(&path, &style, 0.), default);
+        assert_eq!(stroke_to_path_lod(&path, &style, f32::NAN), default);
+        assert_eq!(stroke_to_path_lod(&path, &style, f32::INFINITY), default);
+    }
+
+    #[test]
+    fn path_count_ops() {
+        assert_eq!(Path { ops: Vec::new(), winding: Winding::NonZero }.count_ops(), OpCounts::default());
+
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 10.);
+        pb.move_to(20., 0.);
+        pb.quad_to(25., 5., 30., 0.);
+        pb.cubic_to(30., 5., 35., 5., 35., 0.);
+        pb.line_to(40., 0.);
+        let counts = pb.finish().count_ops();
+        assert_eq!(counts, OpCounts { moves: 2, lines: 4, quads: 1, cubics: 1, closes: 1 });
+        assert_eq!(counts.curves(), 2);
+    }
+
+    #[test]
+    fn stroke_info() {
+        let mut pb = PathBuilder::new();
+        pb.line_to(5., 5.);
+        pb.rect(0., 0., 10., 10.);
+        pb.line_to(10., 20.);
+        pb.quad_to(20., 20., 20., 10.);
+        pb.move_to(30., 0.);
+        pb.move_to(40., 0.);
+        pb.line_to(40., 0.);
+        pb.cubic_to(40., 0., 40., 0., 40., 0.);
+        pb.move_to(50., 0.);
+        pb.quad_to(55., 5., 50., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2., ..Default::default() };
+
+        let (stroked, infos) = stroke_to_path_with_info(&path, &style, &StrokeOptions::default());
+        assert_eq!(stroked, stroke_to_path_with_options(&path, &style, &StrokeOptions::default()));
+        assert_eq!(infos, vec![
+            SubpathInfo { closed: true, segments: 3, degenerate: false },
+            SubpathInfo { closed: false, segments: 2, degenerate: false },
+            SubpathInfo { closed: false, segments: 0, degenerate: true },
+            SubpathInfo { closed: false, segments: 2, degenerate: true },
+            SubpathInfo { closed: false, segments: 1, degenerate: false },
+        ]);
+    }
+
+    #[test]
+    fn segment_eval() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(10., 0.);
+        pb.quad_to(20., 0., 20., 10.);
+        pb.cubic_to(20., 20., 0., 20., 0., 10.);
+        pb.close();
+        let segments: Vec<PathSegment> = pb.finish().segments().collect();
+
+        assert_eq!(segments[0].eval(0.25), Point::new(2.5, 0.));
+        assert_eq!(segments[0].deriv(0.25), Vector::new(10., 0.));
+
+        // B(t) = (1-t)²p0 + 2t(1-t)p1 + t²p2 and B'(t) = 2(1-t)(p1-p0) + 2t(p2-p1)
+        assert_eq!(segments[1].eval(0.), Point::new(10., 0.));
+        assert_eq!(segments[1].eval(0.5), Point::new(17.5, 2.5));
+        assert_eq!(segments[1].eval(1.), Point::new(20., 10.));
+        assert_eq!(segments[1].deriv(0.), Vector::new(20., 0.));
+        assert_eq!(segments[1].deriv(0.5), Vector::new(10., 10.));
+
+        assert_eq!(segments[2].eval(0.5), Point::new(10., 17.5));
+        assert_eq!(segments[2].deriv(0.), Vector::new(0., 30.));
+        assert_eq!(segments[2].deriv(0.5), Vector::new(-30., 0.));
+        assert_eq!(segments[2].deriv(1.), Vector::new(0., -30.));
+
+        assert_eq!(segments[3].eval(0.5), Point::new(0., 5.));
+        assert_eq!(segments[3].deriv(0.5), Vector::new(0., -10.));
+    }
+
+    #[test]
+    fn stroke_rounded_cap() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(20., 0.);
+        let path = pb.finish();
+        let stroke = |cap| stroke_to_path(&path, &StrokeStyle { width: 10., cap, ..Default::default() });
+
+        let rounded = stroke(LineCap::Rounded(2.));
+        let square = stroke(LineCap::Square);
+        // the middle of the end is as far out as a square cap
+        assert!(rounded.contains_point(0.1, 24.9, 0.1));
+        assert!(rounded.contains_point(0.1, -4.9, 0.1));
+        // the corners are cut off by a circle of radius 2 around (23, ±3)
+        for &(x, y) in &[(24.8, 4.8), (-4.8, -4.8), (24.7, -4.3)] {
+            assert!(square.contains_point(0.1, x, y));
+            assert!(!rounded.contains_point(0.1, x, y));
+        }
+        assert!(rounded.contains_point(0.1, 24.3, 4.3));
+        assert!(rounded.contains_point(0.1, 22.9, 4.9));
+        assert!(rounded.contains_point(0.1, 24.9, 2.9));
+
+        let style = StrokeStyle { width: 10., cap: LineCap::Rounded(2.), ..Default::default() };
+        for &(x, y) in &[(24.8, 4.8), (24.3, 4.3), (24.9, 0.1), (-4.8, 4.8), (-4.3, -4.3)] {
+            assert_eq!(path.point_on_stroke(Point::new(x, y), &style, 0.1), rounded.contains_point(0.1, x, y));
+        }
+
+        // the extremes of the radius give the other caps
+        let round = stroke(LineCap::Round);
+        for &(x, y) in &[(24.8, 0.1), (24.8, 4.8), (23.5, 3.5), (24., 3.1)] {
+            assert_eq!(stroke(LineCap::Rounded(5.)).contains_point(0.1, x, y), round.contains_point(0.1, x, y));
+            assert_eq!(stroke(LineCap::Rounded(10.)).contains_point(0.1, x, y), round.contains_point(0.1, x, y));
+            assert_eq!(stroke(LineCap::Rounded(0.)).contains_point(0.1, x, y), square.contains_point(0.1, x, y));
+            assert_eq!(stroke(LineCap::Rounded(f32::NAN)).contains_point(0.1, x, y), square.contains_point(0.1, x, y));
+        }
+        assert_eq!(stroke_bounds(&path, &StrokeStyle { width: 10., cap: LineCap::Rounded(2.), ..Default::default() }),
+                   stroke_bounds(&path, &StrokeStyle { width: 10., cap: LineCap::Square, ..Default::default() }));
+    }
+
+    #[test]
+    fn path_perimeter() {
+        let mut pb = PathBuilder::new();
+        pb.rect(0., 0., 10., 20.);
+        let rect = pb.finish();
+        assert_eq!(rect.perimeter(0.1), 60.);
+        assert_eq!(rect.perimeter(0.1), rect.length(0.1));
+
+        // an open triangle is measured as if it were closed
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(3., 0.);
+        pb.line_to(3., 4.);
+        pb.move_to(10., 0.);
+        pb.line_to(20., 0.);
+        let path = pb.finish();
+        assert_eq!(path.length(0.1), 17.);
+        assert_eq!(path.perimeter(0.1), 32.);
+
+        // drawing on after a close starts another contour from the same point
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(3., 0.);
+        pb.line_to(3., 4.);
+        pb.close();
+        pb.line_to(0., 4.);
+        let path = pb.finish();
+        assert_eq!(path.perimeter(0.1), 20.);
+
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.quad_to(5., 10., 10., 0.);
+        let path = pb.finish();
+        assert!((path.perimeter(0.1) - path.length(0.1) - 10.).abs() < 1e-4);
+        assert_eq!(Path { ops: Vec::new(), winding: Winding::NonZero }.perimeter(0.1), 0.);
+    }
+
+    #[test]
+    fn stroke_non_overlapping_crossing() {
+        // a figure eight whose diagonals cross at (30, 30)
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(50., 50.);
+        pb.line_to(50., 10.);
+        pb.line_to(10., 50.);
+        pb.close();
+        let path = pb.finish();
+        let src = Source::Solid(SolidSource { r: 0, g: 0, b: 0, a: 0x80 });
+        let options = DrawOptions { antialias: AntialiasMode::None, ..Default::default() };
+        let fill_pieces = |stroked: &Path| {
+            let mut dt = DrawTarget::new(60, 60);
+            for piece in stroked.subpath_ranges() {
+                dt.fill(&Path { ops: stroked.ops[piece].to_vec(), winding: stroked.winding }, &src, &options);
+            }
+            dt
+        };
+        // the crossing and around it, away from the edges that pieces share
+        let crossing = [(30, 30), (27, 30), (32, 30), (30, 27), (30, 32)];
+
+        for &join in &[LineJoin::Round, LineJoin::Miter, LineJoin::Bevel] {
+            let style = StrokeStyle { width: 8., join, ..Default::default() };
+            let dt = fill_pieces(&stroke_to_path_non_overlapping(&path, &style, &StrokeOptions::default()));
+            for &(x, y) in &crossing {
+                assert_eq!(dt.get_data()[y * 60 + x] >> 24, 0x80);
+            }
+            // the same as along the rest of the stroke
+            assert_eq!(dt.get_data()[20 * 60 + 20] >> 24, 0x80);
+
+            // while the pieces of the plain stroke overlap there
+            let dt = fill_pieces(&stroke_to_path(&path, &style));
+            assert!(dt.get_data()[30 * 60 + 30] >> 24 > 0x80);
+
+            // and the union of the stroke is an outline with the two holes of the eight
+            let outline = stroke_to_path(&path, &style).union(&Path { ops: Vec::new(), winding: Winding::NonZero }, 0.1);
+            assert_eq!(outline.count_ops().moves, 3);
+            assert!(outline.contains_point(0.1, 30., 30.));
+            assert!(!outline.contains_point(0.1, 43., 30.2));
+            assert!(!outline.contains_point(0.1, 17., 30.2));
+        }
+    }
+
+    #[test]
+    fn path_scale_to_fit() {
+        let mut pb = PathBuilder::new();
+        pb.move_to(10., 10.);
+        pb.line_to(30., 10.);
+        pb.quad_to(30., 20., 10., 20.);
+        let path = pb.finish();
+        let target = euclid::default::Box2D::new(Point::new(0., 0.), Point::new(100., 100.));
+
+        // stretched to fill the target exactly
+        let fitted = path.scale_to_fit(&target, false);
+        assert_eq!(fitted.bounds(), target);
+        assert_eq!(fitted.ops[0], PathOp::MoveTo(Point::new(0., 0.)));
+
+        // 20 by 10 scaled by 5 and centered vertically
+        let fitted = path.scale_to_fit(&target, true);
+        let bounds = fitted.bounds();
+        assert_eq!((bounds.min, bounds.max), (Point::new(0., 25.), Point::new(100., 75.)));
+        assert_eq!(fitted.ops[1], PathOp::LineTo(Point::new(100., 25.)));
+
+        // a horizontal line only scales horizontally
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 5.);
+        pb.line_to(50., 5.);
+        let line = pb.finish();
+        for &preserve_aspect in &[false, true] {
+            assert_eq!(line.scale_to_fit(&target, preserve_aspect).ops,
+                       vec![PathOp::MoveTo(Point::new(0., 50.)), PathOp::LineTo(Point::new(100., 50.))]);
+        }
+
+        let empty = Path { ops: Vec::new(), winding: Winding::NonZero };
+        assert_eq!(empty.scale_to_fit(&target, true), empty);
+    }
+
+    #[test]
+    fn stroke_arcs_inside_outline() {
+        // a U turn gives a round join of half a circle, made of two quarter circles
+        let mut pb = PathBuilder::new();
+        pb.move_to(0., 0.);
+        pb.line_to(3000., 0.);
+        pb.line_to(0., 0.);
+        let path = pb.finish();
+        let style = StrokeStyle { width: 2000., cap: LineCap::Round, join: LineJoin::Round, ..Default::default() };
+        // coarse enough for the caps to be two quarter circles as well
+        let options = StrokeOptions { round_tolerance: 100., ..Default::default() };
+        let parts = stroke_to_parts(&path, &style, &options);
+
+        // how far the curves of `part` reach outside of and inside of the circle
+        // of radius 1000 around whichever end of the line is closest, with the
+        // caps at both ends of the path around the origin
+        let deviation = |part: &Path| {
+            let (mut outside, mut inside) = (f32::MIN, f32::MIN);
+            let mut curves = 0;
+            for segment in part.segments() {
+                if let PathSegment::Cubic { .. } = segment {
+                    curves += 1;
+                    for i in 0..=100 {
+                        let p = segment.eval(i as f32 / 100.);
+                        let d = p.to_vector().length().min((p - Point::new(3000., 0.)).length());
+                        outside = outside.max(d - 1000.);
+                        inside = inside.max(1000. - d);
+                    }
+                }
+            }
+            (curves, outside, inside)
+        };
+        let (curves, outside, inside) = deviation(&parts.joins);
+        assert_eq!(curves, 2);
+        // the usual approximation would reach about 0.27 outside
+        assert!(outside < 0.01);
+        assert!(inside < 1000. * std::f32::consts::FRAC_PI_2.powi(6) / 27648.);
+        let (curves, outside, inside) = deviation(&parts.caps);
+        assert_eq!(curves, 4);
+        assert!(outside < 0.01);
+        assert!(inside < 1000. * std::f32::consts::FRAC_PI_2.powi(6) / 27648.);
+
+        // joins follow the tolerance like caps do
+        let options = StrokeOptions { round_tolerance: 0.01, ..Default::default() };
+        let parts = stroke_to_parts(&path, &style, &options);
+        let (curves, outside, inside) = deviation(&parts.joins);
+        assert!(curves > 2);
+        assert!(outside < 0.01);
+        assert!(inside < 0.01);
+    }
 }